@@ -0,0 +1,67 @@
+//! Structural reduction benchmarks on generated nets.
+//!
+//! Run with `cargo bench --bench reduction`. Every net is a ring of `length` steps moving a token
+//! around, each step doubled by a parallel place and a parallel transition, so that every rule but
+//! the implicit place one has work to do. The time per node should stay roughly flat as nets grow.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use petri_engine::analysis::Reduction;
+use petri_engine::net::{PetriNet, PetriNetBuilder};
+
+fn ring(length: usize) -> PetriNet {
+    let mut builder = PetriNetBuilder::new();
+
+    for step in 0..length {
+        let tokens = if step == 0 { 1 } else { 0 };
+        builder.place(&format!("P{}", step)).tokens(tokens);
+        builder.place(&format!("Q{}", step)).tokens(tokens);
+    }
+
+    for step in 0..length {
+        let next = (step + 1) % length;
+        for branch in ["A", "B"] {
+            let transition = format!("T{}{}", step, branch);
+            builder.transition(&transition);
+            for (from, to) in [("P", "P"), ("Q", "Q")] {
+                builder.arc(format!("{}{}", from, step), transition.as_str());
+                builder.arc(transition.as_str(), format!("{}{}", to, next));
+            }
+        }
+    }
+
+    builder.build().expect("Generated net is valid")
+}
+
+fn bench(name: &str, net: PetriNet, kept: &[usize], iterations: u32) {
+    let nodes = net.places().len() + net.transitions().len();
+
+    let mut times: Vec<Duration> = (0..iterations)
+        .map(|_| {
+            let start = Instant::now();
+            let reduction = Reduction::new(&net, kept).expect("Kept places exist");
+            let elapsed = start.elapsed();
+            black_box(reduction.net());
+            elapsed
+        })
+        .collect();
+    times.sort();
+
+    let median = times[times.len() / 2];
+    println!(
+        "{:<24} {:>7} nodes   median {:>12?}   min {:>12?}   per node {:>10?}",
+        name,
+        nodes,
+        median,
+        times[0],
+        median / nodes as u32
+    );
+}
+
+fn main() {
+    bench("ring 100", ring(100), &[0], 10);
+    bench("ring 1000", ring(1000), &[0], 5);
+    bench("ring 10000", ring(10000), &[0], 3);
+    bench("ring 10000, none kept", ring(10000), &[], 3);
+}
@@ -0,0 +1,65 @@
+//! Simulation benchmarks on generated nets.
+//!
+//! Run with `cargo bench`. Every net is made of `chains` independent chains of `length` steps.
+//! Each step is a place with two competing transitions leading to the next place, so every cycle
+//! fires one transition per chain and resolves one conflict per chain.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use petri_engine::net::{PetriNet, PetriNetBuilder, Simulation};
+
+fn chains(chains: usize, length: usize) -> PetriNet {
+    let mut builder = PetriNetBuilder::new();
+
+    for chain in 0..chains {
+        builder.place(&format!("P{}_0", chain)).tokens(1);
+
+        for step in 0..length {
+            let from = format!("P{}_{}", chain, step);
+            let to = format!("P{}_{}", chain, step + 1);
+            builder.place(&to);
+
+            for branch in ["A", "B"] {
+                let transition = format!("T{}_{}{}", chain, step, branch);
+                builder.transition(&transition);
+                builder.arc(from.as_str(), transition.as_str());
+                builder.arc(transition.as_str(), to.as_str());
+            }
+        }
+    }
+
+    builder.build().expect("Generated net is valid")
+}
+
+fn bench(name: &str, net: PetriNet, iterations: u32) {
+    let nodes = net.places().len() + net.transitions().len();
+    let net = std::sync::Arc::new(net);
+
+    let mut times: Vec<Duration> = (0..iterations)
+        .map(|_| {
+            let simul = Simulation::new(net.clone());
+            let start = Instant::now();
+            simul.run();
+            let elapsed = start.elapsed();
+            black_box(simul.marking());
+            elapsed
+        })
+        .collect();
+    times.sort();
+
+    println!(
+        "{:<24} {:>7} nodes   median {:>12?}   min {:>12?}",
+        name,
+        nodes,
+        times[times.len() / 2],
+        times[0]
+    );
+}
+
+fn main() {
+    bench("chains 10 x 10", chains(10, 10), 20);
+    bench("chains 100 x 20", chains(100, 20), 10);
+    bench("chains 1000 x 20", chains(1000, 20), 5);
+    bench("chains 5000 x 4", chains(5000, 4), 3);
+}
@@ -0,0 +1,307 @@
+//! Structural subclasses of Petri nets.
+//!
+//! Many results only hold for some classes of nets, like the theorem of Commoner and Hack for
+//! extended free-choice nets. Classes are decided from the normal connections only: inhibitor and
+//! reset connections make a net non-ordinary, and every class except pure and conservative nets
+//! requires an ordinary net.
+//!
+//! State machines and marked graphs are free-choice, free-choice nets are extended free-choice,
+//! and extended free-choice nets have asymmetric choice.
+
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+use crate::net::{ConnectionType, InputFrom, PetriNet};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NetClass {
+    /// Every transition has exactly one input and one output place
+    StateMachine,
+    /// Every place has exactly one input and one output transition
+    MarkedGraph,
+    /// A place with several output transitions is their only input place
+    FreeChoice,
+    /// Transitions sharing an input place have the same input places
+    ExtendedFreeChoice,
+    /// Places sharing an output transition have nested sets of output transitions
+    AsymmetricChoice,
+    /// Every connection is normal with weight one
+    Ordinary,
+    /// No place is both an input and an output of the same transition
+    Pure,
+    /// Every transition produces as many tokens as it consumes
+    Conservative,
+}
+
+impl NetClass {
+    pub const ALL: [NetClass; 8] = [
+        NetClass::StateMachine,
+        NetClass::MarkedGraph,
+        NetClass::FreeChoice,
+        NetClass::ExtendedFreeChoice,
+        NetClass::AsymmetricChoice,
+        NetClass::Ordinary,
+        NetClass::Pure,
+        NetClass::Conservative,
+    ];
+
+    fn requires_ordinary(&self) -> bool {
+        !matches!(
+            self,
+            NetClass::Ordinary | NetClass::Pure | NetClass::Conservative
+        )
+    }
+}
+
+impl std::fmt::Display for NetClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                NetClass::StateMachine => "state machine",
+                NetClass::MarkedGraph => "marked graph",
+                NetClass::FreeChoice => "free-choice",
+                NetClass::ExtendedFreeChoice => "extended free-choice",
+                NetClass::AsymmetricChoice => "asymmetric choice",
+                NetClass::Ordinary => "ordinary",
+                NetClass::Pure => "pure",
+                NetClass::Conservative => "conservative",
+            }
+        )
+    }
+}
+
+/// Why a net is not in a class
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ClassViolation {
+    /// Connections, by index in `PetriNet::connections`, that together break the class
+    Connections(Vec<usize>),
+    /// The transition has no input place or no output place
+    Transition(usize),
+    /// The place has no input transition or no output transition
+    Place(usize),
+}
+
+/// The classes a net belongs to, and the violations of the others
+#[derive(Clone, Debug)]
+pub struct Classification {
+    violations: HashMap<NetClass, Vec<ClassViolation>>,
+}
+
+impl Classification {
+    pub fn new(net: &PetriNet) -> Self {
+        let places = net.places().len();
+        let transitions = net.transitions().len();
+        let place_index: HashMap<&Uuid, usize> = net
+            .places()
+            .iter()
+            .enumerate()
+            .map(|(index, place)| (place.id(), index))
+            .collect();
+        let transition_index: HashMap<&Uuid, usize> = net
+            .transitions()
+            .iter()
+            .enumerate()
+            .map(|(index, transition)| (transition.id(), index))
+            .collect();
+
+        let mut violations: HashMap<NetClass, Vec<ClassViolation>> =
+            NetClass::ALL.iter().map(|class| (*class, vec![])).collect();
+        let mut violate = |class: NetClass, violation: ClassViolation| {
+            if let Some(list) = violations.get_mut(&class) {
+                if !list.contains(&violation) {
+                    list.push(violation);
+                }
+            }
+        };
+
+        // `(place, connection)` of the normal connections into and out of every transition,
+        // `(transition, connection)` of those into and out of every place
+        let mut inputs = vec![vec![]; transitions];
+        let mut outputs = vec![vec![]; transitions];
+        let mut producers = vec![vec![]; places];
+        let mut consumers = vec![vec![]; places];
+        let mut balance = vec![0; transitions];
+        for (index, connection) in net.connections().iter().enumerate() {
+            let place = place_index[connection.place().id()];
+            let transition = transition_index[connection.transition().id()];
+            let normal = connection.connection_type() == ConnectionType::NORMAL;
+            if !normal || *connection.weight() != 1 {
+                violate(NetClass::Ordinary, ClassViolation::Connections(vec![index]));
+            }
+            if !normal {
+                continue;
+            }
+
+            match connection.input_from() {
+                InputFrom::PLACE => {
+                    inputs[transition].push((place, index));
+                    consumers[place].push((transition, index));
+                    balance[transition] -= connection.weight();
+                }
+                InputFrom::TRANSITION => {
+                    outputs[transition].push((place, index));
+                    producers[place].push((transition, index));
+                    balance[transition] += connection.weight();
+                }
+            }
+        }
+
+        for transition in 0..transitions {
+            for arcs in [&inputs, &outputs] {
+                match arcs[transition].len() {
+                    0 => violate(
+                        NetClass::StateMachine,
+                        ClassViolation::Transition(transition),
+                    ),
+                    1 => {}
+                    _ => violate(
+                        NetClass::StateMachine,
+                        ClassViolation::Connections(
+                            arcs[transition].iter().map(|(_, c)| *c).collect(),
+                        ),
+                    ),
+                }
+            }
+
+            for (place, connection) in inputs[transition].iter() {
+                if let Some((_, back)) = outputs[transition].iter().find(|(p, _)| p == place) {
+                    let mut arcs = vec![*connection, *back];
+                    arcs.sort_unstable();
+                    violate(NetClass::Pure, ClassViolation::Connections(arcs));
+                }
+            }
+
+            if balance[transition] != 0 {
+                let mut arcs: Vec<usize> = inputs[transition]
+                    .iter()
+                    .chain(outputs[transition].iter())
+                    .map(|(_, c)| *c)
+                    .collect();
+                arcs.sort_unstable();
+                violate(NetClass::Conservative, ClassViolation::Connections(arcs));
+            }
+        }
+
+        for place in 0..places {
+            for arcs in [&producers, &consumers] {
+                match arcs[place].len() {
+                    0 => violate(NetClass::MarkedGraph, ClassViolation::Place(place)),
+                    1 => {}
+                    _ => violate(
+                        NetClass::MarkedGraph,
+                        ClassViolation::Connections(arcs[place].iter().map(|(_, c)| *c).collect()),
+                    ),
+                }
+            }
+
+            // A conflict on the place: its output transitions must have no other input
+            if consumers[place].len() > 1 {
+                for (transition, _) in consumers[place].iter() {
+                    for (other, other_connection) in inputs[*transition].iter() {
+                        if *other != place {
+                            let mut arcs: Vec<usize> =
+                                consumers[place].iter().map(|(_, c)| *c).collect();
+                            arcs.push(*other_connection);
+                            arcs.sort_unstable();
+                            violate(NetClass::FreeChoice, ClassViolation::Connections(arcs));
+                        }
+                    }
+                }
+            }
+
+            let preset = |transition: usize| {
+                let mut places: Vec<usize> = inputs[transition].iter().map(|(p, _)| *p).collect();
+                places.sort_unstable();
+                places
+            };
+            for (i, (t, t_arc)) in consumers[place].iter().enumerate() {
+                for (u, u_arc) in consumers[place].iter().skip(i + 1) {
+                    let (t_preset, u_preset) = (preset(*t), preset(*u));
+                    if t_preset == u_preset {
+                        continue;
+                    }
+                    // The shared place, and the input connections only one of them has
+                    let mut arcs = vec![*t_arc, *u_arc];
+                    for (transition, other_preset) in [(*t, &u_preset), (*u, &t_preset)] {
+                        arcs.extend(
+                            inputs[transition]
+                                .iter()
+                                .filter(|(p, _)| !other_preset.contains(p))
+                                .map(|(_, c)| *c),
+                        );
+                    }
+                    arcs.sort_unstable();
+                    violate(
+                        NetClass::ExtendedFreeChoice,
+                        ClassViolation::Connections(arcs),
+                    );
+                }
+            }
+        }
+
+        // Places sharing an output transition, each with an output transition the other lacks
+        let postset = |place: usize| -> Vec<usize> {
+            let mut transitions: Vec<usize> = consumers[place].iter().map(|(t, _)| *t).collect();
+            transitions.sort_unstable();
+            transitions
+        };
+        for p in 0..places {
+            for q in p + 1..places {
+                let (p_post, q_post) = (postset(p), postset(q));
+                let Some(shared) = p_post.iter().find(|t| q_post.contains(t)) else {
+                    continue;
+                };
+                let p_only = p_post.iter().find(|t| !q_post.contains(t));
+                let q_only = q_post.iter().find(|t| !p_post.contains(t));
+                if let (Some(p_only), Some(q_only)) = (p_only, q_only) {
+                    let arc = |place: usize, transition: usize| {
+                        consumers[place]
+                            .iter()
+                            .find(|(t, _)| *t == transition)
+                            .map(|(_, c)| *c)
+                    };
+                    let mut arcs: Vec<usize> = [
+                        arc(p, *shared),
+                        arc(q, *shared),
+                        arc(p, *p_only),
+                        arc(q, *q_only),
+                    ]
+                    .into_iter()
+                    .flatten()
+                    .collect();
+                    arcs.sort_unstable();
+                    violate(
+                        NetClass::AsymmetricChoice,
+                        ClassViolation::Connections(arcs),
+                    );
+                }
+            }
+        }
+
+        Self { violations }
+    }
+
+    /// Whether the net belongs to `class`
+    pub fn is(&self, class: NetClass) -> bool {
+        self.violations[&class].is_empty()
+            && (!class.requires_ordinary() || self.violations[&NetClass::Ordinary].is_empty())
+    }
+
+    /// Every class the net belongs to
+    pub fn classes(&self) -> Vec<NetClass> {
+        NetClass::ALL
+            .iter()
+            .copied()
+            .filter(|class| self.is(*class))
+            .collect()
+    }
+
+    /// What keeps the net out of `class`, not counting the violations of `NetClass::Ordinary` for
+    /// the classes requiring it
+    pub fn violations(&self, class: NetClass) -> &[ClassViolation] {
+        &self.violations[&class]
+    }
+}
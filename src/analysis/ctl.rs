@@ -0,0 +1,552 @@
+//! CTL model checking on the reachability graph.
+//!
+//! Formulas are built with the constructors of `Formula` or parsed from text:
+//!
+//! ```text
+//! AG (Buffer <= 3 & !deadlock)
+//! EF enabled(Consume)
+//! A[Idle >= 1 U Done = 1] -> EX "Worker 2" > 0
+//! ```
+//!
+//! Atomic propositions compare the tokens of a place with a number (`<`, `<=`, `=`, `!=`, `>=`,
+//! `>`), test whether a transition is `enabled(...)`, or whether the marking is a `deadlock`.
+//! Names that are not plain identifiers are written between double quotes. Operators are, by
+//! decreasing precedence: `!` and the temporal operators `EX AX EF AF EG AG E[_ U _] A[_ U _]`,
+//! then `&`, `|` and `->`.
+//!
+//! Paths are maximal: a deadlock repeats itself forever, so `EG p` holds in a deadlock where `p`
+//! holds and `AX p` holds in a deadlock exactly when `p` does.
+
+pub use super::formula::{Atom, Comparison, ParseError};
+use super::formula::{Logic, Parser, Token};
+use super::{AnalysisError, Path, StateSpace};
+use crate::net::{IndexedNet, PetriNet};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Formula {
+    True,
+    False,
+    Atom(Atom),
+    Not(Box<Formula>),
+    And(Box<Formula>, Box<Formula>),
+    Or(Box<Formula>, Box<Formula>),
+    Implies(Box<Formula>, Box<Formula>),
+    /// Holds in some successor
+    EX(Box<Formula>),
+    /// Holds in every successor
+    AX(Box<Formula>),
+    /// Holds eventually on some path
+    EF(Box<Formula>),
+    /// Holds eventually on every path
+    AF(Box<Formula>),
+    /// Holds forever on some path
+    EG(Box<Formula>),
+    /// Holds forever on every path
+    AG(Box<Formula>),
+    /// On some path the first formula holds until the second one does
+    EU(Box<Formula>, Box<Formula>),
+    /// On every path the first formula holds until the second one does
+    AU(Box<Formula>, Box<Formula>),
+}
+
+impl Formula {
+    pub fn tokens(place: usize, comparison: Comparison, value: i32) -> Self {
+        Formula::Atom(Atom::Tokens {
+            place,
+            comparison,
+            value,
+        })
+    }
+
+    pub fn enabled(transition: usize) -> Self {
+        Formula::Atom(Atom::Enabled(transition))
+    }
+
+    pub fn deadlock() -> Self {
+        Formula::Atom(Atom::Deadlock)
+    }
+
+    pub fn and(self, other: Formula) -> Self {
+        Formula::And(Box::new(self), Box::new(other))
+    }
+
+    pub fn or(self, other: Formula) -> Self {
+        Formula::Or(Box::new(self), Box::new(other))
+    }
+
+    pub fn implies(self, other: Formula) -> Self {
+        Formula::Implies(Box::new(self), Box::new(other))
+    }
+
+    pub fn ex(formula: Formula) -> Self {
+        Formula::EX(Box::new(formula))
+    }
+
+    pub fn ax(formula: Formula) -> Self {
+        Formula::AX(Box::new(formula))
+    }
+
+    pub fn ef(formula: Formula) -> Self {
+        Formula::EF(Box::new(formula))
+    }
+
+    pub fn af(formula: Formula) -> Self {
+        Formula::AF(Box::new(formula))
+    }
+
+    pub fn eg(formula: Formula) -> Self {
+        Formula::EG(Box::new(formula))
+    }
+
+    pub fn ag(formula: Formula) -> Self {
+        Formula::AG(Box::new(formula))
+    }
+
+    pub fn eu(holds: Formula, until: Formula) -> Self {
+        Formula::EU(Box::new(holds), Box::new(until))
+    }
+
+    pub fn au(holds: Formula, until: Formula) -> Self {
+        Formula::AU(Box::new(holds), Box::new(until))
+    }
+
+    /// Parses `text`, resolving place and transition names in `net`
+    pub fn parse(text: &str, net: &PetriNet) -> Result<Self, ParseError> {
+        Parser::parse(text, net, unary)
+    }
+}
+
+impl std::ops::Not for Formula {
+    type Output = Formula;
+
+    fn not(self) -> Formula {
+        Formula::Not(Box::new(self))
+    }
+}
+
+impl Logic for Formula {
+    fn constant(value: bool) -> Self {
+        if value {
+            Formula::True
+        } else {
+            Formula::False
+        }
+    }
+
+    fn atom(atom: Atom) -> Self {
+        Formula::Atom(atom)
+    }
+
+    fn negate(self) -> Self {
+        !self
+    }
+
+    fn and(self, other: Self) -> Self {
+        Formula::and(self, other)
+    }
+
+    fn or(self, other: Self) -> Self {
+        Formula::or(self, other)
+    }
+
+    fn implies(self, other: Self) -> Self {
+        Formula::implies(self, other)
+    }
+}
+
+/// Temporal operators, or whatever `Parser::primary` accepts
+fn unary(parser: &mut Parser<'_>) -> Result<Formula, ParseError> {
+    let operator: fn(Formula) -> Formula = match parser.keyword() {
+        Some("EX") => Formula::ex,
+        Some("AX") => Formula::ax,
+        Some("EF") => Formula::ef,
+        Some("AF") => Formula::af,
+        Some("EG") => Formula::eg,
+        Some("AG") => Formula::ag,
+        Some(quantifier @ ("E" | "A")) => {
+            let universal = quantifier == "A";
+            parser.advance();
+            parser.expect(Token::OpenBracket)?;
+            let holds = parser.implication(unary)?;
+            parser.expect(Token::Name("U".into()))?;
+            let until = parser.implication(unary)?;
+            parser.expect(Token::CloseBracket)?;
+
+            return Ok(if universal {
+                Formula::au(holds, until)
+            } else {
+                Formula::eu(holds, until)
+            });
+        }
+        _ => return parser.primary(unary, unary),
+    };
+
+    parser.advance();
+    Ok(operator(unary(parser)?))
+}
+
+/// Result of checking a formula in the initial marking.
+///
+/// `path` is a witness when the formula holds and a counterexample when it does not, as long as
+/// the formula has an existential meaning in that case (`EX`, `EF`, `EG`, `EU` holding, or `AX`,
+/// `AF`, `AG`, `AU` failing, possibly under negations and boolean connectives).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Verdict {
+    pub holds: bool,
+    pub path: Option<Path>,
+}
+
+/// Path through state numbers
+struct Run {
+    states: Vec<usize>,
+    firings: Vec<usize>,
+    cycle: Option<usize>,
+}
+
+impl Run {
+    fn single(state: usize) -> Self {
+        Self {
+            states: vec![state],
+            firings: vec![],
+            cycle: None,
+        }
+    }
+
+    /// Continues a finite run with `other`, which starts where this one ends
+    fn then(mut self, other: Run) -> Self {
+        let offset = self.states.len() - 1;
+        self.states.extend(other.states.into_iter().skip(1));
+        self.firings.extend(other.firings);
+        self.cycle = other.cycle.map(|cycle| cycle + offset);
+        self
+    }
+}
+
+/// Evaluates CTL formulas on the reachability graph of a net
+pub struct ModelChecker {
+    indexed: IndexedNet,
+    space: StateSpace,
+    /// `(transition, target)` of every state, deadlocks looping on themselves with no transition
+    successors: Vec<Vec<(Option<usize>, usize)>>,
+    predecessors: Vec<Vec<usize>>,
+}
+
+impl ModelChecker {
+    /// Builds the reachability graph of `net` from its initial marking, exploring at most `limit`
+    /// markings
+    pub fn new(net: &PetriNet, limit: Option<usize>) -> Result<Self, AnalysisError> {
+        let indexed = IndexedNet::new(net);
+        let space = StateSpace::explore(&indexed, net.initial_marking(), limit)?;
+
+        Ok(Self::with_state_space(indexed, space))
+    }
+
+    /// Checks formulas on an already explored state space of `indexed`
+    pub fn with_state_space(indexed: IndexedNet, space: StateSpace) -> Self {
+        let successors: Vec<Vec<(Option<usize>, usize)>> = (0..space.len())
+            .map(|state| match space.successors(state) {
+                [] => vec![(None, state)],
+                edges => edges
+                    .iter()
+                    .map(|(t, target)| (Some(*t), *target))
+                    .collect(),
+            })
+            .collect();
+
+        let mut predecessors = vec![vec![]; space.len()];
+        for (source, edges) in successors.iter().enumerate() {
+            for (_, target) in edges.iter() {
+                predecessors[*target].push(source);
+            }
+        }
+
+        Self {
+            indexed,
+            space,
+            successors,
+            predecessors,
+        }
+    }
+
+    pub fn state_space(&self) -> &StateSpace {
+        &self.space
+    }
+
+    /// Checks `formula` in the initial marking
+    pub fn check(&self, formula: &Formula) -> Verdict {
+        let holds = self.satisfying(formula)[0];
+        let path = self.explain(formula, 0, holds).map(|run| Path {
+            markings: run
+                .states
+                .iter()
+                .map(|state| self.space.marking(*state).clone())
+                .collect(),
+            firings: run.firings,
+            cycle: run.cycle,
+        });
+
+        Verdict { holds, path }
+    }
+
+    /// Whether `formula` holds, for every state of the state space
+    pub fn satisfying(&self, formula: &Formula) -> Vec<bool> {
+        let states = self.space.len();
+
+        match formula {
+            Formula::True => vec![true; states],
+            Formula::False => vec![false; states],
+            Formula::Atom(atom) => self
+                .space
+                .markings()
+                .iter()
+                .map(|marking| atom.holds(&self.indexed, marking))
+                .collect(),
+            Formula::Not(f) => self.satisfying(f).into_iter().map(|s| !s).collect(),
+            Formula::And(f, g) => zip(self.satisfying(f), self.satisfying(g), |a, b| a && b),
+            Formula::Or(f, g) => zip(self.satisfying(f), self.satisfying(g), |a, b| a || b),
+            Formula::Implies(f, g) => zip(self.satisfying(f), self.satisfying(g), |a, b| !a || b),
+            Formula::EX(f) => self.pre_exists(&self.satisfying(f)),
+            Formula::AX(f) => negate(self.pre_exists(&negate(self.satisfying(f)))),
+            Formula::EF(f) => self.exists_until(&vec![true; states], &self.satisfying(f)),
+            Formula::AF(f) => negate(self.exists_always(&negate(self.satisfying(f)))),
+            Formula::EG(f) => self.exists_always(&self.satisfying(f)),
+            Formula::AG(f) => {
+                negate(self.exists_until(&vec![true; states], &negate(self.satisfying(f))))
+            }
+            Formula::EU(f, g) => self.exists_until(&self.satisfying(f), &self.satisfying(g)),
+            Formula::AU(f, g) => {
+                // not A[f U g] = E[not g U (not f and not g)] or EG not g
+                let not_f = negate(self.satisfying(f));
+                let not_g = negate(self.satisfying(g));
+                let stuck = zip(not_f, not_g.clone(), |a, b| a && b);
+
+                negate(zip(
+                    self.exists_until(&not_g, &stuck),
+                    self.exists_always(&not_g),
+                    |a, b| a || b,
+                ))
+            }
+        }
+    }
+
+    /// States with a successor in `set`
+    fn pre_exists(&self, set: &[bool]) -> Vec<bool> {
+        self.successors
+            .iter()
+            .map(|edges| edges.iter().any(|(_, target)| set[*target]))
+            .collect()
+    }
+
+    /// States from which a path through `holds` reaches `until`
+    fn exists_until(&self, holds: &[bool], until: &[bool]) -> Vec<bool> {
+        let mut result = until.to_vec();
+        let mut pending: Vec<usize> = (0..result.len()).filter(|s| result[*s]).collect();
+
+        while let Some(state) = pending.pop() {
+            for source in self.predecessors[state].iter() {
+                if !result[*source] && holds[*source] {
+                    result[*source] = true;
+                    pending.push(*source);
+                }
+            }
+        }
+
+        result
+    }
+
+    /// States from which an infinite path stays in `holds`
+    fn exists_always(&self, holds: &[bool]) -> Vec<bool> {
+        let mut result = holds.to_vec();
+        let mut count: Vec<usize> = self
+            .successors
+            .iter()
+            .map(|edges| edges.iter().filter(|(_, t)| result[*t]).count())
+            .collect();
+        let mut pending: Vec<usize> = (0..result.len())
+            .filter(|s| result[*s] && count[*s] == 0)
+            .collect();
+        for state in pending.iter() {
+            result[*state] = false;
+        }
+
+        while let Some(state) = pending.pop() {
+            for source in self.predecessors[state].iter() {
+                if result[*source] {
+                    // Predecessors are listed once per edge, like the successors were counted
+                    count[*source] -= 1;
+                    if count[*source] == 0 {
+                        result[*source] = false;
+                        pending.push(*source);
+                    }
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Path from `state` showing that `formula` holds, or that it fails when `holds` is false
+    fn explain(&self, formula: &Formula, state: usize, holds: bool) -> Option<Run> {
+        match (formula, holds) {
+            (Formula::Not(f), _) => self.explain(f, state, !holds),
+            (Formula::And(f, g), true) | (Formula::Or(f, g), false) => self
+                .explain(f, state, holds)
+                .or_else(|| self.explain(g, state, holds)),
+            (Formula::And(f, g), false) | (Formula::Or(f, g), true) => {
+                // Explain the operand that decides the result
+                if self.satisfying(f)[state] == holds {
+                    self.explain(f, state, holds)
+                } else {
+                    self.explain(g, state, holds)
+                }
+            }
+            (Formula::Implies(f, g), _) => {
+                let equivalent = Formula::Or(Box::new(!(**f).clone()), g.clone());
+                self.explain(&equivalent, state, holds)
+            }
+            (Formula::EX(f), true) => self.explain_next(f, state, true),
+            (Formula::AX(f), false) => self.explain_next(f, state, false),
+            (Formula::EF(f), true) => {
+                self.explain_until(&vec![true; self.space.len()], f, state, true)
+            }
+            (Formula::AG(f), false) => {
+                self.explain_until(&vec![true; self.space.len()], f, state, false)
+            }
+            (Formula::EU(f, g), true) => self.explain_until(&self.satisfying(f), g, state, true),
+            (Formula::EG(f), true) => Some(self.explain_always(&self.satisfying(f), state)),
+            (Formula::AF(f), false) => {
+                Some(self.explain_always(&negate(self.satisfying(f)), state))
+            }
+            (Formula::AU(f, g), false) => {
+                let not_g = negate(self.satisfying(g));
+                let stuck = Formula::And(Box::new(!(**f).clone()), Box::new(!(**g).clone()));
+
+                if self.exists_until(&not_g, &self.satisfying(&stuck))[state] {
+                    self.explain_until(&not_g, &stuck, state, true)
+                } else {
+                    Some(self.explain_always(&not_g, state))
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// One step to a successor where `formula` holds, or fails when `holds` is false
+    fn explain_next(&self, formula: &Formula, state: usize, holds: bool) -> Option<Run> {
+        let set = self.satisfying(formula);
+        let (transition, target) = self.successors[state]
+            .iter()
+            .find(|(_, target)| set[*target] == holds)?;
+
+        let rest = self
+            .explain(formula, *target, holds)
+            .unwrap_or_else(|| Run::single(*target));
+
+        // A deadlock is its own successor, without firing anything
+        Some(match transition {
+            Some(transition) => Run {
+                states: vec![state, *target],
+                firings: vec![*transition],
+                cycle: None,
+            }
+            .then(rest),
+            None => rest,
+        })
+    }
+
+    /// Shortest path through `through` to a state where `formula` holds, or fails when `holds`
+    /// is false
+    fn explain_until(
+        &self,
+        through: &[bool],
+        formula: &Formula,
+        state: usize,
+        holds: bool,
+    ) -> Option<Run> {
+        let target = self.satisfying(formula);
+        let mut parents: Vec<Option<(usize, usize)>> = vec![None; self.space.len()];
+        let mut visited = vec![false; self.space.len()];
+        let mut pending = std::collections::VecDeque::from([state]);
+        visited[state] = true;
+
+        while let Some(current) = pending.pop_front() {
+            if target[current] == holds {
+                let mut states = vec![current];
+                let mut firings = vec![];
+                let mut walk = current;
+                while let Some((parent, transition)) = parents[walk] {
+                    states.push(parent);
+                    firings.push(transition);
+                    walk = parent;
+                }
+                states.reverse();
+                firings.reverse();
+
+                let prefix = Run {
+                    states,
+                    firings,
+                    cycle: None,
+                };
+                let rest = self
+                    .explain(formula, current, holds)
+                    .unwrap_or_else(|| Run::single(current));
+                return Some(prefix.then(rest));
+            }
+
+            if !through[current] {
+                continue;
+            }
+            for (transition, next) in self.space.successors(current).iter() {
+                if !visited[*next] {
+                    visited[*next] = true;
+                    parents[*next] = Some((current, *transition));
+                    pending.push_back(*next);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Infinite path staying in `set`, from a state where `EG set` holds
+    fn explain_always(&self, set: &[bool], state: usize) -> Run {
+        let always = self.exists_always(set);
+        let mut run = Run::single(state);
+        let mut position = vec![None; self.space.len()];
+        position[state] = Some(0);
+        let mut current = state;
+
+        loop {
+            let (transition, next) = *self.successors[current]
+                .iter()
+                .find(|(_, next)| always[*next])
+                .expect("States satisfying EG have a successor satisfying it");
+
+            let Some(transition) = transition else {
+                // Deadlock: the path ends here and stutters forever
+                return run;
+            };
+
+            run.firings.push(transition);
+            match position[next] {
+                Some(index) => {
+                    run.cycle = Some(index);
+                    return run;
+                }
+                None => {
+                    position[next] = Some(run.states.len());
+                    run.states.push(next);
+                    current = next;
+                }
+            }
+        }
+    }
+}
+
+fn negate(set: Vec<bool>) -> Vec<bool> {
+    set.into_iter().map(|s| !s).collect()
+}
+
+fn zip(a: Vec<bool>, b: Vec<bool>, f: impl Fn(bool, bool) -> bool) -> Vec<bool> {
+    a.into_iter().zip(b).map(|(a, b)| f(a, b)).collect()
+}
@@ -0,0 +1,364 @@
+//! Atomic propositions and the textual syntax shared by the CTL and LTL formulas.
+//!
+//! Both languages have the same atoms, constants and boolean connectives. They only differ by
+//! their temporal operators, which each language parses in its own `unary` function before
+//! handing over to `Parser::primary`.
+
+use crate::net::{IndexedNet, Marking, PetriNet};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Comparison {
+    Less,
+    LessEqual,
+    Equal,
+    NotEqual,
+    GreaterEqual,
+    Greater,
+}
+
+impl Comparison {
+    pub(super) fn holds(&self, left: i32, right: i32) -> bool {
+        match self {
+            Comparison::Less => left < right,
+            Comparison::LessEqual => left <= right,
+            Comparison::Equal => left == right,
+            Comparison::NotEqual => left != right,
+            Comparison::GreaterEqual => left >= right,
+            Comparison::Greater => left > right,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Atom {
+    /// Compares the tokens of a place with a number
+    Tokens {
+        place: usize,
+        comparison: Comparison,
+        value: i32,
+    },
+    /// The transition is enabled
+    Enabled(usize),
+    /// No transition is enabled
+    Deadlock,
+}
+
+impl Atom {
+    /// Whether the proposition holds in `marking`
+    pub fn holds(&self, net: &IndexedNet, marking: &Marking) -> bool {
+        match self {
+            Atom::Tokens {
+                place,
+                comparison,
+                value,
+            } => comparison.holds(marking[*place], *value),
+            Atom::Enabled(transition) => net.is_enabled(*transition, marking),
+            Atom::Deadlock => net.enabled(marking).is_empty(),
+        }
+    }
+}
+
+/// Error raised when parsing a formula, with the byte offset where it happened
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    pub offset: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    pub(super) fn new(offset: usize, message: String) -> Self {
+        Self { offset, message }
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at offset {}", self.message, self.offset)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Constructors the parser needs from a formula type
+pub(super) trait Logic: Sized {
+    fn constant(value: bool) -> Self;
+    fn atom(atom: Atom) -> Self;
+    fn negate(self) -> Self;
+    fn and(self, other: Self) -> Self;
+    fn or(self, other: Self) -> Self;
+    fn implies(self, other: Self) -> Self;
+}
+
+/// Parses the tightest binding level of a formula
+pub(super) type Level<F> = fn(&mut Parser<'_>) -> Result<F, ParseError>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub(super) enum Token {
+    Name(String),
+    /// A name written between quotes, never a keyword
+    Quoted(String),
+    Number(i32),
+    Comparison(Comparison),
+    Not,
+    And,
+    Or,
+    Implies,
+    Open,
+    Close,
+    OpenBracket,
+    CloseBracket,
+}
+
+fn tokenize(text: &str) -> Result<Vec<(usize, Token)>, ParseError> {
+    let mut tokens = vec![];
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((offset, c)) = chars.next() {
+        let mut next_is = |expected: char| chars.next_if(|(_, next)| *next == expected).is_some();
+
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '[' => Token::OpenBracket,
+            ']' => Token::CloseBracket,
+            '&' => Token::And,
+            '|' => Token::Or,
+            '-' if next_is('>') => Token::Implies,
+            '!' if next_is('=') => Token::Comparison(Comparison::NotEqual),
+            '!' => Token::Not,
+            '=' => {
+                next_is('=');
+                Token::Comparison(Comparison::Equal)
+            }
+            '<' if next_is('=') => Token::Comparison(Comparison::LessEqual),
+            '<' => Token::Comparison(Comparison::Less),
+            '>' if next_is('=') => Token::Comparison(Comparison::GreaterEqual),
+            '>' => Token::Comparison(Comparison::Greater),
+            '"' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, c)) => name.push(c),
+                        None => return Err(ParseError::new(offset, "unterminated name".into())),
+                    }
+                }
+                Token::Quoted(name)
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut word = c.to_string();
+                while let Some((_, c)) =
+                    chars.next_if(|(_, c)| c.is_alphanumeric() || *c == '_' || *c == '\'')
+                {
+                    word.push(c);
+                }
+
+                match word.parse() {
+                    Ok(number) => Token::Number(number),
+                    Err(_) => Token::Name(word),
+                }
+            }
+            c => return Err(ParseError::new(offset, format!("unexpected '{}'", c))),
+        };
+
+        tokens.push((offset, token));
+    }
+
+    Ok(tokens)
+}
+
+pub(super) struct Parser<'a> {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+    net: &'a PetriNet,
+}
+
+impl<'a> Parser<'a> {
+    /// Parses the whole of `text`, `operand` being the level binding tighter than `&`
+    pub(super) fn parse<F: Logic>(
+        text: &str,
+        net: &'a PetriNet,
+        operand: Level<F>,
+    ) -> Result<F, ParseError> {
+        let mut parser = Parser {
+            tokens: tokenize(text)?,
+            position: 0,
+            net,
+        };
+        let formula = parser.implication(operand)?;
+
+        match parser.tokens.get(parser.position) {
+            None => Ok(formula),
+            Some((offset, token)) => Err(ParseError::new(
+                *offset,
+                format!("unexpected {:?} after formula", token),
+            )),
+        }
+    }
+
+    pub(super) fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(_, token)| token)
+    }
+
+    pub(super) fn offset(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .map(|(offset, _)| *offset)
+            .unwrap_or_else(|| {
+                self.tokens
+                    .last()
+                    .map(|(offset, _)| offset + 1)
+                    .unwrap_or(0)
+            })
+    }
+
+    pub(super) fn advance(&mut self) -> Option<Token> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .map(|(_, token)| token.clone());
+        self.position += 1;
+        token
+    }
+
+    pub(super) fn expect(&mut self, expected: Token) -> Result<(), ParseError> {
+        let offset = self.offset();
+        match self.advance() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(ParseError::new(
+                offset,
+                format!("expected {:?}, found {:?}", expected, token),
+            )),
+            None => Err(ParseError::new(
+                offset,
+                format!("expected {:?}, found end of formula", expected),
+            )),
+        }
+    }
+
+    /// The next token if it is an unquoted name that is not compared to a number, which makes it
+    /// a keyword rather than a place
+    pub(super) fn keyword(&self) -> Option<&str> {
+        let comparison_follows = matches!(
+            self.tokens.get(self.position + 1),
+            Some((_, Token::Comparison(_)))
+        );
+
+        match self.peek() {
+            Some(Token::Name(name)) if !comparison_follows => Some(name),
+            _ => None,
+        }
+    }
+
+    pub(super) fn implication<F: Logic>(&mut self, operand: Level<F>) -> Result<F, ParseError> {
+        let left = self.disjunction(operand)?;
+        if self.peek() == Some(&Token::Implies) {
+            self.advance();
+            return Ok(left.implies(self.implication(operand)?));
+        }
+
+        Ok(left)
+    }
+
+    fn disjunction<F: Logic>(&mut self, operand: Level<F>) -> Result<F, ParseError> {
+        let mut left = self.conjunction(operand)?;
+        while self.peek() == Some(&Token::Or) {
+            self.advance();
+            left = left.or(self.conjunction(operand)?);
+        }
+
+        Ok(left)
+    }
+
+    fn conjunction<F: Logic>(&mut self, operand: Level<F>) -> Result<F, ParseError> {
+        let mut left = operand(self)?;
+        while self.peek() == Some(&Token::And) {
+            self.advance();
+            left = left.and(operand(self)?);
+        }
+
+        Ok(left)
+    }
+
+    /// Negations, parentheses, constants and atoms. `unary` parses the operand of a negation and
+    /// `operand` the level binding tighter than `&`, used inside parentheses.
+    pub(super) fn primary<F: Logic>(
+        &mut self,
+        unary: Level<F>,
+        operand: Level<F>,
+    ) -> Result<F, ParseError> {
+        let offset = self.offset();
+        let keyword = self.keyword().map(|k| k.to_string());
+
+        match (self.advance(), keyword.as_deref()) {
+            (Some(Token::Not), _) => Ok(unary(self)?.negate()),
+            (Some(Token::Open), _) => {
+                let formula = self.implication(operand)?;
+                self.expect(Token::Close)?;
+                Ok(formula)
+            }
+            (_, Some("true")) => Ok(F::constant(true)),
+            (_, Some("false")) => Ok(F::constant(false)),
+            (_, Some("deadlock")) => Ok(F::atom(Atom::Deadlock)),
+            (_, Some("enabled")) => {
+                self.expect(Token::Open)?;
+                let offset = self.offset();
+                let name = self.name()?;
+                let transition = self
+                    .net
+                    .transitions()
+                    .iter()
+                    .position(|t| t.name() == name)
+                    .ok_or_else(|| {
+                        ParseError::new(offset, format!("unknown transition {}", name))
+                    })?;
+                self.expect(Token::Close)?;
+                Ok(F::atom(Atom::Enabled(transition)))
+            }
+            (_, Some(name)) => Err(ParseError::new(
+                offset,
+                format!("expected a comparison after {}", name),
+            )),
+            (Some(Token::Name(name)), _) | (Some(Token::Quoted(name)), _) => {
+                let place = self
+                    .net
+                    .places()
+                    .iter()
+                    .position(|p| p.name() == name)
+                    .ok_or_else(|| ParseError::new(offset, format!("unknown place {}", name)))?;
+
+                let offset = self.offset();
+                let comparison = match self.advance() {
+                    Some(Token::Comparison(comparison)) => comparison,
+                    _ => {
+                        return Err(ParseError::new(
+                            offset,
+                            format!("expected a comparison after {}", name),
+                        ))
+                    }
+                };
+
+                let offset = self.offset();
+                match self.advance() {
+                    Some(Token::Number(value)) => Ok(F::atom(Atom::Tokens {
+                        place,
+                        comparison,
+                        value,
+                    })),
+                    _ => Err(ParseError::new(offset, "expected a number".into())),
+                }
+            }
+            (Some(token), _) => Err(ParseError::new(offset, format!("unexpected {:?}", token))),
+            (None, _) => Err(ParseError::new(offset, "unexpected end of formula".into())),
+        }
+    }
+
+    fn name(&mut self) -> Result<String, ParseError> {
+        let offset = self.offset();
+        match self.advance() {
+            Some(Token::Name(name)) | Some(Token::Quoted(name)) => Ok(name),
+            _ => Err(ParseError::new(offset, "expected a name".into())),
+        }
+    }
+}
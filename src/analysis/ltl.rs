@@ -0,0 +1,784 @@
+//! LTL model checking with Büchi automata.
+//!
+//! A formula holds when every infinite run of the net from its initial marking satisfies it. As
+//! for CTL, a run reaching a deadlock stays in it forever. Formulas use the atoms and connectives
+//! of `ctl` with the temporal operators `X` (next), `F` (finally), `G` (globally), `U` (until) and
+//! `R` (release):
+//!
+//! ```text
+//! G (Requested = 1 -> F Granted = 1)
+//! G F enabled(Tick)
+//! !Granted >= 1 U Requested >= 1
+//! ```
+//!
+//! The negation of the formula is translated into a Büchi automaton with the tableau construction
+//! of Gerth, Peled, Vardi and Wolper. Its product with the net is explored on the fly with a
+//! nested depth-first search, which stops at the first accepting cycle: that cycle is a run
+//! violating the formula.
+
+use std::collections::{BTreeSet, HashMap};
+
+pub use super::formula::{Atom, Comparison, ParseError};
+use super::formula::{Logic, Parser};
+use super::{AnalysisError, Path};
+use crate::net::{ConnectionType, IndexedNet, Marking, PetriNet};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Formula {
+    True,
+    False,
+    Atom(Atom),
+    Not(Box<Formula>),
+    And(Box<Formula>, Box<Formula>),
+    Or(Box<Formula>, Box<Formula>),
+    Implies(Box<Formula>, Box<Formula>),
+    /// Holds in the next marking
+    Next(Box<Formula>),
+    /// Holds at some point
+    Finally(Box<Formula>),
+    /// Holds from now on
+    Globally(Box<Formula>),
+    /// The first formula holds until the second one does, which must happen
+    Until(Box<Formula>, Box<Formula>),
+    /// The second formula holds up to and including the point where the first one does, if ever
+    Release(Box<Formula>, Box<Formula>),
+}
+
+impl Formula {
+    pub fn tokens(place: usize, comparison: Comparison, value: i32) -> Self {
+        Formula::Atom(Atom::Tokens {
+            place,
+            comparison,
+            value,
+        })
+    }
+
+    pub fn enabled(transition: usize) -> Self {
+        Formula::Atom(Atom::Enabled(transition))
+    }
+
+    pub fn deadlock() -> Self {
+        Formula::Atom(Atom::Deadlock)
+    }
+
+    pub fn and(self, other: Formula) -> Self {
+        Formula::And(Box::new(self), Box::new(other))
+    }
+
+    pub fn or(self, other: Formula) -> Self {
+        Formula::Or(Box::new(self), Box::new(other))
+    }
+
+    pub fn implies(self, other: Formula) -> Self {
+        Formula::Implies(Box::new(self), Box::new(other))
+    }
+
+    pub fn next(formula: Formula) -> Self {
+        Formula::Next(Box::new(formula))
+    }
+
+    pub fn finally(formula: Formula) -> Self {
+        Formula::Finally(Box::new(formula))
+    }
+
+    pub fn globally(formula: Formula) -> Self {
+        Formula::Globally(Box::new(formula))
+    }
+
+    pub fn until(holds: Formula, until: Formula) -> Self {
+        Formula::Until(Box::new(holds), Box::new(until))
+    }
+
+    pub fn release(release: Formula, holds: Formula) -> Self {
+        Formula::Release(Box::new(release), Box::new(holds))
+    }
+
+    /// Parses `text`, resolving place and transition names in `net`
+    pub fn parse(text: &str, net: &PetriNet) -> Result<Self, ParseError> {
+        Parser::parse(text, net, binary)
+    }
+}
+
+impl std::ops::Not for Formula {
+    type Output = Formula;
+
+    fn not(self) -> Formula {
+        Formula::Not(Box::new(self))
+    }
+}
+
+impl Logic for Formula {
+    fn constant(value: bool) -> Self {
+        if value {
+            Formula::True
+        } else {
+            Formula::False
+        }
+    }
+
+    fn atom(atom: Atom) -> Self {
+        Formula::Atom(atom)
+    }
+
+    fn negate(self) -> Self {
+        !self
+    }
+
+    fn and(self, other: Self) -> Self {
+        Formula::and(self, other)
+    }
+
+    fn or(self, other: Self) -> Self {
+        Formula::or(self, other)
+    }
+
+    fn implies(self, other: Self) -> Self {
+        Formula::implies(self, other)
+    }
+}
+
+/// `U` and `R`, right associative and binding tighter than `&`
+fn binary(parser: &mut Parser<'_>) -> Result<Formula, ParseError> {
+    let left = unary(parser)?;
+    let operator: fn(Formula, Formula) -> Formula = match parser.keyword() {
+        Some("U") => Formula::until,
+        Some("R") => Formula::release,
+        _ => return Ok(left),
+    };
+
+    parser.advance();
+    Ok(operator(left, binary(parser)?))
+}
+
+/// Temporal operators, or whatever `Parser::primary` accepts
+fn unary(parser: &mut Parser<'_>) -> Result<Formula, ParseError> {
+    let operator: fn(Formula) -> Formula = match parser.keyword() {
+        Some("X") => Formula::next,
+        Some("F") => Formula::finally,
+        Some("G") => Formula::globally,
+        _ => return parser.primary(unary, binary),
+    };
+
+    parser.advance();
+    Ok(operator(unary(parser)?))
+}
+
+/// Formula in negation normal form, negations only appearing in literals
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Nnf {
+    True,
+    False,
+    /// An atom, or its negation when the flag is false
+    Literal(Atom, bool),
+    And(Box<Nnf>, Box<Nnf>),
+    Or(Box<Nnf>, Box<Nnf>),
+    Next(Box<Nnf>),
+    Until(Box<Nnf>, Box<Nnf>),
+    Release(Box<Nnf>, Box<Nnf>),
+}
+
+impl Nnf {
+    /// Normal form of `formula`, or of its negation when `negated` is set
+    fn new(formula: &Formula, negated: bool) -> Self {
+        let both = |a: &Formula, b: &Formula| {
+            (
+                Box::new(Nnf::new(a, negated)),
+                Box::new(Nnf::new(b, negated)),
+            )
+        };
+
+        match (formula, negated) {
+            (Formula::True, false) | (Formula::False, true) => Nnf::True,
+            (Formula::True, true) | (Formula::False, false) => Nnf::False,
+            (Formula::Atom(atom), _) => Nnf::Literal(atom.clone(), !negated),
+            (Formula::Not(f), _) => Nnf::new(f, !negated),
+            (Formula::And(a, b), false) | (Formula::Or(a, b), true) => {
+                let (a, b) = both(a, b);
+                Nnf::And(a, b)
+            }
+            (Formula::And(a, b), true) | (Formula::Or(a, b), false) => {
+                let (a, b) = both(a, b);
+                Nnf::Or(a, b)
+            }
+            (Formula::Implies(a, b), _) => {
+                Nnf::new(&Formula::Or(Box::new(!(**a).clone()), b.clone()), negated)
+            }
+            // Runs are infinite, so the negation of "next f" is "next not f"
+            (Formula::Next(f), _) => Nnf::Next(Box::new(Nnf::new(f, negated))),
+            (Formula::Finally(f), false) | (Formula::Globally(f), true) => {
+                Nnf::Until(Box::new(Nnf::True), Box::new(Nnf::new(f, negated)))
+            }
+            (Formula::Finally(f), true) | (Formula::Globally(f), false) => {
+                Nnf::Release(Box::new(Nnf::False), Box::new(Nnf::new(f, negated)))
+            }
+            (Formula::Until(a, b), false) | (Formula::Release(a, b), true) => {
+                let (a, b) = both(a, b);
+                Nnf::Until(a, b)
+            }
+            (Formula::Until(a, b), true) | (Formula::Release(a, b), false) => {
+                let (a, b) = both(a, b);
+                Nnf::Release(a, b)
+            }
+        }
+    }
+
+    /// Adds every `Until` subformula to `untils`
+    fn untils(&self, untils: &mut BTreeSet<Nnf>) {
+        match self {
+            Nnf::True | Nnf::False | Nnf::Literal(..) => {}
+            Nnf::Next(f) => f.untils(untils),
+            Nnf::And(a, b) | Nnf::Or(a, b) | Nnf::Release(a, b) => {
+                a.untils(untils);
+                b.untils(untils);
+            }
+            Nnf::Until(a, b) => {
+                untils.insert(self.clone());
+                a.untils(untils);
+                b.untils(untils);
+            }
+        }
+    }
+}
+
+/// Node of the tableau. Node ids start at 1, 0 standing for the initial state.
+#[derive(Clone)]
+struct Node {
+    incoming: BTreeSet<usize>,
+    new: BTreeSet<Nnf>,
+    old: BTreeSet<Nnf>,
+    next: BTreeSet<Nnf>,
+}
+
+impl Node {
+    /// Copy of the node where `formula` has been processed and `pending` still has to be
+    fn split(&self, formula: &Nnf, pending: &[&Nnf], next: Option<&Nnf>) -> Node {
+        let mut node = self.clone();
+        node.old.insert(formula.clone());
+        for f in pending.iter() {
+            if !node.old.contains(*f) {
+                node.new.insert((*f).clone());
+            }
+        }
+        if let Some(f) = next {
+            node.next.insert(f.clone());
+        }
+
+        node
+    }
+}
+
+fn expand(mut node: Node, nodes: &mut Vec<Node>) {
+    let Some(formula) = node.new.pop_first() else {
+        if let Some(existing) = nodes
+            .iter_mut()
+            .find(|n| n.old == node.old && n.next == node.next)
+        {
+            existing.incoming.extend(node.incoming);
+            return;
+        }
+
+        let successor = Node {
+            incoming: BTreeSet::from([nodes.len() + 1]),
+            new: node.next.clone(),
+            old: BTreeSet::new(),
+            next: BTreeSet::new(),
+        };
+        nodes.push(node);
+        return expand(successor, nodes);
+    };
+
+    match &formula {
+        Nnf::False => {}
+        Nnf::True => {
+            node.old.insert(formula);
+            expand(node, nodes);
+        }
+        Nnf::Literal(atom, positive) => {
+            if !node.old.contains(&Nnf::Literal(atom.clone(), !positive)) {
+                node.old.insert(formula);
+                expand(node, nodes);
+            }
+        }
+        Nnf::And(a, b) => expand(node.split(&formula, &[a, b], None), nodes),
+        Nnf::Next(f) => expand(node.split(&formula, &[], Some(f)), nodes),
+        Nnf::Or(a, b) => {
+            expand(node.split(&formula, &[a], None), nodes);
+            expand(node.split(&formula, &[b], None), nodes);
+        }
+        Nnf::Until(a, b) => {
+            expand(node.split(&formula, &[a], Some(&formula)), nodes);
+            expand(node.split(&formula, &[b], None), nodes);
+        }
+        Nnf::Release(a, b) => {
+            expand(node.split(&formula, &[b], Some(&formula)), nodes);
+            expand(node.split(&formula, &[a, b], None), nodes);
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BuchiState {
+    /// Literals that must hold in the marking read when entering the state, an atom being
+    /// negated when its flag is false
+    pub literals: Vec<(Atom, bool)>,
+    pub successors: Vec<usize>,
+    pub accepting: bool,
+}
+
+/// Büchi automaton over the markings of a net, accepting the runs that visit accepting states
+/// infinitely often
+#[derive(Clone, Debug, PartialEq)]
+pub struct BuchiAutomaton {
+    pub states: Vec<BuchiState>,
+    /// States that can read the first marking of a run
+    pub initial: Vec<usize>,
+}
+
+impl BuchiAutomaton {
+    /// Automaton accepting exactly the runs satisfying `formula`
+    pub fn new(formula: &Formula) -> Self {
+        let formula = Nnf::new(formula, false);
+
+        let mut nodes = vec![];
+        expand(
+            Node {
+                incoming: BTreeSet::from([0]),
+                new: BTreeSet::from([formula.clone()]),
+                old: BTreeSet::new(),
+                next: BTreeSet::new(),
+            },
+            &mut nodes,
+        );
+
+        // Every until has an acceptance condition: either it is not pending, or it is fulfilled.
+        // A counter cycling through the conditions turns them into a single one.
+        let mut untils = BTreeSet::new();
+        formula.untils(&mut untils);
+        let fulfilled: Vec<Vec<bool>> = untils
+            .iter()
+            .map(|until| {
+                let Nnf::Until(_, goal) = until else {
+                    unreachable!()
+                };
+                nodes
+                    .iter()
+                    .map(|n| !n.old.contains(until) || n.old.contains(goal))
+                    .collect()
+            })
+            .collect();
+        let counters = fulfilled.len().max(1);
+        let id = |node: usize, counter: usize| node * counters + counter;
+
+        // Counter reached after leaving `node` with `counter`, and whether that state accepts
+        let step = |node: usize, counter: usize| match fulfilled.get(counter) {
+            None => (0, true),
+            Some(conditions) if conditions[node] => ((counter + 1) % counters, counter == 0),
+            Some(_) => (counter, false),
+        };
+
+        let mut states = vec![];
+        for node in nodes.iter() {
+            let literals: Vec<(Atom, bool)> = node
+                .old
+                .iter()
+                .filter_map(|f| match f {
+                    Nnf::Literal(atom, positive) => Some((atom.clone(), *positive)),
+                    _ => None,
+                })
+                .collect();
+
+            for counter in 0..counters {
+                states.push(BuchiState {
+                    literals: literals.clone(),
+                    successors: vec![],
+                    accepting: step(states.len() / counters, counter).1,
+                });
+            }
+        }
+
+        for (target, node) in nodes.iter().enumerate() {
+            for source in node.incoming.iter().filter(|source| **source > 0) {
+                for counter in 0..counters {
+                    let (next_counter, _) = step(source - 1, counter);
+                    states[id(source - 1, counter)]
+                        .successors
+                        .push(id(target, next_counter));
+                }
+            }
+        }
+
+        let initial = nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.incoming.contains(&0))
+            .map(|(node, _)| id(node, 0))
+            .collect();
+
+        Self { states, initial }
+    }
+
+    /// Whether `state` can read `marking`
+    pub fn reads(&self, state: usize, net: &IndexedNet, marking: &Marking) -> bool {
+        self.states[state]
+            .literals
+            .iter()
+            .all(|(atom, positive)| atom.holds(net, marking) == *positive)
+    }
+}
+
+/// Result of checking a formula on every run of a net
+#[derive(Clone, Debug, PartialEq)]
+pub struct Verdict {
+    pub holds: bool,
+    /// A run violating the formula, as a lasso or a path ending in a deadlock
+    pub counterexample: Option<Path>,
+}
+
+/// Checks LTL formulas, exploring the net on the fly
+pub struct ModelChecker {
+    indexed: IndexedNet,
+    initial: Marking,
+    limit: Option<usize>,
+}
+
+/// Firing and target of a step, without firing for the stuttering step of a deadlock
+type Step = (Option<usize>, usize);
+
+/// Product of the net and the automaton, explored lazily
+struct Product<'a> {
+    indexed: &'a IndexedNet,
+    automaton: &'a BuchiAutomaton,
+    markings: Vec<Marking>,
+    marking_index: HashMap<Marking, usize>,
+    /// `(marking, transition)` each marking was first reached from
+    marking_parents: Vec<Option<(usize, usize)>>,
+    /// Whether every connection is normal, so that covering an earlier marking proves unboundedness
+    monotonic: bool,
+    /// Successors of every marking explored so far
+    marking_successors: Vec<Option<Vec<Step>>>,
+    /// `(marking, automaton state)` of every product state
+    states: Vec<(usize, usize)>,
+    state_index: HashMap<(usize, usize), usize>,
+    limit: Option<usize>,
+}
+
+impl Product<'_> {
+    /// Index of `marking`, reached by firing `parent`, if any. Like `StateSpace::explore`, fails
+    /// with `AnalysisError::Unbounded` when a new marking strictly covers one of its ancestors.
+    fn marking(
+        &mut self,
+        marking: Marking,
+        parent: Option<(usize, usize)>,
+    ) -> Result<usize, AnalysisError> {
+        if let Some(index) = self.marking_index.get(&marking) {
+            return Ok(*index);
+        }
+
+        if let Some((source, transition)) = parent.filter(|_| self.monotonic) {
+            let mut current = Some(source);
+            while let Some(ancestor) = current {
+                let earlier = &self.markings[ancestor];
+                if marking.covers(earlier) && marking != *earlier {
+                    let mut firings = self.path_to(source);
+                    firings.push(transition);
+                    return Err(AnalysisError::Unbounded { marking, firings });
+                }
+                current = self.marking_parents[ancestor].map(|(parent, _)| parent);
+            }
+        }
+
+        self.markings.push(marking.clone());
+        self.marking_parents.push(parent);
+        self.marking_successors.push(None);
+        self.marking_index.insert(marking, self.markings.len() - 1);
+        Ok(self.markings.len() - 1)
+    }
+
+    /// Firings leading from the initial marking to `marking`
+    fn path_to(&self, marking: usize) -> Vec<usize> {
+        let mut firings = vec![];
+        let mut current = marking;
+        while let Some((parent, transition)) = self.marking_parents[current] {
+            firings.push(transition);
+            current = parent;
+        }
+        firings.reverse();
+
+        firings
+    }
+
+    fn state(&mut self, marking: usize, automaton: usize) -> Result<usize, AnalysisError> {
+        if let Some(state) = self.state_index.get(&(marking, automaton)) {
+            return Ok(*state);
+        }
+        if self.limit.is_some_and(|limit| self.states.len() >= limit) {
+            return Err(AnalysisError::StateLimit(self.states.len()));
+        }
+
+        self.states.push((marking, automaton));
+        self.state_index
+            .insert((marking, automaton), self.states.len() - 1);
+        Ok(self.states.len() - 1)
+    }
+
+    fn initial(&mut self, marking: Marking) -> Result<Vec<usize>, AnalysisError> {
+        let index = self.marking(marking, None)?;
+        let mut initial = vec![];
+        for automaton in self.automaton.initial.iter() {
+            if self
+                .automaton
+                .reads(*automaton, self.indexed, &self.markings[index])
+            {
+                initial.push(self.state(index, *automaton)?);
+            }
+        }
+
+        Ok(initial)
+    }
+
+    fn successors(&mut self, state: usize) -> Result<Vec<Step>, AnalysisError> {
+        let (marking, automaton) = self.states[state];
+
+        if self.marking_successors[marking].is_none() {
+            let enabled = self.indexed.enabled(&self.markings[marking]);
+            let successors = if enabled.is_empty() {
+                vec![(None, marking)]
+            } else {
+                let mut successors = vec![];
+                for transition in enabled {
+                    let mut next = self.markings[marking].clone();
+                    self.indexed.fire(transition, &mut next);
+                    let next = self.marking(next, Some((marking, transition)))?;
+                    successors.push((Some(transition), next));
+                }
+                successors
+            };
+            self.marking_successors[marking] = Some(successors);
+        }
+
+        let mut successors = vec![];
+        for (transition, next) in self.marking_successors[marking].clone().unwrap() {
+            for target in self.automaton.states[automaton].successors.iter() {
+                if self
+                    .automaton
+                    .reads(*target, self.indexed, &self.markings[next])
+                {
+                    successors.push((transition, self.state(next, *target)?));
+                }
+            }
+        }
+
+        Ok(successors)
+    }
+
+    fn accepting(&self, state: usize) -> bool {
+        self.automaton.states[self.states[state].1].accepting
+    }
+}
+
+/// Frame of a depth-first search: the state, the firing it was entered with, its successors and
+/// how many of them were visited
+struct Frame {
+    state: usize,
+    firing: Option<usize>,
+    successors: Vec<Step>,
+    visited: usize,
+}
+
+impl ModelChecker {
+    /// Checker for the initial marking of `net`, without limit.
+    ///
+    /// When all connections are normal, `check` fails with `AnalysisError::Unbounded` as soon as a
+    /// marking strictly covers one of its predecessors. Nets with inhibitor or reset connections
+    /// may have infinitely many markings without that being detected, and need `with_limit` for
+    /// `check` to terminate.
+    pub fn new(net: &PetriNet) -> Self {
+        Self {
+            indexed: IndexedNet::new(net),
+            initial: net.initial_marking(),
+            limit: None,
+        }
+    }
+
+    /// Gives up with `AnalysisError::StateLimit` after exploring `limit` product states
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn with_initial_marking(mut self, marking: Marking) -> Self {
+        self.initial = marking;
+        self
+    }
+
+    /// Checks `formula` on every run, looking for an accepting cycle of the automaton of its
+    /// negation. Unbounded nets fail as described in [`new`](Self::new), and an initial marking
+    /// without one entry per place with `AnalysisError::MarkingLength`.
+    pub fn check(&self, formula: &Formula) -> Result<Verdict, AnalysisError> {
+        if self.initial.len() != self.indexed.place_count() {
+            return Err(AnalysisError::MarkingLength {
+                expected: self.indexed.place_count(),
+                found: self.initial.len(),
+            });
+        }
+
+        let automaton = BuchiAutomaton::new(&!formula.clone());
+        let mut product = Product {
+            indexed: &self.indexed,
+            automaton: &automaton,
+            markings: vec![],
+            marking_index: HashMap::new(),
+            marking_parents: vec![],
+            monotonic: (0..self.indexed.transition_count()).all(|transition| {
+                self.indexed
+                    .pre(transition)
+                    .iter()
+                    .all(|con| con.con_type == ConnectionType::NORMAL)
+            }),
+            marking_successors: vec![],
+            states: vec![],
+            state_index: HashMap::new(),
+            limit: self.limit,
+        };
+
+        let mut blue = vec![];
+        let mut red = vec![];
+
+        for initial in product.initial(self.initial.clone())? {
+            if blue.get(initial).copied().unwrap_or(false) {
+                continue;
+            }
+
+            let mut stack = vec![Frame {
+                state: initial,
+                firing: None,
+                successors: product.successors(initial)?,
+                visited: 0,
+            }];
+            mark(&mut blue, initial);
+
+            while let Some(frame) = stack.last_mut() {
+                if let Some((firing, next)) = frame.successors.get(frame.visited).copied() {
+                    frame.visited += 1;
+                    if !blue.get(next).copied().unwrap_or(false) {
+                        mark(&mut blue, next);
+                        stack.push(Frame {
+                            state: next,
+                            firing,
+                            successors: product.successors(next)?,
+                            visited: 0,
+                        });
+                    }
+                    continue;
+                }
+
+                // Every successor is done: look for a cycle through accepting states in postorder
+                let frame = stack.pop().unwrap();
+                if product.accepting(frame.state) {
+                    if let Some(cycle) = red_search(&mut product, &mut red, frame.state)? {
+                        stack.push(frame);
+                        return Ok(Verdict {
+                            holds: false,
+                            counterexample: Some(lasso(&product, &stack, &cycle)),
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(Verdict {
+            holds: true,
+            counterexample: None,
+        })
+    }
+}
+
+fn mark(visited: &mut Vec<bool>, state: usize) {
+    if visited.len() <= state {
+        visited.resize(state + 1, false);
+    }
+    visited[state] = true;
+}
+
+/// Looks for a path from `seed` back to itself, returning its steps
+fn red_search(
+    product: &mut Product<'_>,
+    red: &mut Vec<bool>,
+    seed: usize,
+) -> Result<Option<Vec<Step>>, AnalysisError> {
+    let mut stack = vec![Frame {
+        state: seed,
+        firing: None,
+        successors: product.successors(seed)?,
+        visited: 0,
+    }];
+
+    while let Some(frame) = stack.last_mut() {
+        let Some((firing, next)) = frame.successors.get(frame.visited).copied() else {
+            stack.pop();
+            continue;
+        };
+        frame.visited += 1;
+
+        if next == seed {
+            let mut cycle: Vec<Step> = stack
+                .iter()
+                .skip(1)
+                .map(|frame| (frame.firing, frame.state))
+                .collect();
+            cycle.push((firing, seed));
+            return Ok(Some(cycle));
+        }
+
+        if !red.get(next).copied().unwrap_or(false) {
+            mark(red, next);
+            stack.push(Frame {
+                state: next,
+                firing,
+                successors: product.successors(next)?,
+                visited: 0,
+            });
+        }
+    }
+
+    Ok(None)
+}
+
+/// Path through the net following the blue `stack`, whose top is the seed, and the `cycle`
+/// leading back to it
+fn lasso(product: &Product<'_>, stack: &[Frame], cycle: &[Step]) -> Path {
+    let marking = |state: usize| product.markings[product.states[state].0].clone();
+    let seed = stack.len() - 1;
+
+    // A deadlock only steps to itself, so a cycle either fires transitions all along or only
+    // stutters in a deadlock where the path ends
+    if cycle.iter().all(|(firing, _)| firing.is_none()) {
+        let steps = stack.iter().filter(|frame| frame.firing.is_some());
+        return Path {
+            markings: std::iter::once(marking(stack[0].state))
+                .chain(steps.clone().map(|frame| marking(frame.state)))
+                .collect(),
+            firings: steps.filter_map(|frame| frame.firing).collect(),
+            cycle: None,
+        };
+    }
+
+    let mut markings: Vec<Marking> = stack.iter().map(|frame| marking(frame.state)).collect();
+    let mut firings: Vec<usize> = stack
+        .iter()
+        .skip(1)
+        .filter_map(|frame| frame.firing)
+        .collect();
+    for (firing, state) in cycle.iter() {
+        firings.push(firing.expect("Cycles through transitions never stutter"));
+        if *state != stack[seed].state {
+            markings.push(marking(*state));
+        }
+    }
+
+    Path {
+        markings,
+        firings,
+        cycle: Some(seed),
+    }
+}
@@ -0,0 +1,233 @@
+//! Quasi-reduced multi-valued decision diagrams over markings.
+//!
+//! Level `k` of a diagram decides the tokens in place `k`, and every path from the root goes
+//! through every level before reaching a terminal. Nodes are shared through a unique table, so
+//! two sets are equal exactly when their roots are, and results of the set operations are cached
+//! for the lifetime of the manager.
+
+use std::collections::HashMap;
+
+/// Index of a node in its `Mdd`
+pub(super) type Node = usize;
+
+/// The empty set, at any level
+pub(super) const EMPTY: Node = 0;
+/// The terminal reached by the markings of a set, below the last level
+pub(super) const ACCEPT: Node = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Operation {
+    Union,
+    Intersection,
+    Difference,
+}
+
+/// Node manager, deciding `levels` variables each ranging over `0..domain`
+#[derive(Clone, Debug)]
+pub(super) struct Mdd {
+    levels: usize,
+    domain: usize,
+    /// `(level, children)` of every node, terminals included
+    nodes: Vec<(usize, Vec<Node>)>,
+    unique: HashMap<(usize, Vec<Node>), Node>,
+    cache: HashMap<(Operation, Node, Node), Node>,
+}
+
+impl Mdd {
+    pub(super) fn new(levels: usize, domain: usize) -> Self {
+        Self {
+            levels,
+            domain,
+            nodes: vec![(levels, vec![]), (levels, vec![])],
+            unique: HashMap::new(),
+            cache: HashMap::new(),
+        }
+    }
+
+    pub(super) fn levels(&self) -> usize {
+        self.levels
+    }
+
+    pub(super) fn domain(&self) -> usize {
+        self.domain
+    }
+
+    /// Number of nodes created so far, terminals included
+    pub(super) fn size(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub(super) fn level(&self, node: Node) -> usize {
+        self.nodes[node].0
+    }
+
+    /// Child of `node` for every value of its level
+    pub(super) fn children(&self, node: Node) -> &[Node] {
+        &self.nodes[node].1
+    }
+
+    /// The node deciding `level` with `children`, which is `EMPTY` if they all are
+    pub(super) fn node(&mut self, level: usize, children: Vec<Node>) -> Node {
+        if children.iter().all(|child| *child == EMPTY) {
+            return EMPTY;
+        }
+
+        let key = (level, children);
+        if let Some(node) = self.unique.get(&key) {
+            return *node;
+        }
+        let node = self.nodes.len();
+        self.nodes.push(key.clone());
+        self.unique.insert(key, node);
+
+        node
+    }
+
+    /// The set holding only `values`, one per level
+    pub(super) fn singleton(&mut self, values: &[usize]) -> Node {
+        let mut node = ACCEPT;
+        for (level, value) in values.iter().enumerate().rev() {
+            let mut children = vec![EMPTY; self.domain];
+            children[*value] = node;
+            node = self.node(level, children);
+        }
+
+        node
+    }
+
+    pub(super) fn union(&mut self, left: Node, right: Node) -> Node {
+        self.apply(Operation::Union, left, right)
+    }
+
+    pub(super) fn intersection(&mut self, left: Node, right: Node) -> Node {
+        self.apply(Operation::Intersection, left, right)
+    }
+
+    pub(super) fn difference(&mut self, left: Node, right: Node) -> Node {
+        self.apply(Operation::Difference, left, right)
+    }
+
+    fn apply(&mut self, operation: Operation, left: Node, right: Node) -> Node {
+        match operation {
+            Operation::Union if left == EMPTY || left == right => return right,
+            Operation::Union if right == EMPTY => return left,
+            Operation::Intersection if left == EMPTY || right == EMPTY => return EMPTY,
+            Operation::Intersection if left == right => return left,
+            Operation::Difference if left == EMPTY || left == right => return EMPTY,
+            Operation::Difference if right == EMPTY => return left,
+            // Below the last level, the only non-empty set is ACCEPT, which the cases above cover
+            _ => {}
+        }
+
+        // Union and intersection are commutative, share their cache entries
+        let key = match operation {
+            Operation::Difference => (operation, left, right),
+            _ => (operation, left.min(right), left.max(right)),
+        };
+        if let Some(node) = self.cache.get(&key) {
+            return *node;
+        }
+
+        let level = self.level(left);
+        let children = (0..self.domain)
+            .map(|value| {
+                let (left, right) = (self.children(left)[value], self.children(right)[value]);
+                self.apply(operation, left, right)
+            })
+            .collect();
+        let node = self.node(level, children);
+        self.cache.insert(key, node);
+
+        node
+    }
+
+    /// Number of value combinations in the set, saturating at `u128::MAX`
+    pub(super) fn count(&self, node: Node) -> u128 {
+        fn count(mdd: &Mdd, node: Node, memo: &mut HashMap<Node, u128>) -> u128 {
+            match node {
+                EMPTY => 0,
+                ACCEPT => 1,
+                _ => {
+                    if let Some(count) = memo.get(&node) {
+                        return *count;
+                    }
+                    let total = mdd.children(node).iter().fold(0u128, |total, child| {
+                        total.saturating_add(count(mdd, *child, memo))
+                    });
+                    memo.insert(node, total);
+                    total
+                }
+            }
+        }
+
+        count(self, node, &mut HashMap::new())
+    }
+
+    pub(super) fn contains(&self, node: Node, values: &[usize]) -> bool {
+        let mut current = node;
+        for value in values.iter() {
+            if current == EMPTY || *value >= self.domain {
+                return false;
+            }
+            current = self.children(current)[*value];
+        }
+
+        current == ACCEPT
+    }
+
+    /// Some value combination of the set, the smallest in lexicographic order
+    pub(super) fn pick(&self, node: Node) -> Option<Vec<usize>> {
+        if node == EMPTY {
+            return None;
+        }
+
+        let mut values = Vec::with_capacity(self.levels);
+        let mut current = node;
+        while current != ACCEPT {
+            let value = self
+                .children(current)
+                .iter()
+                .position(|child| *child != EMPTY)?;
+            values.push(value);
+            current = self.children(current)[value];
+        }
+
+        Some(values)
+    }
+
+    /// Subset of `node` whose value at `level` satisfies `keep`
+    pub(super) fn restrict(
+        &mut self,
+        node: Node,
+        level: usize,
+        keep: &dyn Fn(usize) -> bool,
+        memo: &mut HashMap<Node, Node>,
+    ) -> Node {
+        if node == EMPTY || self.level(node) > level {
+            return node;
+        }
+        if let Some(result) = memo.get(&node) {
+            return *result;
+        }
+
+        let current = self.level(node);
+        let children = (0..self.domain)
+            .map(|value| {
+                let child = self.children(node)[value];
+                if current == level {
+                    if keep(value) {
+                        child
+                    } else {
+                        EMPTY
+                    }
+                } else {
+                    self.restrict(child, level, keep, memo)
+                }
+            })
+            .collect();
+        let result = self.node(current, children);
+        memo.insert(node, result);
+
+        result
+    }
+}
@@ -0,0 +1,105 @@
+//! Behavioural and structural analysis of nets.
+//!
+//! Unlike `Simulation`, which fires every enabled transition at once, analyses use interleaving
+//! semantics: a step fires exactly one enabled transition. Places and transitions are referred to
+//! by their index in `PetriNet::places` and `PetriNet::transitions`.
+
+pub mod classes;
+pub mod ctl;
+mod formula;
+pub mod ltl;
+mod mdd;
+pub mod reachability;
+pub mod reduction;
+mod simplex;
+pub mod state_equation;
+pub mod state_space;
+pub mod structure;
+pub mod stubborn;
+pub mod symbolic;
+pub mod unfolding;
+pub mod workflow;
+
+pub use classes::{ClassViolation, Classification, NetClass};
+pub use reachability::{Reachability, Strategy};
+pub use reduction::{Reduction, Rule};
+pub use state_equation::{StateEquation, Verdict};
+pub use state_space::StateSpace;
+pub use structure::{Liveness, Structure};
+pub use stubborn::StubbornSets;
+pub use symbolic::SymbolicStateSpace;
+pub use unfolding::{Unfolding, UnfoldingError};
+pub use workflow::{Soundness, Violation, WorkflowError, WorkflowNet};
+
+use crate::net::Marking;
+
+/// A path through the reachability graph.
+///
+/// `firings[i]` leads from `markings[i]` to `markings[i + 1]`. When `cycle` is set, the path is
+/// infinite: the last firing leads from the last marking back to `markings[cycle]`, so there is
+/// one firing more than markings. A path ending without a cycle ends in a deadlock, or is a
+/// finite prefix that is enough to show the property.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Path {
+    pub markings: Vec<Marking>,
+    pub firings: Vec<usize>,
+    pub cycle: Option<usize>,
+}
+
+/// Errors raised while exploring the behaviour of a net
+#[derive(Clone, Debug, PartialEq)]
+pub enum AnalysisError {
+    /// The net has infinitely many reachable markings. `firings` leads from the initial marking to
+    /// `marking`, which strictly covers a marking visited on the way.
+    Unbounded {
+        marking: Marking,
+        firings: Vec<usize>,
+    },
+    /// More markings than the given limit are reachable
+    StateLimit(usize),
+    /// A reachable marking puts more tokens than the given bound in `place`
+    BoundExceeded { place: usize, bound: i32 },
+    /// A marking given to an analysis does not have one entry per place of the net
+    MarkingLength { expected: usize, found: usize },
+    /// A place index given to an analysis is not below the number of places
+    UnknownPlace { place: usize, places: usize },
+    /// A transition index given to an analysis is not below the number of transitions
+    UnknownTransition {
+        transition: usize,
+        transitions: usize,
+    },
+}
+
+impl std::fmt::Display for AnalysisError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnalysisError::Unbounded { marking, .. } => {
+                write!(f, "net is unbounded, {} covers an earlier marking", marking)
+            }
+            AnalysisError::StateLimit(limit) => {
+                write!(f, "more than {} markings are reachable", limit)
+            }
+            AnalysisError::BoundExceeded { place, bound } => {
+                write!(f, "place {} holds more than {} tokens", place, bound)
+            }
+            AnalysisError::MarkingLength { expected, found } => write!(
+                f,
+                "marking has {} entries but the net has {} places",
+                found, expected
+            ),
+            AnalysisError::UnknownPlace { place, places } => {
+                write!(f, "place {} does not exist, the net has {}", place, places)
+            }
+            AnalysisError::UnknownTransition {
+                transition,
+                transitions,
+            } => write!(
+                f,
+                "transition {} does not exist, the net has {}",
+                transition, transitions
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AnalysisError {}
@@ -0,0 +1,335 @@
+//! Searches for a marking satisfying a predicate, returning the firings leading to it.
+//!
+//! ```
+//! use petri_engine::petri_net;
+//!
+//! let net = petri_net! {
+//!     places => [Start<1>, Middle, End],
+//!     transitions => [A, B],
+//!     connections => [Start -> A, A -> Middle, Middle -> B, B -> End]
+//! };
+//!
+//! let path = net.can_reach(|marking| marking[2] == 1).unwrap().unwrap();
+//! assert_eq!(path.firings, vec![0, 1]);
+//! assert!(net.can_cover(&vec![0, 2, 0].into()).unwrap().is_none());
+//! ```
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+
+use super::{AnalysisError, Path, StubbornSets};
+use crate::net::{IndexedNet, Marking, PetriNet};
+
+/// Order in which markings are explored
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Strategy {
+    /// Finds a shortest firing sequence
+    #[default]
+    BreadthFirst,
+    /// Uses little memory on deep state spaces, but the sequence found may be long
+    DepthFirst,
+    /// Finds a shortest firing sequence, exploring first the markings a heuristic deems closest
+    /// to the goal
+    AStar,
+}
+
+/// Reachability and coverability queries on a net
+pub struct Reachability<'a> {
+    net: &'a PetriNet,
+    indexed: IndexedNet,
+    initial: Marking,
+    strategy: Strategy,
+    limit: Option<usize>,
+    /// Visible places when searching with partial-order reduction
+    partial_order: Option<Vec<usize>>,
+}
+
+/// Markings found so far, with the firing they were first reached by
+struct Visited {
+    markings: Vec<Marking>,
+    index: HashMap<Marking, usize>,
+    parents: Vec<Option<(usize, usize)>>,
+    depth: Vec<usize>,
+    limit: Option<usize>,
+}
+
+impl Visited {
+    /// Index of `marking`, and whether it was seen for the first time
+    fn insert(
+        &mut self,
+        marking: Marking,
+        parent: Option<(usize, usize)>,
+    ) -> Result<(usize, bool), AnalysisError> {
+        if let Some(index) = self.index.get(&marking) {
+            return Ok((*index, false));
+        }
+        if self.limit.is_some_and(|limit| self.markings.len() >= limit) {
+            return Err(AnalysisError::StateLimit(self.markings.len()));
+        }
+
+        let depth = parent.map(|(p, _)| self.depth[p] + 1).unwrap_or(0);
+        self.index.insert(marking.clone(), self.markings.len());
+        self.markings.push(marking);
+        self.parents.push(parent);
+        self.depth.push(depth);
+
+        Ok((self.markings.len() - 1, true))
+    }
+
+    fn path(&self, state: usize) -> Path {
+        let mut markings = vec![self.markings[state].clone()];
+        let mut firings = vec![];
+        let mut current = state;
+        while let Some((parent, transition)) = self.parents[current] {
+            markings.push(self.markings[parent].clone());
+            firings.push(transition);
+            current = parent;
+        }
+        markings.reverse();
+        firings.reverse();
+
+        Path {
+            markings,
+            firings,
+            cycle: None,
+        }
+    }
+}
+
+impl<'a> Reachability<'a> {
+    /// Queries starting from the initial marking of `net`, searching breadth first without limit
+    pub fn new(net: &'a PetriNet) -> Self {
+        Self {
+            net,
+            indexed: IndexedNet::new(net),
+            initial: net.initial_marking(),
+            strategy: Strategy::default(),
+            limit: None,
+            partial_order: None,
+        }
+    }
+
+    pub fn strategy(mut self, strategy: Strategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Gives up with `AnalysisError::StateLimit` after visiting `limit` markings. Without a limit,
+    /// a search for an unreachable goal in an unbounded net never ends.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Only fires the transitions of a stubborn set in each marking. The goal must only depend on
+    /// the tokens in `visible` places, or be a deadlock; `can_cover` adds the places of its target
+    /// itself.
+    pub fn partial_order(mut self, visible: &[usize]) -> Self {
+        self.partial_order = Some(visible.to_vec());
+        self
+    }
+
+    /// Searches from `marking` instead. Queries fail with `AnalysisError::MarkingLength` unless it
+    /// has one entry per place.
+    pub fn with_initial_marking(mut self, marking: Marking) -> Self {
+        self.initial = marking;
+        self
+    }
+
+    pub fn net(&self) -> &PetriNet {
+        self.net
+    }
+
+    /// Shortest path to a marking satisfying `predicate`, except with `Strategy::DepthFirst`.
+    /// `Strategy::AStar` has no estimate of the distance to the goal and behaves like a
+    /// breadth-first search; use `can_reach_guided` to give it one.
+    pub fn can_reach(
+        &self,
+        predicate: impl Fn(&Marking) -> bool,
+    ) -> Result<Option<Path>, AnalysisError> {
+        self.search(predicate, |_| Some(0), &[])
+    }
+
+    /// Like `can_reach`, with `heuristic` estimating how many firings are left before reaching
+    /// the goal, or `None` if it cannot be reached anymore. For the path to be a shortest one,
+    /// the estimate must never exceed the actual number of firings.
+    pub fn can_reach_guided(
+        &self,
+        predicate: impl Fn(&Marking) -> bool,
+        heuristic: impl Fn(&Marking) -> Option<usize>,
+    ) -> Result<Option<Path>, AnalysisError> {
+        self.search(predicate, heuristic, &[])
+    }
+
+    /// Path to a marking with at least as many tokens as `target` in every place.
+    ///
+    /// Markings from which a place lacking tokens cannot be filled anymore are pruned, and
+    /// `Strategy::AStar` is guided by how many firings the most lacking place needs at least.
+    /// Fails with `AnalysisError::MarkingLength` unless `target` has one entry per place, as every
+    /// query does for the initial marking.
+    pub fn can_cover(&self, target: &Marking) -> Result<Option<Path>, AnalysisError> {
+        self.check_length(target)?;
+
+        // Most tokens a single firing can add to each place
+        let mut production = vec![0; self.indexed.place_count()];
+        for transition in 0..self.indexed.transition_count() {
+            for con in self.indexed.post(transition).iter() {
+                production[con.place] = production[con.place].max(con.weight);
+            }
+        }
+
+        let covered: Vec<usize> = (0..target.len()).filter(|p| target[*p] > 0).collect();
+        self.search(
+            |marking| marking.covers(target),
+            |marking| {
+                let mut estimate = 0;
+                for (place, (tokens, wanted)) in marking.iter().zip(target.iter()).enumerate() {
+                    let lacking = wanted - tokens;
+                    if lacking > 0 {
+                        if production[place] == 0 {
+                            return None;
+                        }
+                        let firings = (lacking as u32).div_ceil(production[place] as u32);
+                        estimate = estimate.max(firings as usize);
+                    }
+                }
+                Some(estimate)
+            },
+            &covered,
+        )
+    }
+
+    fn check_length(&self, marking: &Marking) -> Result<(), AnalysisError> {
+        if marking.len() != self.indexed.place_count() {
+            return Err(AnalysisError::MarkingLength {
+                expected: self.indexed.place_count(),
+                found: marking.len(),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn search(
+        &self,
+        goal: impl Fn(&Marking) -> bool,
+        heuristic: impl Fn(&Marking) -> Option<usize>,
+        visible: &[usize],
+    ) -> Result<Option<Path>, AnalysisError> {
+        self.check_length(&self.initial)?;
+        let stubborn = self.partial_order.as_ref().map(|places| {
+            StubbornSets::new(&self.indexed)
+                .with_visible_places(places)
+                .with_visible_places(visible)
+        });
+        let firing = |marking: &Marking, visited: &Visited| {
+            let enabled = self.indexed.enabled(marking);
+            let Some(stubborn) = &stubborn else {
+                return enabled;
+            };
+            let reduced = stubborn.stubborn(&self.indexed, marking, &enabled);
+            // A reduced firing closing a cycle could postpone the other transitions forever
+            let closes_cycle = reduced.iter().any(|transition| {
+                self.indexed
+                    .successor(*transition, marking)
+                    .is_some_and(|next| visited.index.contains_key(&next))
+            });
+            if closes_cycle {
+                enabled
+            } else {
+                reduced
+            }
+        };
+
+        let mut visited = Visited {
+            markings: vec![],
+            index: HashMap::new(),
+            parents: vec![],
+            depth: vec![],
+            limit: self.limit,
+        };
+        let (initial, _) = visited.insert(self.initial.clone(), None)?;
+        if goal(&self.initial) {
+            return Ok(Some(visited.path(initial)));
+        }
+
+        match self.strategy {
+            Strategy::BreadthFirst | Strategy::DepthFirst => {
+                let mut pending = VecDeque::from([initial]);
+                let next = |pending: &mut VecDeque<usize>| match self.strategy {
+                    Strategy::DepthFirst => pending.pop_back(),
+                    _ => pending.pop_front(),
+                };
+
+                while let Some(state) = next(&mut pending) {
+                    if heuristic(&visited.markings[state]).is_none() {
+                        continue;
+                    }
+
+                    for transition in firing(&visited.markings[state], &visited) {
+                        let mut marking = visited.markings[state].clone();
+                        self.indexed.fire(transition, &mut marking);
+
+                        let (target, new) = visited.insert(marking, Some((state, transition)))?;
+                        if new {
+                            if goal(&visited.markings[target]) {
+                                return Ok(Some(visited.path(target)));
+                            }
+                            pending.push_back(target);
+                        }
+                    }
+                }
+            }
+            Strategy::AStar => {
+                let mut pending = BinaryHeap::new();
+                if let Some(estimate) = heuristic(&self.initial) {
+                    pending.push(Reverse((estimate, 0, initial)));
+                }
+
+                while let Some(Reverse((_, depth, state))) = pending.pop() {
+                    if depth > visited.depth[state] {
+                        // Reached again through a shorter path since this entry was queued
+                        continue;
+                    }
+                    if goal(&visited.markings[state]) {
+                        return Ok(Some(visited.path(state)));
+                    }
+
+                    for transition in firing(&visited.markings[state], &visited) {
+                        let mut marking = visited.markings[state].clone();
+                        self.indexed.fire(transition, &mut marking);
+                        let Some(estimate) = heuristic(&marking) else {
+                            continue;
+                        };
+
+                        let (target, new) = visited.insert(marking, Some((state, transition)))?;
+                        if !new && visited.depth[target] <= depth + 1 {
+                            continue;
+                        }
+                        visited.parents[target] = Some((state, transition));
+                        visited.depth[target] = depth + 1;
+                        pending.push(Reverse((depth + 1 + estimate, depth + 1, target)));
+                    }
+                }
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+impl PetriNet {
+    /// Firing sequence from the initial marking to a marking satisfying `predicate`, if there is
+    /// one. Searches breadth first, see `Reachability` for other strategies and limits.
+    pub fn can_reach(
+        &self,
+        predicate: impl Fn(&Marking) -> bool,
+    ) -> Result<Option<Path>, AnalysisError> {
+        Reachability::new(self).can_reach(predicate)
+    }
+
+    /// Firing sequence from the initial marking to a marking covering `target`, if there is one
+    pub fn can_cover(&self, target: &Marking) -> Result<Option<Path>, AnalysisError> {
+        Reachability::new(self).can_cover(target)
+    }
+}
@@ -0,0 +1,737 @@
+//! Structural reductions in the style of Berthelot, shrinking a net before analysing it.
+//!
+//! Each rule removes or fuses nodes while preserving liveness, boundedness and the tokens that
+//! can be reached in the places asked to be kept. The rules are applied until none applies:
+//!
+//! - series places: a transition moving a token from a place it is the only consumer of to
+//!   another place is removed, and both places are fused into one holding the sum of their tokens,
+//! - series transitions: an unmarked place with a single producer and a single consumer, of which
+//!   it is the only input, is removed and both transitions are fused into one firing them in turn,
+//! - parallel places and transitions: a node with exactly the same connections as another one,
+//!   and the same tokens for places, is removed,
+//! - self-loop places and transitions: a place only ever giving back the tokens it lent, holding
+//!   enough of them, and a transition giving back every token it takes, either a single token of
+//!   a single place or tokens such self-loop places always hold, are removed,
+//! - implicit places: a place never holding fewer tokens than another one needing at least as
+//!   much, and changing by the same amount on every firing, is removed.
+//!
+//! Only places and transitions with normal connections are reduced. Kept places are never removed
+//! or fused, and transitions touching them are never fused, so that every token count of a kept
+//! place reachable in the net is reachable in the reduced net, and conversely.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use super::AnalysisError;
+use crate::net::{Connection, ConnectionType, IndexedNet, InputFrom, PetriNet, Place, Transition};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rule {
+    SeriesPlaces,
+    SeriesTransitions,
+    ParallelPlaces,
+    ParallelTransitions,
+    SelfLoopPlace,
+    SelfLoopTransition,
+    ImplicitPlace,
+}
+
+/// A place or transition of the net being reduced
+#[derive(Clone, Debug)]
+struct Node {
+    name: String,
+    /// Original nodes it stands for
+    origins: Vec<usize>,
+    /// Original node it still is unchanged, if any
+    original: Option<usize>,
+    alive: bool,
+    /// Touched by an inhibitor or reset connection
+    frozen: bool,
+}
+
+impl Node {
+    fn new(name: &str, index: usize) -> Self {
+        Self {
+            name: name.to_string(),
+            origins: vec![index],
+            original: Some(index),
+            alive: true,
+            frozen: false,
+        }
+    }
+}
+
+/// A reduced net, with the original nodes behind each of its nodes
+pub struct Reduction {
+    net: PetriNet,
+    places: Vec<Vec<usize>>,
+    transitions: Vec<Vec<usize>>,
+    removed_places: Vec<usize>,
+    removed_transitions: Vec<usize>,
+    rules: Vec<Rule>,
+}
+
+/// Tokens and `(transition, pre, post)` weights of a place, equal for parallel places
+type PlaceKey = (i32, Vec<(usize, i32, i32)>);
+/// Input and output weights of a transition, equal for parallel transitions
+type TransitionKey = (Vec<(usize, i32)>, Vec<(usize, i32)>);
+
+/// Net being reduced, with normal connections by place for every transition.
+///
+/// Rules are only tried on pending nodes, which are the nodes next to a change, and parallel nodes
+/// are found through their keys, which are computed again for every changed node.
+struct Reducer {
+    places: Vec<Node>,
+    tokens: Vec<i32>,
+    transitions: Vec<Node>,
+    pre: Vec<BTreeMap<usize, i32>>,
+    post: Vec<BTreeMap<usize, i32>>,
+    /// Transitions putting tokens into each place
+    producers: Vec<BTreeSet<usize>>,
+    /// Transitions taking tokens from each place
+    consumers: Vec<BTreeSet<usize>>,
+    /// Places whose tokens must stay observable
+    kept: Vec<bool>,
+    pending_places: BTreeSet<usize>,
+    pending_transitions: BTreeSet<usize>,
+    /// Nodes whose key has to be computed again
+    stale_places: Vec<usize>,
+    stale_transitions: Vec<usize>,
+    place_keys: Vec<Option<PlaceKey>>,
+    transition_keys: Vec<Option<TransitionKey>>,
+    places_by_key: HashMap<PlaceKey, BTreeSet<usize>>,
+    transitions_by_key: HashMap<TransitionKey, BTreeSet<usize>>,
+    removed_places: Vec<usize>,
+    removed_transitions: Vec<usize>,
+    rules: Vec<Rule>,
+}
+
+impl Reduction {
+    /// Reduces `net` as long as a rule applies, never removing the places of `kept`. Fails with
+    /// `AnalysisError::UnknownPlace` if one of them is not a place of `net`.
+    pub fn new(net: &PetriNet, kept: &[usize]) -> Result<Self, AnalysisError> {
+        let indexed = IndexedNet::new(net);
+        if let Some(place) = kept.iter().find(|p| **p >= indexed.place_count()) {
+            return Err(AnalysisError::UnknownPlace {
+                place: *place,
+                places: indexed.place_count(),
+            });
+        }
+
+        let marking = net.initial_marking();
+        let mut reducer = Reducer {
+            places: net
+                .places()
+                .iter()
+                .enumerate()
+                .map(|(index, place)| Node::new(place.name(), index))
+                .collect(),
+            tokens: marking.iter().copied().collect(),
+            transitions: net
+                .transitions()
+                .iter()
+                .enumerate()
+                .map(|(index, transition)| Node::new(transition.name(), index))
+                .collect(),
+            pre: vec![BTreeMap::new(); indexed.transition_count()],
+            post: vec![BTreeMap::new(); indexed.transition_count()],
+            producers: vec![BTreeSet::new(); indexed.place_count()],
+            consumers: vec![BTreeSet::new(); indexed.place_count()],
+            kept: vec![false; indexed.place_count()],
+            pending_places: (0..indexed.place_count()).collect(),
+            pending_transitions: (0..indexed.transition_count()).collect(),
+            stale_places: (0..indexed.place_count()).collect(),
+            stale_transitions: (0..indexed.transition_count()).collect(),
+            place_keys: vec![None; indexed.place_count()],
+            transition_keys: vec![None; indexed.transition_count()],
+            places_by_key: HashMap::new(),
+            transitions_by_key: HashMap::new(),
+            removed_places: vec![],
+            removed_transitions: vec![],
+            rules: vec![],
+        };
+
+        for place in kept.iter() {
+            reducer.kept[*place] = true;
+        }
+        for transition in 0..indexed.transition_count() {
+            for con in indexed.pre(transition).iter() {
+                match con.con_type {
+                    ConnectionType::NORMAL => {
+                        *reducer.pre[transition].entry(con.place).or_default() += con.weight;
+                        reducer.consumers[con.place].insert(transition);
+                    }
+                    _ => {
+                        reducer.places[con.place].frozen = true;
+                        reducer.transitions[transition].frozen = true;
+                    }
+                }
+            }
+            for con in indexed.post(transition).iter() {
+                *reducer.post[transition].entry(con.place).or_default() += con.weight;
+                reducer.producers[con.place].insert(transition);
+            }
+        }
+
+        // Every rule removes a node, so the worklist empties after at most as many changes
+        loop {
+            reducer.index_stale();
+            if let Some(place) = reducer.pending_places.pop_first() {
+                reducer.reduce_place(place);
+            } else if let Some(transition) = reducer.pending_transitions.pop_first() {
+                reducer.reduce_transition(transition);
+            } else {
+                break;
+            }
+        }
+
+        Ok(reducer.build(net))
+    }
+
+    pub fn net(&self) -> &PetriNet {
+        &self.net
+    }
+
+    /// Original places whose tokens add up to those of `place` in the reduced net
+    pub fn place_origins(&self, place: usize) -> &[usize] {
+        &self.places[place]
+    }
+
+    /// Original transitions a firing of `transition` in the reduced net stands for, in order
+    pub fn transition_origins(&self, transition: usize) -> &[usize] {
+        &self.transitions[transition]
+    }
+
+    /// Reduced place holding the tokens of the original `place`, if it was not removed
+    pub fn place(&self, place: usize) -> Option<usize> {
+        self.places
+            .iter()
+            .position(|origins| origins.contains(&place))
+    }
+
+    /// Original places removed as parallel, self-loop or implicit places
+    pub fn removed_places(&self) -> &[usize] {
+        &self.removed_places
+    }
+
+    /// Original transitions removed as parallel or self-loop transitions, or while fusing series
+    /// places
+    pub fn removed_transitions(&self) -> &[usize] {
+        &self.removed_transitions
+    }
+
+    /// Rules applied, in order
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+}
+
+impl Reducer {
+    /// Whether `place` must stay as it is
+    fn is_fixed(&self, place: usize) -> bool {
+        self.places[place].frozen || self.kept[place]
+    }
+
+    /// Whether `transition` changes the tokens of a kept place
+    fn touches_kept(&self, transition: usize) -> bool {
+        self.pre[transition]
+            .keys()
+            .chain(self.post[transition].keys())
+            .any(|place| self.kept[*place])
+    }
+
+    /// Transitions connected to `place`, in order
+    fn neighbours(&self, place: usize) -> impl Iterator<Item = usize> + '_ {
+        self.producers[place].union(&self.consumers[place]).copied()
+    }
+
+    /// Weights of the connections from and to `place`, by connected transition
+    fn column(&self, place: usize) -> Vec<(usize, i32, i32)> {
+        self.neighbours(place)
+            .map(|t| {
+                (
+                    t,
+                    self.pre[t].get(&place).copied().unwrap_or(0),
+                    self.post[t].get(&place).copied().unwrap_or(0),
+                )
+            })
+            .collect()
+    }
+
+    /// Makes `place` pending again with its transitions, as they may reduce now
+    fn touch_place(&mut self, place: usize) {
+        if let Some(key) = self.place_keys[place].take() {
+            if let Some(bucket) = self.places_by_key.get_mut(&key) {
+                bucket.remove(&place);
+                if bucket.is_empty() {
+                    self.places_by_key.remove(&key);
+                }
+            }
+        }
+        if self.places[place].alive {
+            self.stale_places.push(place);
+            self.pending_places.insert(place);
+            let neighbours: Vec<usize> = self.neighbours(place).collect();
+            self.pending_transitions.extend(neighbours);
+        }
+    }
+
+    /// Makes `transition` pending again with its places, as they may reduce now
+    fn touch_transition(&mut self, transition: usize) {
+        if let Some(key) = self.transition_keys[transition].take() {
+            if let Some(bucket) = self.transitions_by_key.get_mut(&key) {
+                bucket.remove(&transition);
+                if bucket.is_empty() {
+                    self.transitions_by_key.remove(&key);
+                }
+            }
+        }
+        if self.transitions[transition].alive {
+            self.stale_transitions.push(transition);
+            self.pending_transitions.insert(transition);
+            let places = self.pre[transition]
+                .keys()
+                .chain(self.post[transition].keys());
+            self.pending_places.extend(places);
+        }
+    }
+
+    /// Computes the keys of the nodes changed since the last call
+    fn index_stale(&mut self) {
+        while let Some(place) = self.stale_places.pop() {
+            if self.places[place].alive && self.place_keys[place].is_none() {
+                self.index_place(place);
+            }
+        }
+        while let Some(transition) = self.stale_transitions.pop() {
+            if self.transitions[transition].alive && self.transition_keys[transition].is_none() {
+                self.index_transition(transition);
+            }
+        }
+    }
+
+    fn index_place(&mut self, place: usize) {
+        let key = (self.tokens[place], self.column(place));
+        self.places_by_key
+            .entry(key.clone())
+            .or_default()
+            .insert(place);
+        self.place_keys[place] = Some(key);
+    }
+
+    fn index_transition(&mut self, transition: usize) {
+        let key = (
+            self.pre[transition].clone().into_iter().collect(),
+            self.post[transition].clone().into_iter().collect(),
+        );
+        self.transitions_by_key
+            .entry(key.clone())
+            .or_default()
+            .insert(transition);
+        self.transition_keys[transition] = Some(key);
+    }
+
+    /// Sets the weight of the connection from `place` to `transition`, removing it at 0
+    fn set_pre(&mut self, transition: usize, place: usize, weight: i32) {
+        if weight == 0 {
+            self.pre[transition].remove(&place);
+            self.consumers[place].remove(&transition);
+        } else {
+            self.pre[transition].insert(place, weight);
+            self.consumers[place].insert(transition);
+        }
+        self.touch_place(place);
+        self.touch_transition(transition);
+    }
+
+    /// Sets the weight of the connection from `transition` to `place`, removing it at 0
+    fn set_post(&mut self, transition: usize, place: usize, weight: i32) {
+        if weight == 0 {
+            self.post[transition].remove(&place);
+            self.producers[place].remove(&transition);
+        } else {
+            self.post[transition].insert(place, weight);
+            self.producers[place].insert(transition);
+        }
+        self.touch_place(place);
+        self.touch_transition(transition);
+    }
+
+    fn remove_place(&mut self, place: usize) {
+        for transition in self.neighbours(place).collect::<Vec<_>>() {
+            self.set_pre(transition, place, 0);
+            self.set_post(transition, place, 0);
+        }
+        self.places[place].alive = false;
+        self.touch_place(place);
+    }
+
+    fn remove_transition(&mut self, transition: usize) {
+        let pre: Vec<usize> = self.pre[transition].keys().copied().collect();
+        let post: Vec<usize> = self.post[transition].keys().copied().collect();
+        for place in pre {
+            self.set_pre(transition, place, 0);
+        }
+        for place in post {
+            self.set_post(transition, place, 0);
+        }
+        self.transitions[transition].alive = false;
+        self.touch_transition(transition);
+    }
+
+    fn reduce_transition(&mut self, transition: usize) {
+        if !self.transitions[transition].alive {
+            return;
+        }
+
+        // A rule may leave the transition itself, e.g. when removing a parallel one
+        if self.series_places(transition)
+            || self.parallel_transitions(transition)
+            || self.self_loop_transition(transition)
+        {
+            self.touch_transition(transition);
+        }
+    }
+
+    fn reduce_place(&mut self, place: usize) {
+        if !self.places[place].alive {
+            return;
+        }
+
+        if self.series_transitions(place)
+            || self.parallel_places(place)
+            || self.self_loop_place(place)
+            || self.implicit_place(place)
+        {
+            self.touch_place(place);
+        }
+    }
+
+    fn series_places(&mut self, transition: usize) -> bool {
+        if self.transitions[transition].frozen
+            || self.pre[transition].len() != 1
+            || self.post[transition].len() != 1
+        {
+            return false;
+        }
+        let (input, in_weight) = self.pre[transition].iter().next().unwrap();
+        let (output, out_weight) = self.post[transition].iter().next().unwrap();
+        let (input, output) = (*input, *output);
+        if input == output
+            || *in_weight != 1
+            || *out_weight != 1
+            || self.is_fixed(input)
+            || self.is_fixed(output)
+            || self.consumers[input].len() != 1
+        {
+            return false;
+        }
+
+        self.removed_transitions
+            .extend(self.transitions[transition].origins.iter().copied());
+        self.remove_transition(transition);
+        for other in self.neighbours(output).collect::<Vec<_>>() {
+            if let Some(weight) = self.pre[other].get(&output).copied() {
+                let merged = self.pre[other].get(&input).copied().unwrap_or(0) + weight;
+                self.set_pre(other, output, 0);
+                self.set_pre(other, input, merged);
+            }
+            if let Some(weight) = self.post[other].get(&output).copied() {
+                let merged = self.post[other].get(&input).copied().unwrap_or(0) + weight;
+                self.set_post(other, output, 0);
+                self.set_post(other, input, merged);
+            }
+        }
+        self.tokens[input] += self.tokens[output];
+        let output_node = self.places[output].clone();
+        let input_node = &mut self.places[input];
+        input_node.name = format!("{}+{}", input_node.name, output_node.name);
+        input_node.origins.extend(output_node.origins);
+        input_node.original = None;
+        self.places[output].alive = false;
+        self.touch_place(output);
+        self.touch_place(input);
+        self.rules.push(Rule::SeriesPlaces);
+        true
+    }
+
+    fn series_transitions(&mut self, place: usize) -> bool {
+        if self.is_fixed(place) || self.tokens[place] != 0 {
+            return false;
+        }
+        let (Some(first), Some(second)) = (
+            self.producers[place].first().copied(),
+            self.consumers[place].first().copied(),
+        ) else {
+            return false;
+        };
+        if self.producers[place].len() != 1
+            || self.consumers[place].len() != 1
+            || first == second
+            || self.transitions[first].frozen
+            || self.transitions[second].frozen
+            || self.touches_kept(first)
+            || self.touches_kept(second)
+            || self.post[first][&place] != 1
+            || self.pre[second].len() != 1
+            || self.pre[second][&place] != 1
+        {
+            return false;
+        }
+
+        let produced = self.post[second].clone();
+        self.remove_transition(second);
+        self.set_post(first, place, 0);
+        for (output, weight) in produced {
+            let merged = self.post[first].get(&output).copied().unwrap_or(0) + weight;
+            self.set_post(first, output, merged);
+        }
+        self.places[place].alive = false;
+        self.touch_place(place);
+
+        let second_node = self.transitions[second].clone();
+        let first_node = &mut self.transitions[first];
+        first_node.name = format!("{}+{}", first_node.name, second_node.name);
+        first_node.origins.extend(second_node.origins);
+        first_node.original = None;
+        self.touch_transition(first);
+        self.rules.push(Rule::SeriesTransitions);
+        true
+    }
+
+    /// Removes a place with the same key as `place`, or `place` itself, keeping the first one
+    /// that may stay
+    fn parallel_places(&mut self, place: usize) -> bool {
+        let Some(key) = &self.place_keys[place] else {
+            return false;
+        };
+        let pair = self.places_by_key[key]
+            .iter()
+            .filter(|other| **other != place)
+            .flat_map(|other| {
+                [
+                    (place.min(*other), place.max(*other)),
+                    (place.max(*other), place.min(*other)),
+                ]
+            })
+            .find(|(kept, removed)| !self.is_fixed(*removed) && !self.places[*kept].frozen);
+        let Some((_, removed)) = pair else {
+            return false;
+        };
+
+        self.removed_places
+            .extend(self.places[removed].origins.iter().copied());
+        self.remove_place(removed);
+        self.rules.push(Rule::ParallelPlaces);
+        true
+    }
+
+    /// Removes a transition with the same key as `transition`, or `transition` itself
+    fn parallel_transitions(&mut self, transition: usize) -> bool {
+        let Some(key) = &self.transition_keys[transition] else {
+            return false;
+        };
+        if self.transitions[transition].frozen {
+            return false;
+        }
+        let other = self.transitions_by_key[key]
+            .iter()
+            .copied()
+            .find(|other| *other != transition && !self.transitions[*other].frozen);
+        let Some(other) = other else {
+            return false;
+        };
+
+        let removed = transition.max(other);
+        self.removed_transitions
+            .extend(self.transitions[removed].origins.iter().copied());
+        self.remove_transition(removed);
+        self.rules.push(Rule::ParallelTransitions);
+        true
+    }
+
+    /// Whether every transition gives back the tokens it takes from `place`, which holds enough
+    /// of them for all, so that it never changes nor disables anything
+    fn always_holds(&self, place: usize) -> bool {
+        self.column(place)
+            .iter()
+            .all(|(_, pre, post)| pre == post && *pre <= self.tokens[place])
+    }
+
+    fn self_loop_place(&mut self, place: usize) -> bool {
+        if self.is_fixed(place) || !self.always_holds(place) {
+            return false;
+        }
+
+        self.removed_places
+            .extend(self.places[place].origins.iter().copied());
+        self.remove_place(place);
+        self.rules.push(Rule::SelfLoopPlace);
+        true
+    }
+
+    /// Removes `transition` if it gives back every token it takes and is enabled whenever one of
+    /// its places is marked, or always. A heavier self-loop may be dead, which removing it would
+    /// hide.
+    fn self_loop_transition(&mut self, transition: usize) -> bool {
+        let pre = &self.pre[transition];
+        if self.transitions[transition].frozen || *pre != self.post[transition] {
+            return false;
+        }
+        let single = pre.len() == 1 && pre.values().all(|weight| *weight == 1);
+        if !single && !pre.keys().all(|place| self.always_holds(*place)) {
+            return false;
+        }
+
+        self.removed_transitions
+            .extend(self.transitions[transition].origins.iter().copied());
+        self.remove_transition(transition);
+        self.rules.push(Rule::SelfLoopTransition);
+        true
+    }
+
+    /// Whether `implicit` never holds fewer tokens than `other` needs, by at least as much as
+    /// `other` holds: the difference never changes, and `other` always disables first
+    fn is_implicit(&self, implicit: usize, other: usize) -> bool {
+        if self.is_fixed(implicit)
+            || self.places[other].frozen
+            || self.tokens[implicit] < self.tokens[other]
+        {
+            return false;
+        }
+
+        let weights = |place: usize, transition: usize| {
+            (
+                self.pre[transition].get(&place).copied().unwrap_or(0),
+                self.post[transition].get(&place).copied().unwrap_or(0),
+            )
+        };
+        self.neighbours(implicit)
+            .chain(self.neighbours(other))
+            .all(|transition| {
+                let (pre, post) = weights(implicit, transition);
+                let (other_pre, other_post) = weights(other, transition);
+                pre <= other_pre && post - pre == other_post - other_pre
+            })
+    }
+
+    /// Removes `place` if it is implicit, or a place it makes implicit. Both share a transition,
+    /// unless the implicit place has none and is a self-loop place.
+    fn implicit_place(&mut self, place: usize) -> bool {
+        let candidates: BTreeSet<usize> = self
+            .neighbours(place)
+            .flat_map(|t| self.pre[t].keys().chain(self.post[t].keys()))
+            .copied()
+            .filter(|other| *other != place)
+            .collect();
+        let implicit = candidates.iter().find_map(|other| {
+            if self.is_implicit(place, *other) {
+                Some(place)
+            } else if self.is_implicit(*other, place) {
+                Some(*other)
+            } else {
+                None
+            }
+        });
+        let Some(implicit) = implicit else {
+            return false;
+        };
+
+        self.removed_places
+            .extend(self.places[implicit].origins.iter().copied());
+        self.remove_place(implicit);
+        self.rules.push(Rule::ImplicitPlace);
+        true
+    }
+
+    fn build(self, original: &PetriNet) -> Reduction {
+        let mut net = PetriNet::new();
+        let mut place_index = vec![None; self.places.len()];
+        let mut places = vec![];
+        for (index, node) in self.places.iter().enumerate() {
+            if !node.alive {
+                continue;
+            }
+            let place = match node.original {
+                Some(original_index) => original.places()[original_index].renamed(&node.name),
+                None => Place::new(&node.name).with_tokens(self.tokens[index]),
+            };
+            place_index[index] = Some(places.len());
+            places.push(node.origins.clone());
+            net.add_place(place);
+        }
+
+        let mut transition_index = vec![None; self.transitions.len()];
+        let mut transitions = vec![];
+        for (index, node) in self.transitions.iter().enumerate() {
+            if !node.alive {
+                continue;
+            }
+            let transition = match node.original {
+                Some(original_index) => original.transitions()[original_index].renamed(&node.name),
+                None => Transition::new(&node.name),
+            };
+            transition_index[index] = Some(transitions.len());
+            transitions.push(node.origins.clone());
+            net.add_transition(transition);
+        }
+
+        let connect = |net: &mut PetriNet, place: usize, transition: usize, weight, from, kind| {
+            let (Some(place), Some(transition)) =
+                (place_index[place], transition_index[transition])
+            else {
+                return;
+            };
+            let place = net.places()[place].clone();
+            let transition = net.transitions()[transition].clone();
+            net.add_connection(Connection::new(place, transition, weight, from, kind));
+        };
+        for transition in 0..self.transitions.len() {
+            for (place, weight) in self.pre[transition].iter() {
+                connect(
+                    &mut net,
+                    *place,
+                    transition,
+                    *weight,
+                    InputFrom::PLACE,
+                    ConnectionType::NORMAL,
+                );
+            }
+            for (place, weight) in self.post[transition].iter() {
+                connect(
+                    &mut net,
+                    *place,
+                    transition,
+                    *weight,
+                    InputFrom::TRANSITION,
+                    ConnectionType::NORMAL,
+                );
+            }
+        }
+
+        // Inhibitor and reset connections only touch frozen nodes, which are still there
+        let indexed = IndexedNet::new(original);
+        for transition in 0..indexed.transition_count() {
+            for con in indexed.pre(transition).iter() {
+                if con.con_type != ConnectionType::NORMAL {
+                    connect(
+                        &mut net,
+                        con.place,
+                        transition,
+                        con.weight,
+                        InputFrom::PLACE,
+                        con.con_type,
+                    );
+                }
+            }
+        }
+
+        Reduction {
+            net,
+            places,
+            transitions,
+            removed_places: self.removed_places,
+            removed_transitions: self.removed_transitions,
+            rules: self.rules,
+        }
+    }
+}
@@ -0,0 +1,253 @@
+//! A small dense simplex solver, with branch and bound for integer solutions.
+//!
+//! Problems come from state equations of nets, with a few dozen variables at most, so a dense
+//! tableau and Bland's rule, which never cycles, are good enough.
+
+const EPSILON: f64 = 1e-9;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Sense {
+    LessEqual,
+    Equal,
+    GreaterEqual,
+}
+
+/// `coefficients · x <sense> rhs`
+#[derive(Clone, Debug)]
+pub(super) struct Constraint {
+    pub(super) coefficients: Vec<f64>,
+    pub(super) sense: Sense,
+    pub(super) rhs: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(super) enum Outcome {
+    Optimal { value: f64, solution: Vec<f64> },
+    Infeasible,
+    Unbounded,
+}
+
+/// Tableau in canonical form: the columns of `basis` form an identity in `rows`
+struct Tableau {
+    /// Coefficients of every row, with the right-hand side last
+    rows: Vec<Vec<f64>>,
+    basis: Vec<usize>,
+}
+
+impl Tableau {
+    fn pivot(&mut self, row: usize, column: usize) {
+        let factor = self.rows[row][column];
+        for value in self.rows[row].iter_mut() {
+            *value /= factor;
+        }
+
+        let pivot_row = self.rows[row].clone();
+        for (index, other) in self.rows.iter_mut().enumerate() {
+            let factor = other[column];
+            if index != row && factor.abs() > EPSILON {
+                for (value, pivot) in other.iter_mut().zip(pivot_row.iter()) {
+                    *value -= factor * pivot;
+                }
+            }
+        }
+        self.basis[row] = column;
+    }
+
+    /// Maximizes `cost` over the first `columns` columns, `false` if it is unbounded
+    fn optimize(&mut self, cost: &[f64], columns: usize) -> bool {
+        loop {
+            // Bland's rule: the first improving column, then the first basic variable leaving
+            let reduced = |tableau: &Tableau, column: usize| {
+                cost[column]
+                    - tableau
+                        .rows
+                        .iter()
+                        .zip(tableau.basis.iter())
+                        .map(|(row, basic)| cost[*basic] * row[column])
+                        .sum::<f64>()
+            };
+            let Some(column) = (0..columns).find(|column| reduced(self, *column) > EPSILON) else {
+                return true;
+            };
+
+            let mut leaving: Option<(usize, f64)> = None;
+            for (row, values) in self.rows.iter().enumerate() {
+                if values[column] <= EPSILON {
+                    continue;
+                }
+                let ratio = values[values.len() - 1] / values[column];
+                let better = match leaving {
+                    None => true,
+                    Some((best, best_ratio)) => {
+                        ratio < best_ratio - EPSILON
+                            || (ratio < best_ratio + EPSILON && self.basis[row] < self.basis[best])
+                    }
+                };
+                if better {
+                    leaving = Some((row, ratio));
+                }
+            }
+
+            match leaving {
+                Some((row, _)) => self.pivot(row, column),
+                None => return false,
+            }
+        }
+    }
+
+    fn value(&self, column: usize) -> f64 {
+        self.basis
+            .iter()
+            .position(|basic| *basic == column)
+            .map_or(0.0, |row| self.rows[row][self.rows[row].len() - 1])
+    }
+}
+
+/// Maximizes `objective · x` subject to `constraints` and `x >= 0`
+pub(super) fn maximize(objective: &[f64], constraints: &[Constraint]) -> Outcome {
+    let variables = objective.len();
+    let slacks = constraints
+        .iter()
+        .filter(|c| c.sense != Sense::Equal)
+        .count();
+    let width = variables + slacks + constraints.len();
+
+    // Columns: variables, slack or surplus variables, one artificial variable per row
+    let mut tableau = Tableau {
+        rows: vec![],
+        basis: vec![],
+    };
+    let mut slack = variables;
+    for (index, constraint) in constraints.iter().enumerate() {
+        let mut row = vec![0.0; width + 1];
+        row[..variables].copy_from_slice(&constraint.coefficients);
+        row[width] = constraint.rhs;
+        match constraint.sense {
+            Sense::LessEqual => row[slack] = 1.0,
+            Sense::GreaterEqual => row[slack] = -1.0,
+            Sense::Equal => {}
+        }
+        if constraint.sense != Sense::Equal {
+            slack += 1;
+        }
+        if row[width] < 0.0 {
+            for value in row.iter_mut() {
+                *value = -*value;
+            }
+        }
+
+        let artificial = variables + slacks + index;
+        row[artificial] = 1.0;
+        tableau.rows.push(row);
+        tableau.basis.push(artificial);
+    }
+
+    // Phase one: drive the artificial variables to zero
+    let mut cost = vec![0.0; width];
+    for value in cost[variables + slacks..].iter_mut() {
+        *value = -1.0;
+    }
+    tableau.optimize(&cost, width);
+    let infeasibility: f64 = (variables + slacks..width)
+        .map(|column| tableau.value(column))
+        .sum();
+    if infeasibility > EPSILON * 1e3 {
+        return Outcome::Infeasible;
+    }
+
+    // Artificial variables still basic at zero leave the basis, or their row was redundant
+    let mut row = 0;
+    while row < tableau.rows.len() {
+        if tableau.basis[row] >= variables + slacks {
+            match (0..variables + slacks).find(|c| tableau.rows[row][*c].abs() > EPSILON) {
+                Some(column) => tableau.pivot(row, column),
+                None => {
+                    tableau.rows.remove(row);
+                    tableau.basis.remove(row);
+                    continue;
+                }
+            }
+        }
+        row += 1;
+    }
+
+    // Phase two, never letting an artificial variable back in
+    let mut cost = vec![0.0; width];
+    cost[..variables].copy_from_slice(objective);
+    if !tableau.optimize(&cost, variables + slacks) {
+        return Outcome::Unbounded;
+    }
+
+    let solution: Vec<f64> = (0..variables).map(|column| tableau.value(column)).collect();
+    let value = solution
+        .iter()
+        .zip(objective.iter())
+        .map(|(x, c)| x * c)
+        .sum();
+    Outcome::Optimal { value, solution }
+}
+
+/// Maximizes `objective · x` over integer `x >= 0`, branching at most `limit` times.
+///
+/// Returns `None` once the limit is reached, with what was found so far being no proof.
+pub(super) fn maximize_integer(
+    objective: &[f64],
+    constraints: &[Constraint],
+    limit: usize,
+) -> Option<Outcome> {
+    let mut best: Option<(f64, Vec<f64>)> = None;
+    let mut unbounded = false;
+    let mut pending = vec![constraints.to_vec()];
+    let mut branches = 0;
+
+    while let Some(constraints) = pending.pop() {
+        let (value, solution) = match maximize(objective, &constraints) {
+            Outcome::Infeasible => continue,
+            Outcome::Unbounded => {
+                // With rational data this branch is unbounded too unless it has no integer point, and
+                // assuming it is errs on the safe side for bounds
+                unbounded = true;
+                continue;
+            }
+            Outcome::Optimal { value, solution } => (value, solution),
+        };
+        if best
+            .as_ref()
+            .is_some_and(|(best, _)| value <= best + EPSILON)
+        {
+            continue;
+        }
+
+        let fractional = solution.iter().position(|x| (x - x.round()).abs() > 1e-6);
+        let Some(variable) = fractional else {
+            let solution = solution.iter().map(|x| x.round()).collect();
+            best = Some((value, solution));
+            continue;
+        };
+
+        branches += 1;
+        if branches > limit {
+            return None;
+        }
+        let mut unit = vec![0.0; objective.len()];
+        unit[variable] = 1.0;
+        for (sense, rhs) in [
+            (Sense::LessEqual, solution[variable].floor()),
+            (Sense::GreaterEqual, solution[variable].ceil()),
+        ] {
+            let mut branch = constraints.clone();
+            branch.push(Constraint {
+                coefficients: unit.clone(),
+                sense,
+                rhs,
+            });
+            pending.push(branch);
+        }
+    }
+
+    Some(match (best, unbounded) {
+        (_, true) => Outcome::Unbounded,
+        (Some((value, solution)), false) => Outcome::Optimal { value, solution },
+        (None, false) => Outcome::Infeasible,
+    })
+}
@@ -0,0 +1,205 @@
+//! Over-approximate reachability through the state equation `M' = M0 + C·σ`.
+//!
+//! Firing a sequence in which every transition `t` occurs `σ[t]` times changes the marking by the
+//! incidence matrix `C` times `σ`. If no vector of non-negative integers solves the equation for a
+//! marking, that marking is unreachable, which integer linear programming decides without
+//! exploring a single marking. The converse does not hold: a solution may not be fireable in any
+//! order, so a solution only means the marking may be reachable.
+//!
+//! Inhibitor connections only disable transitions and are ignored, which keeps the answers sound.
+//! A reset connection empties its place whatever it holds, so places with one are left out of the
+//! equation.
+//!
+//! ```
+//! use petri_engine::analysis::{StateEquation, Verdict};
+//! use petri_engine::petri_net;
+//!
+//! let net = petri_net! {
+//!     places => [Start<1>, End],
+//!     transitions => [Double],
+//!     connections => [Start -> Double, (2) Double -> End]
+//! };
+//!
+//! let equation = StateEquation::new(&net);
+//! assert_eq!(equation.can_reach(&vec![0, 2].into()), Ok(Verdict::Solution(vec![1])));
+//! assert_eq!(equation.can_reach(&vec![0, 1].into()), Ok(Verdict::Unreachable));
+//! assert_eq!(equation.bound(1), Ok(Some(2)));
+//! ```
+
+use super::simplex::{self, Constraint, Outcome, Sense};
+use super::AnalysisError;
+use crate::net::{ConnectionType, IndexedNet, Marking, PetriNet};
+
+/// Answer of the state equation to a reachability or coverability question
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    /// No firing sequence leads to the marking
+    Unreachable,
+    /// How many times each transition fires in a smallest solution. The marking may still be
+    /// unreachable, when no order of these firings is enabled.
+    Solution(Vec<u32>),
+    /// The branching limit was reached before an integer solution was found or ruled out
+    Unknown,
+}
+
+/// The incidence matrix of a net and the integer programs built on it
+#[derive(Clone, Debug)]
+pub struct StateEquation {
+    /// Tokens each transition adds to each place, one row per place
+    incidence: Vec<Vec<i32>>,
+    /// Places emptied by a reset connection, on which the equation says nothing
+    reset: Vec<bool>,
+    transitions: usize,
+    initial: Marking,
+    limit: usize,
+}
+
+impl StateEquation {
+    /// Equation from the initial marking of `net`, branching at most 10000 times per question
+    pub fn new(net: &PetriNet) -> Self {
+        let indexed = IndexedNet::new(net);
+        let mut reset = vec![false; indexed.place_count()];
+
+        // Effect of each transition on every place, as columns of the matrix
+        let columns: Vec<Vec<i32>> = (0..indexed.transition_count())
+            .map(|transition| {
+                let mut column = vec![0; indexed.place_count()];
+                for con in indexed.pre(transition).iter() {
+                    match con.con_type {
+                        ConnectionType::NORMAL => column[con.place] -= con.weight,
+                        ConnectionType::RESET => reset[con.place] = true,
+                        ConnectionType::INHIBITOR => {}
+                    }
+                }
+                for con in indexed.post(transition).iter() {
+                    column[con.place] += con.weight;
+                }
+                column
+            })
+            .collect();
+        let incidence = (0..indexed.place_count())
+            .map(|place| columns.iter().map(|column| column[place]).collect())
+            .collect();
+
+        Self {
+            incidence,
+            reset,
+            transitions: indexed.transition_count(),
+            initial: net.initial_marking(),
+            limit: 10_000,
+        }
+    }
+
+    pub fn with_initial_marking(mut self, marking: Marking) -> Self {
+        self.initial = marking;
+        self
+    }
+
+    /// Branches at most `limit` times on fractional solutions before giving up on a question
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Tokens each transition adds to each place, negative when it removes them, one row per place
+    pub fn incidence(&self) -> &[Vec<i32>] {
+        &self.incidence
+    }
+
+    /// Whether `M0 + C·σ = target` has a solution. Fails with `AnalysisError::MarkingLength`
+    /// unless `target` and the initial marking have one entry per place.
+    pub fn can_reach(&self, target: &Marking) -> Result<Verdict, AnalysisError> {
+        self.solve(target, Sense::Equal)
+    }
+
+    /// Whether `M0 + C·σ >= target` has a solution, failing like `can_reach`
+    pub fn can_cover(&self, target: &Marking) -> Result<Verdict, AnalysisError> {
+        self.solve(target, Sense::GreaterEqual)
+    }
+
+    /// An upper bound on the tokens of `place` in every reachable marking, or `None` if the
+    /// equation allows any number of them. The bound is exact within the branching limit, and
+    /// rounded down from the linear relaxation beyond it.
+    ///
+    /// Fails with `AnalysisError::UnknownPlace` if `place` is not a place of the net, and like
+    /// `can_reach` for the initial marking.
+    pub fn bound(&self, place: usize) -> Result<Option<i32>, AnalysisError> {
+        self.check_length(&self.initial)?;
+        if place >= self.incidence.len() {
+            return Err(AnalysisError::UnknownPlace {
+                place,
+                places: self.incidence.len(),
+            });
+        }
+        if self.reset[place] {
+            return Ok(None);
+        }
+
+        let objective = self.coefficients(place);
+        let constraints: Vec<Constraint> = self
+            .rows()
+            .map(|p| Constraint {
+                coefficients: self.coefficients(p),
+                sense: Sense::GreaterEqual,
+                rhs: -self.initial[p] as f64,
+            })
+            .collect();
+
+        let outcome = simplex::maximize_integer(&objective, &constraints, self.limit)
+            .unwrap_or_else(|| simplex::maximize(&objective, &constraints));
+        Ok(match outcome {
+            Outcome::Optimal { value, .. } => {
+                Some(self.initial[place] + (value + 1e-6).floor() as i32)
+            }
+            // The initial marking itself is a solution, so only an unbounded place is left
+            Outcome::Infeasible | Outcome::Unbounded => None,
+        })
+    }
+
+    fn solve(&self, target: &Marking, sense: Sense) -> Result<Verdict, AnalysisError> {
+        self.check_length(&self.initial)?;
+        self.check_length(target)?;
+
+        let constraints: Vec<Constraint> = self
+            .rows()
+            .map(|place| Constraint {
+                coefficients: self.coefficients(place),
+                sense,
+                rhs: (target[place] - self.initial[place]) as f64,
+            })
+            .collect();
+        // Firing as few transitions as possible
+        let objective = vec![-1.0; self.transitions];
+
+        Ok(
+            match simplex::maximize_integer(&objective, &constraints, self.limit) {
+                Some(Outcome::Optimal { solution, .. }) => {
+                    Verdict::Solution(solution.iter().map(|x| *x as u32).collect())
+                }
+                Some(Outcome::Infeasible) => Verdict::Unreachable,
+                // Minimizing a sum of non-negative variables is never unbounded
+                Some(Outcome::Unbounded) | None => Verdict::Unknown,
+            },
+        )
+    }
+
+    fn check_length(&self, marking: &Marking) -> Result<(), AnalysisError> {
+        if marking.len() != self.incidence.len() {
+            return Err(AnalysisError::MarkingLength {
+                expected: self.incidence.len(),
+                found: marking.len(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Places the equation constrains
+    fn rows(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.incidence.len()).filter(|place| !self.reset[*place])
+    }
+
+    fn coefficients(&self, place: usize) -> Vec<f64> {
+        self.incidence[place].iter().map(|c| *c as f64).collect()
+    }
+}
@@ -0,0 +1,236 @@
+use std::collections::{HashMap, VecDeque};
+
+use super::{AnalysisError, StubbornSets};
+use crate::net::{ConnectionType, IndexedNet, Marking, PetriNet};
+
+/// Reachability graph of a net: every marking reachable from the initial one, with an edge for
+/// every single transition firing.
+///
+/// States are numbered in breadth-first order, state 0 being the initial marking, so the path
+/// returned by `path_to` is always a shortest one.
+#[derive(Clone, Debug)]
+pub struct StateSpace {
+    markings: Vec<Marking>,
+    index: HashMap<Marking, usize>,
+    /// `(transition, target)` for every state
+    edges: Vec<Vec<(usize, usize)>>,
+    /// `(source, transition)` of the edge a state was discovered through
+    parents: Vec<Option<(usize, usize)>>,
+}
+
+impl StateSpace {
+    /// Explores every marking reachable from the initial marking of `net`
+    pub fn new(net: &PetriNet) -> Result<Self, AnalysisError> {
+        Self::explore(&IndexedNet::new(net), net.initial_marking(), None)
+    }
+
+    /// Explores every marking reachable from `initial`, giving up after `limit` markings. Fails with
+    /// `AnalysisError::MarkingLength` unless `initial` has one entry per place.
+    ///
+    /// When all connections are normal, exploration stops with `AnalysisError::Unbounded` as
+    /// soon as a marking strictly covers one of its predecessors, as the firings between them can
+    /// then be repeated forever. Inhibitor and reset connections break that argument, so nets
+    /// using them rely on `limit` to terminate.
+    pub fn explore(
+        net: &IndexedNet,
+        initial: Marking,
+        limit: Option<usize>,
+    ) -> Result<Self, AnalysisError> {
+        Self::search(net, initial, limit, None)
+    }
+
+    /// Explores the initial marking of `net` with partial-order reduction, keeping every deadlock
+    /// and every reachable combination of tokens in `visible` places
+    pub fn reduced(net: &PetriNet, visible: &[usize]) -> Result<Self, AnalysisError> {
+        let indexed = IndexedNet::new(net);
+        let stubborn = StubbornSets::new(&indexed).with_visible_places(visible);
+        Self::explore_reduced(&indexed, net.initial_marking(), None, &stubborn)
+    }
+
+    /// Like `explore`, only firing the transitions of a stubborn set in each marking.
+    ///
+    /// The result is a subgraph of the reachability graph: `deadlocks` finds every reachable
+    /// deadlock, and a marking with given tokens in the visible places of `stubborn` is present
+    /// exactly when one is reachable. Paths stay valid firing sequences of the net.
+    pub fn explore_reduced(
+        net: &IndexedNet,
+        initial: Marking,
+        limit: Option<usize>,
+        stubborn: &StubbornSets,
+    ) -> Result<Self, AnalysisError> {
+        Self::search(net, initial, limit, Some(stubborn))
+    }
+
+    fn search(
+        net: &IndexedNet,
+        initial: Marking,
+        limit: Option<usize>,
+        stubborn: Option<&StubbornSets>,
+    ) -> Result<Self, AnalysisError> {
+        if initial.len() != net.place_count() {
+            return Err(AnalysisError::MarkingLength {
+                expected: net.place_count(),
+                found: initial.len(),
+            });
+        }
+
+        let monotonic = (0..net.transition_count()).all(|transition| {
+            net.pre(transition)
+                .iter()
+                .all(|con| con.con_type == ConnectionType::NORMAL)
+        });
+
+        let mut space = Self {
+            markings: vec![initial.clone()],
+            index: HashMap::from([(initial, 0)]),
+            edges: vec![vec![]],
+            parents: vec![None],
+        };
+        let mut pending = VecDeque::from([0]);
+
+        while let Some(state) = pending.pop_front() {
+            let enabled = net.enabled(&space.markings[state]);
+            let mut firing = match stubborn {
+                Some(stubborn) => stubborn.stubborn(net, &space.markings[state], &enabled),
+                None => enabled.clone(),
+            };
+            // A reduced firing closing a cycle could postpone the other transitions forever
+            if firing.len() < enabled.len()
+                && firing.iter().any(|transition| {
+                    net.successor(*transition, &space.markings[state])
+                        .is_some_and(|next| space.index.contains_key(&next))
+                })
+            {
+                firing = enabled;
+            }
+
+            for transition in firing {
+                let mut next = space.markings[state].clone();
+                net.fire(transition, &mut next);
+
+                let target = match space.index.get(&next) {
+                    Some(target) => *target,
+                    None => {
+                        if monotonic && space.covers_ancestor(state, &next) {
+                            let mut firings = space.path_to(state);
+                            firings.push(transition);
+                            return Err(AnalysisError::Unbounded {
+                                marking: next,
+                                firings,
+                            });
+                        }
+                        if limit.is_some_and(|limit| space.markings.len() >= limit) {
+                            return Err(AnalysisError::StateLimit(space.markings.len()));
+                        }
+
+                        let target = space.markings.len();
+                        space.index.insert(next.clone(), target);
+                        space.markings.push(next);
+                        space.edges.push(vec![]);
+                        space.parents.push(Some((state, transition)));
+                        pending.push_back(target);
+                        target
+                    }
+                };
+
+                space.edges[state].push((transition, target));
+            }
+        }
+
+        Ok(space)
+    }
+
+    /// Number of reachable markings
+    pub fn len(&self) -> usize {
+        self.markings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.markings.is_empty()
+    }
+
+    pub fn markings(&self) -> &[Marking] {
+        &self.markings
+    }
+
+    pub fn marking(&self, state: usize) -> &Marking {
+        &self.markings[state]
+    }
+
+    /// State holding `marking`, if it is reachable
+    pub fn state(&self, marking: &Marking) -> Option<usize> {
+        self.index.get(marking).copied()
+    }
+
+    /// `(transition, target)` of every firing possible in `state`
+    pub fn successors(&self, state: usize) -> &[(usize, usize)] {
+        &self.edges[state]
+    }
+
+    /// `(source, transition)` of every firing leading to each state
+    pub fn predecessors(&self) -> Vec<Vec<(usize, usize)>> {
+        let mut predecessors = vec![vec![]; self.len()];
+        for (source, edges) in self.edges.iter().enumerate() {
+            for (transition, target) in edges.iter() {
+                predecessors[*target].push((source, *transition));
+            }
+        }
+
+        predecessors
+    }
+
+    /// Shortest firing sequence leading from the initial marking to `state`
+    pub fn path_to(&self, state: usize) -> Vec<usize> {
+        let mut firings = vec![];
+        let mut current = state;
+        while let Some((parent, transition)) = self.parents[current] {
+            firings.push(transition);
+            current = parent;
+        }
+        firings.reverse();
+
+        firings
+    }
+
+    /// States in which no transition is enabled
+    pub fn deadlocks(&self) -> Vec<usize> {
+        (0..self.len())
+            .filter(|state| self.edges[*state].is_empty())
+            .collect()
+    }
+
+    /// States from which one of `targets` can be reached, `targets` included
+    pub fn can_reach(&self, targets: &[usize]) -> Vec<bool> {
+        let predecessors = self.predecessors();
+        let mut reached = vec![false; self.len()];
+        let mut pending: Vec<usize> = targets.to_vec();
+        for target in targets.iter() {
+            reached[*target] = true;
+        }
+
+        while let Some(state) = pending.pop() {
+            for (source, _) in predecessors[state].iter() {
+                if !reached[*source] {
+                    reached[*source] = true;
+                    pending.push(*source);
+                }
+            }
+        }
+
+        reached
+    }
+
+    /// Whether `marking`, reached from `state`, strictly covers `state` or one of its ancestors
+    fn covers_ancestor(&self, state: usize, marking: &Marking) -> bool {
+        let mut current = Some(state);
+        while let Some(ancestor) = current {
+            let earlier = &self.markings[ancestor];
+            if marking.covers(earlier) && marking != earlier {
+                return true;
+            }
+            current = self.parents[ancestor].map(|(parent, _)| parent);
+        }
+
+        false
+    }
+}
@@ -0,0 +1,261 @@
+//! Siphons, traps and the liveness results built on them.
+//!
+//! A siphon is a set of places every transition putting tokens into also takes tokens from: once
+//! a siphon is empty it stays empty, and the transitions consuming from it are dead. A trap is a
+//! set of places every transition taking tokens from also puts tokens into: once a trap is marked
+//! it stays marked. Both only depend on the connections of the net, so they are found without
+//! exploring a single marking, even when there are infinitely many.
+//!
+//! In an ordinary net, the places left empty by a deadlock form a siphon. A net in which every
+//! minimal siphon contains an initially marked trap is therefore deadlock-free, and by the
+//! theorem of Commoner and Hack an extended free-choice net is live exactly when that holds.
+//!
+//! Only normal connections move tokens here: inhibitor and reset connections are ignored, and
+//! results are only guaranteed for ordinary nets, where every connection is normal of weight one.
+
+use super::AnalysisError;
+use crate::net::{ConnectionType, IndexedNet, Marking, PetriNet};
+
+/// Outcome of the Commoner-Hack liveness check
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Liveness {
+    /// Every minimal siphon contains a marked trap, so every transition can always fire again
+    Live,
+    /// The minimal siphon contains no marked trap: it can be emptied, killing its consumers
+    NotLive { siphon: Vec<usize> },
+    /// The net is not ordinary and extended free-choice, so the theorem does not apply
+    NotFreeChoice,
+}
+
+/// Connections of a net as seen by structural analyses
+#[derive(Clone, Debug)]
+pub struct Structure {
+    /// Places each transition takes tokens from
+    inputs: Vec<Vec<usize>>,
+    /// Places each transition puts tokens into
+    outputs: Vec<Vec<usize>>,
+    /// Transitions putting tokens into each place
+    producers: Vec<Vec<usize>>,
+    /// Transitions taking tokens from each place
+    consumers: Vec<Vec<usize>>,
+    ordinary: bool,
+}
+
+impl Structure {
+    pub fn new(net: &PetriNet) -> Self {
+        let indexed = IndexedNet::new(net);
+        let places = indexed.place_count();
+        let transitions = indexed.transition_count();
+
+        let mut structure = Self {
+            inputs: vec![vec![]; transitions],
+            outputs: vec![vec![]; transitions],
+            producers: vec![vec![]; places],
+            consumers: vec![vec![]; places],
+            ordinary: true,
+        };
+        for transition in 0..transitions {
+            for con in indexed.pre(transition).iter() {
+                structure.ordinary &= con.con_type == ConnectionType::NORMAL && con.weight == 1;
+                if con.con_type == ConnectionType::NORMAL && con.weight > 0 {
+                    structure.inputs[transition].push(con.place);
+                    structure.consumers[con.place].push(transition);
+                }
+            }
+            for con in indexed.post(transition).iter() {
+                structure.ordinary &= con.weight == 1;
+                structure.outputs[transition].push(con.place);
+                structure.producers[con.place].push(transition);
+            }
+        }
+        for list in structure
+            .inputs
+            .iter_mut()
+            .chain(structure.outputs.iter_mut())
+            .chain(structure.producers.iter_mut())
+            .chain(structure.consumers.iter_mut())
+        {
+            list.sort_unstable();
+            list.dedup();
+        }
+
+        structure
+    }
+
+    /// Whether every connection is normal with weight one
+    pub fn is_ordinary(&self) -> bool {
+        self.ordinary
+    }
+
+    /// Whether any two transitions sharing an input place have the same input places
+    pub fn is_extended_free_choice(&self) -> bool {
+        self.consumers.iter().all(|consumers| {
+            consumers
+                .windows(2)
+                .all(|pair| self.inputs[pair[0]] == self.inputs[pair[1]])
+        })
+    }
+
+    pub fn is_siphon(&self, places: &[usize]) -> bool {
+        !places.is_empty() && self.maximal_siphon(places).len() == places.len()
+    }
+
+    pub fn is_trap(&self, places: &[usize]) -> bool {
+        !places.is_empty() && self.maximal_trap(places).len() == places.len()
+    }
+
+    /// The largest siphon made of `places`, the union of all of them, which may be empty
+    pub fn maximal_siphon(&self, places: &[usize]) -> Vec<usize> {
+        self.largest(places, &self.producers, &self.inputs)
+    }
+
+    /// The largest trap made of `places`, the union of all of them, which may be empty
+    pub fn maximal_trap(&self, places: &[usize]) -> Vec<usize> {
+        self.largest(places, &self.consumers, &self.outputs)
+    }
+
+    /// Siphons none of whose proper subsets are siphons, each sorted
+    pub fn minimal_siphons(&self) -> Vec<Vec<usize>> {
+        self.minimal(&self.producers, &self.inputs)
+    }
+
+    /// Traps none of whose proper subsets are traps, each sorted
+    pub fn minimal_traps(&self) -> Vec<Vec<usize>> {
+        self.minimal(&self.consumers, &self.outputs)
+    }
+
+    /// Minimal siphons without any trap marked in `marking`, which can be emptied. Each of them
+    /// may lead to a deadlock, and in an ordinary net without them no deadlock is reachable.
+    /// Fails with `AnalysisError::MarkingLength` if `marking` does not have one entry per place.
+    pub fn potential_deadlocks(&self, marking: &Marking) -> Result<Vec<Vec<usize>>, AnalysisError> {
+        self.check_length(marking)?;
+
+        Ok(self
+            .minimal_siphons()
+            .into_iter()
+            .filter(|siphon| {
+                self.maximal_trap(siphon)
+                    .iter()
+                    .all(|place| marking[*place] == 0)
+            })
+            .collect())
+    }
+
+    /// Decides the liveness of ordinary extended free-choice nets by the theorem of Commoner and
+    /// Hack. Fails with `AnalysisError::MarkingLength` if `marking` does not have one entry per
+    /// place.
+    pub fn liveness(&self, marking: &Marking) -> Result<Liveness, AnalysisError> {
+        self.check_length(marking)?;
+        if !self.ordinary || !self.is_extended_free_choice() {
+            return Ok(Liveness::NotFreeChoice);
+        }
+
+        Ok(
+            match self.potential_deadlocks(marking)?.into_iter().next() {
+                Some(siphon) => Liveness::NotLive { siphon },
+                None => Liveness::Live,
+            },
+        )
+    }
+
+    fn check_length(&self, marking: &Marking) -> Result<(), AnalysisError> {
+        if marking.len() != self.producers.len() {
+            return Err(AnalysisError::MarkingLength {
+                expected: self.producers.len(),
+                found: marking.len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Removes from `places` those fed by a transition of `feeding` with no connection of
+    /// `inside` to the remaining places, until none is left
+    fn largest(
+        &self,
+        places: &[usize],
+        feeding: &[Vec<usize>],
+        inside: &[Vec<usize>],
+    ) -> Vec<usize> {
+        let mut member = vec![false; self.producers.len()];
+        for place in places.iter() {
+            member[*place] = true;
+        }
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for place in 0..member.len() {
+                if member[place]
+                    && feeding[place]
+                        .iter()
+                        .any(|t| inside[*t].iter().all(|other| !member[*other]))
+                {
+                    member[place] = false;
+                    changed = true;
+                }
+            }
+        }
+
+        (0..member.len()).filter(|place| member[*place]).collect()
+    }
+
+    /// Enumerates minimal sets by partitioning the search space: after finding a set minimal
+    /// among those containing `required`, every other one misses one of its places
+    fn minimal(&self, feeding: &[Vec<usize>], inside: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        let mut found = vec![];
+        let all: Vec<usize> = (0..self.producers.len()).collect();
+        let mut pending = vec![(all, vec![])];
+
+        while let Some((allowed, required)) = pending.pop() {
+            let mut set = self.largest(&allowed, feeding, inside);
+            let contains = |set: &[usize], places: &[usize]| {
+                places.iter().all(|place| set.binary_search(place).is_ok())
+            };
+            if set.is_empty() || !contains(&set, &required) {
+                continue;
+            }
+
+            // Shrinking only makes the largest set of what is left smaller, so a place that
+            // cannot be removed now never can
+            for place in set.clone() {
+                if required.contains(&place) || set.binary_search(&place).is_err() {
+                    continue;
+                }
+                let without: Vec<usize> = set.iter().copied().filter(|p| *p != place).collect();
+                let smaller = self.largest(&without, feeding, inside);
+                if !smaller.is_empty() && contains(&smaller, &required) {
+                    set = smaller;
+                }
+            }
+
+            let mut required = required;
+            for place in set
+                .iter()
+                .filter(|place| !required.contains(place))
+                .copied()
+                .collect::<Vec<_>>()
+            {
+                let allowed: Vec<usize> = allowed.iter().copied().filter(|p| *p != place).collect();
+                pending.push((allowed, required.clone()));
+                required.push(place);
+            }
+            found.push(set);
+        }
+
+        // Sets found while some places were required may contain smaller ones
+        found.sort_by(|a, b| a.len().cmp(&b.len()).then(a.cmp(b)));
+        found.dedup();
+        let mut minimal: Vec<Vec<usize>> = vec![];
+        for set in found {
+            if !minimal
+                .iter()
+                .any(|smaller| smaller.iter().all(|place| set.binary_search(place).is_ok()))
+            {
+                minimal.push(set);
+            }
+        }
+        minimal.sort();
+
+        minimal
+    }
+}
@@ -0,0 +1,197 @@
+//! Partial-order reduction with stubborn sets.
+//!
+//! Firing independent transitions in any order leads to the same marking, so exploring every
+//! interleaving is wasted work. In each marking, a stubborn set is a set of transitions that no
+//! firing outside of it can interfere with: exploring only its enabled transitions still reaches
+//! every deadlock of the net.
+//!
+//! Two transitions interfere when one can disable the other, or when firing them in different
+//! orders leads to different markings. This is decided from the connections they share:
+//!
+//! - one takes tokens from a place (normal or reset connection) the other needs tokens from,
+//! - one puts tokens into a place the other is inhibited by,
+//! - one resets a place the other takes tokens from or puts tokens into.
+//!
+//! Safety properties also need the places they read to be declared visible: once a stubborn set
+//! fires a transition changing a visible place, it fires every such transition, so markings are
+//! reduced without losing any reachable combination of tokens in visible places. Explorations
+//! additionally expand a marking fully whenever a reduced firing leads to a marking seen before,
+//! so that no transition is postponed forever along a cycle.
+
+use crate::net::{ConnectionType, IndexedNet, Marking};
+
+/// Computes stubborn sets of a net, see the module documentation
+#[derive(Clone, Debug)]
+pub struct StubbornSets {
+    /// Transitions interfering with each transition
+    interference: Vec<Vec<usize>>,
+    /// Transitions putting tokens into each place
+    producers: Vec<Vec<usize>>,
+    /// Transitions taking tokens from each place, or resetting it
+    decreasers: Vec<Vec<usize>>,
+    /// Transitions changing a visible place
+    visible: Vec<bool>,
+}
+
+impl StubbornSets {
+    /// Stubborn sets preserving the deadlocks of `net`
+    pub fn new(net: &IndexedNet) -> Self {
+        let places = net.place_count();
+        let transitions = net.transition_count();
+
+        let mut needs = vec![vec![]; places];
+        let mut decreasers = vec![vec![]; places];
+        let mut inhibited = vec![vec![]; places];
+        let mut resets = vec![vec![]; places];
+        let mut producers = vec![vec![]; places];
+        for transition in 0..transitions {
+            for con in net.pre(transition).iter() {
+                match con.con_type {
+                    ConnectionType::NORMAL if con.weight > 0 => {
+                        needs[con.place].push(transition);
+                        decreasers[con.place].push(transition);
+                    }
+                    ConnectionType::NORMAL => {}
+                    ConnectionType::INHIBITOR => inhibited[con.place].push(transition),
+                    ConnectionType::RESET => {
+                        resets[con.place].push(transition);
+                        decreasers[con.place].push(transition);
+                    }
+                }
+            }
+            for con in net.post(transition).iter() {
+                producers[con.place].push(transition);
+            }
+        }
+
+        let mut interference = vec![vec![]; transitions];
+        let mut interfere = |left: &[usize], right: &[usize]| {
+            for a in left.iter() {
+                for b in right.iter() {
+                    if a != b {
+                        interference[*a].push(*b);
+                        interference[*b].push(*a);
+                    }
+                }
+            }
+        };
+        for place in 0..places {
+            interfere(&decreasers[place], &needs[place]);
+            interfere(&producers[place], &inhibited[place]);
+            interfere(&resets[place], &decreasers[place]);
+            interfere(&resets[place], &producers[place]);
+        }
+        for list in interference.iter_mut() {
+            list.sort_unstable();
+            list.dedup();
+        }
+
+        Self {
+            interference,
+            producers,
+            decreasers,
+            visible: vec![false; transitions],
+        }
+    }
+
+    /// Also preserves every reachable combination of tokens in `places`, for safety properties
+    /// reading them
+    pub fn with_visible_places(mut self, places: &[usize]) -> Self {
+        for place in places.iter() {
+            for transition in self.producers[*place]
+                .iter()
+                .chain(self.decreasers[*place].iter())
+            {
+                self.visible[*transition] = true;
+            }
+        }
+
+        self
+    }
+
+    /// Whether `transition` changes a visible place
+    pub fn is_visible(&self, transition: usize) -> bool {
+        self.visible[transition]
+    }
+
+    /// Enabled transitions of the smallest stubborn set found in `marking`, `enabled` being all
+    /// the transitions enabled there. Empty only if `enabled` is.
+    pub fn stubborn(&self, net: &IndexedNet, marking: &Marking, enabled: &[usize]) -> Vec<usize> {
+        let mut is_enabled = vec![false; self.visible.len()];
+        for transition in enabled.iter() {
+            is_enabled[*transition] = true;
+        }
+
+        let mut best: Option<Vec<usize>> = None;
+        for seed in enabled.iter() {
+            let set = self.closure(net, marking, &is_enabled, *seed);
+            if best.as_ref().is_none_or(|best| set.len() < best.len()) {
+                let done = set.len() == 1;
+                best = Some(set);
+                if done {
+                    break;
+                }
+            }
+        }
+
+        best.unwrap_or_default()
+    }
+
+    /// Enabled transitions of the stubborn set grown from `seed`
+    fn closure(
+        &self,
+        net: &IndexedNet,
+        marking: &Marking,
+        is_enabled: &[bool],
+        seed: usize,
+    ) -> Vec<usize> {
+        let mut included = vec![false; self.visible.len()];
+        let mut pending = vec![seed];
+        included[seed] = true;
+        let mut visible_added = false;
+
+        let mut include = |transition: usize, pending: &mut Vec<usize>| {
+            if !included[transition] {
+                included[transition] = true;
+                pending.push(transition);
+            }
+        };
+
+        while let Some(transition) = pending.pop() {
+            if is_enabled[transition] {
+                for other in self.interference[transition].iter() {
+                    include(*other, &mut pending);
+                }
+                if self.visible[transition] && !visible_added {
+                    visible_added = true;
+                    for other in (0..self.visible.len()).filter(|t| self.visible[*t]) {
+                        include(other, &mut pending);
+                    }
+                }
+            } else {
+                // Some connection keeps the transition disabled until one of these fires
+                let blocking = net
+                    .pre(transition)
+                    .iter()
+                    .filter_map(|con| match con.con_type {
+                        ConnectionType::NORMAL if marking[con.place] < con.weight => {
+                            Some(&self.producers[con.place])
+                        }
+                        ConnectionType::INHIBITOR if marking[con.place] >= con.weight => {
+                            Some(&self.decreasers[con.place])
+                        }
+                        _ => None,
+                    })
+                    .min_by_key(|transitions| transitions.len());
+
+                for other in blocking.into_iter().flatten() {
+                    include(*other, &mut pending);
+                }
+            }
+        }
+
+        (0..self.visible.len())
+            .filter(|transition| included[*transition] && is_enabled[*transition])
+            .collect()
+    }
+}
@@ -0,0 +1,346 @@
+//! Symbolic state spaces, storing sets of markings in decision diagrams.
+//!
+//! Markings of a bounded net share long runs of equal token counts, which a multi-valued decision
+//! diagram (MDD) stores once: the reachable set of nets with millions of markings often fits in a
+//! few thousand nodes. The set is computed by chained fixpoints: every transition is fired on the
+//! whole set in turn, adding the markings it leads to, until no transition adds anything.
+//!
+//! ```
+//! use petri_engine::analysis::SymbolicStateSpace;
+//! use petri_engine::petri_net;
+//!
+//! let net = petri_net! {
+//!     places => [Free<2>, Busy],
+//!     transitions => [Take, Release],
+//!     connections => [Free -> Take, Take -> Busy, Busy -> Release, Release -> Free]
+//! };
+//!
+//! let space = SymbolicStateSpace::new(&net, 2).unwrap();
+//! assert_eq!(space.len(), 3);
+//! assert!(space.contains(&vec![1, 1].into()));
+//! assert_eq!(space.deadlock(), None);
+//! ```
+
+use std::collections::HashMap;
+
+use super::formula::Atom;
+use super::mdd::{Mdd, Node, EMPTY};
+use super::AnalysisError;
+use crate::net::{ConnectionType, IndexedNet, Marking, PetriNet};
+
+/// What firing a transition does to one of its places
+#[derive(Clone, Copy, Debug)]
+struct Effect {
+    place: usize,
+    /// Tokens taken through normal connections
+    consumed: i32,
+    /// Lowest weight of the inhibitor connections, if any
+    inhibitor: Option<i32>,
+    reset: bool,
+    produced: i32,
+}
+
+impl Effect {
+    fn is_enabled(&self, tokens: i32) -> bool {
+        tokens >= self.consumed && self.inhibitor.is_none_or(|weight| tokens < weight)
+    }
+
+    fn fire(&self, tokens: i32) -> i32 {
+        let left = if self.reset {
+            0
+        } else {
+            tokens - self.consumed
+        };
+        left + self.produced
+    }
+}
+
+/// Every marking reachable in a net holding at most `bound` tokens per place, stored as a
+/// decision diagram with one level per place.
+#[derive(Clone, Debug)]
+pub struct SymbolicStateSpace {
+    mdd: Mdd,
+    bound: i32,
+    /// Effects of every transition, ordered by place
+    effects: Vec<Vec<Effect>>,
+    reached: Node,
+    deadlocks: Node,
+}
+
+impl SymbolicStateSpace {
+    /// Explores every marking reachable from the initial marking of `net`, with at most `bound`
+    /// tokens per place
+    pub fn new(net: &PetriNet, bound: i32) -> Result<Self, AnalysisError> {
+        Self::explore(&IndexedNet::new(net), net.initial_marking(), bound)
+    }
+
+    /// Explores every marking reachable from `initial`. Fails with
+    /// `AnalysisError::BoundExceeded` if a reachable marking puts more than `bound` tokens in a
+    /// place, which also happens on any unbounded net, and with `AnalysisError::MarkingLength` if
+    /// `initial` does not have one entry per place.
+    pub fn explore(net: &IndexedNet, initial: Marking, bound: i32) -> Result<Self, AnalysisError> {
+        if initial.len() != net.place_count() {
+            return Err(AnalysisError::MarkingLength {
+                expected: net.place_count(),
+                found: initial.len(),
+            });
+        }
+
+        let effects = (0..net.transition_count())
+            .map(|transition| {
+                let mut effects: Vec<Effect> = vec![];
+                let connections = net
+                    .pre(transition)
+                    .iter()
+                    .map(|con| (con, true))
+                    .chain(net.post(transition).iter().map(|con| (con, false)));
+
+                for (con, incoming) in connections {
+                    let index = match effects.iter().position(|e| e.place == con.place) {
+                        Some(index) => index,
+                        None => {
+                            effects.push(Effect {
+                                place: con.place,
+                                consumed: 0,
+                                inhibitor: None,
+                                reset: false,
+                                produced: 0,
+                            });
+                            effects.len() - 1
+                        }
+                    };
+
+                    let effect = &mut effects[index];
+                    match (incoming, con.con_type) {
+                        (false, _) => effect.produced += con.weight,
+                        (true, ConnectionType::NORMAL) => effect.consumed += con.weight,
+                        (true, ConnectionType::INHIBITOR) => {
+                            effect.inhibitor =
+                                Some(effect.inhibitor.map_or(con.weight, |w| w.min(con.weight)))
+                        }
+                        (true, ConnectionType::RESET) => effect.reset = true,
+                    }
+                }
+
+                effects.sort_by_key(|effect| effect.place);
+                effects
+            })
+            .collect();
+
+        let mut space = Self {
+            mdd: Mdd::new(net.place_count(), bound.max(0) as usize + 1),
+            bound,
+            effects,
+            reached: EMPTY,
+            deadlocks: EMPTY,
+        };
+
+        if let Some(place) = (0..initial.len()).find(|p| initial[*p] < 0 || initial[*p] > bound) {
+            return Err(AnalysisError::BoundExceeded { place, bound });
+        }
+        let values: Vec<usize> = initial.iter().map(|tokens| *tokens as usize).collect();
+        let mut reached = space.mdd.singleton(&values);
+
+        // Transitions changing the lowest places first, so that the sets they produce are
+        // already complete when the transitions above fire on them
+        let mut order: Vec<usize> = (0..net.transition_count()).collect();
+        order.sort_by_key(|t| {
+            std::cmp::Reverse(space.effects[*t].first().map_or(usize::MAX, |e| e.place))
+        });
+
+        loop {
+            let previous = reached;
+            for transition in order.iter() {
+                let next = space.fire(*transition, reached, &mut HashMap::new())?;
+                reached = space.mdd.union(reached, next);
+            }
+            if reached == previous {
+                break;
+            }
+        }
+
+        let mut enabled = EMPTY;
+        for transition in 0..net.transition_count() {
+            let enabling = space.enabling(transition, reached, &mut HashMap::new());
+            enabled = space.mdd.union(enabled, enabling);
+        }
+        space.reached = reached;
+        space.deadlocks = space.mdd.difference(reached, enabled);
+
+        Ok(space)
+    }
+
+    /// Number of reachable markings, saturating at `u128::MAX`
+    pub fn len(&self) -> u128 {
+        self.mdd.count(self.reached)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.reached == EMPTY
+    }
+
+    /// Number of decision diagram nodes created while exploring, a measure of memory use
+    pub fn node_count(&self) -> usize {
+        self.mdd.size()
+    }
+
+    /// Whether `marking` is reachable
+    pub fn contains(&self, marking: &Marking) -> bool {
+        marking.len() == self.mdd.levels()
+            && marking.iter().all(|tokens| *tokens >= 0)
+            && self.mdd.contains(self.reached, &Self::values(marking))
+    }
+
+    /// Number of reachable markings in which no transition is enabled
+    pub fn deadlock_count(&self) -> u128 {
+        self.mdd.count(self.deadlocks)
+    }
+
+    /// A reachable marking in which no transition is enabled, if there is one
+    pub fn deadlock(&self) -> Option<Marking> {
+        self.mdd.pick(self.deadlocks).map(Self::marking)
+    }
+
+    /// A reachable marking satisfying every one of `atoms`, if there is one. Fails with
+    /// `AnalysisError::UnknownPlace` or `AnalysisError::UnknownTransition` if an atom names a node
+    /// the net does not have.
+    pub fn can_reach(&mut self, atoms: &[Atom]) -> Result<Option<Marking>, AnalysisError> {
+        for atom in atoms.iter() {
+            match atom {
+                Atom::Tokens { place, .. } if *place >= self.mdd.levels() => {
+                    return Err(AnalysisError::UnknownPlace {
+                        place: *place,
+                        places: self.mdd.levels(),
+                    });
+                }
+                Atom::Enabled(transition) if *transition >= self.effects.len() => {
+                    return Err(AnalysisError::UnknownTransition {
+                        transition: *transition,
+                        transitions: self.effects.len(),
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        let mut set = self.reached;
+        for atom in atoms.iter() {
+            set = match atom {
+                Atom::Tokens {
+                    place,
+                    comparison,
+                    value,
+                } => {
+                    let keep = |tokens: usize| comparison.holds(tokens as i32, *value);
+                    self.mdd.restrict(set, *place, &keep, &mut HashMap::new())
+                }
+                Atom::Enabled(transition) => self.enabling(*transition, set, &mut HashMap::new()),
+                Atom::Deadlock => self.mdd.intersection(set, self.deadlocks),
+            };
+        }
+
+        Ok(self.mdd.pick(set).map(Self::marking))
+    }
+
+    fn values(marking: &Marking) -> Vec<usize> {
+        marking.iter().map(|tokens| *tokens as usize).collect()
+    }
+
+    fn marking(values: Vec<usize>) -> Marking {
+        values
+            .into_iter()
+            .map(|tokens| tokens as i32)
+            .collect::<Vec<i32>>()
+            .into()
+    }
+
+    /// Markings reached by firing `transition` in the markings of `node`
+    fn fire(
+        &mut self,
+        transition: usize,
+        node: Node,
+        memo: &mut HashMap<Node, Node>,
+    ) -> Result<Node, AnalysisError> {
+        if node == EMPTY {
+            return Ok(EMPTY);
+        }
+        let level = self.mdd.level(node);
+        // Levels below the last place the transition touches are left as they are
+        let Some(effect) = self.effects[transition]
+            .iter()
+            .find(|effect| effect.place >= level)
+            .copied()
+        else {
+            return Ok(node);
+        };
+        if let Some(result) = memo.get(&node) {
+            return Ok(*result);
+        }
+
+        let mut children = vec![EMPTY; self.mdd.domain()];
+        for value in 0..self.mdd.domain() {
+            let child = self.mdd.children(node)[value];
+            if child == EMPTY {
+                continue;
+            }
+            if effect.place > level {
+                children[value] = self.fire(transition, child, memo)?;
+                continue;
+            }
+            if !effect.is_enabled(value as i32) {
+                continue;
+            }
+
+            let below = self.fire(transition, child, memo)?;
+            if below == EMPTY {
+                continue;
+            }
+            let tokens = effect.fire(value as i32);
+            if tokens > self.bound {
+                return Err(AnalysisError::BoundExceeded {
+                    place: level,
+                    bound: self.bound,
+                });
+            }
+            children[tokens as usize] = self.mdd.union(children[tokens as usize], below);
+        }
+
+        let result = self.mdd.node(level, children);
+        memo.insert(node, result);
+
+        Ok(result)
+    }
+
+    /// Markings of `node` in which `transition` is enabled
+    fn enabling(&mut self, transition: usize, node: Node, memo: &mut HashMap<Node, Node>) -> Node {
+        if node == EMPTY {
+            return EMPTY;
+        }
+        let level = self.mdd.level(node);
+        let Some(effect) = self.effects[transition]
+            .iter()
+            .find(|effect| effect.place >= level)
+            .copied()
+        else {
+            return node;
+        };
+        if let Some(result) = memo.get(&node) {
+            return *result;
+        }
+
+        let children = (0..self.mdd.domain())
+            .map(|value| {
+                let child = self.mdd.children(node)[value];
+                if effect.place > level || effect.is_enabled(value as i32) {
+                    self.enabling(transition, child, memo)
+                } else {
+                    EMPTY
+                }
+            })
+            .collect();
+        let result = self.mdd.node(level, children);
+        memo.insert(node, result);
+
+        result
+    }
+}
@@ -0,0 +1,478 @@
+//! Unfoldings of safe nets into occurrence nets, and their complete finite prefixes.
+//!
+//! The unfolding of a net records every run as a partial order: each token is a condition, each
+//! firing an event consuming the conditions it took and producing new ones, and two events only
+//! share a condition when they are in conflict. Concurrent firings are never interleaved, so the
+//! prefix of a highly concurrent net stays small where its state space explodes.
+//!
+//! The prefix is built as described by Esparza, Römer and Vogler: possible extensions are added
+//! in order of their local configuration (the event and all its causes), compared by size then by
+//! Parikh vector, which is an adequate order. An event is a cut-off when a smaller local
+//! configuration already reaches the same marking. Nothing is appended after a cut-off, and every
+//! reachable marking is still reached by a configuration of the prefix without cut-offs.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use super::Path;
+use crate::net::{ConnectionType, IndexedConnection, IndexedNet, Marking, PetriNet};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum UnfoldingError {
+    /// The transition has no incoming connection, or a connection that is not normal of weight
+    /// one, which unfoldings do not support
+    Unsupported { transition: usize },
+    /// Two tokens can be in `place` after `firings`, so the net is not safe
+    NotSafe { place: usize, firings: Vec<usize> },
+    /// The prefix has more events than the given limit
+    EventLimit(usize),
+}
+
+impl std::fmt::Display for UnfoldingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UnfoldingError::Unsupported { transition } => write!(
+                f,
+                "transition {} needs incoming connections, all normal with weight one",
+                transition
+            ),
+            UnfoldingError::NotSafe { place, .. } => {
+                write!(f, "net is not safe, place {} can hold two tokens", place)
+            }
+            UnfoldingError::EventLimit(limit) => {
+                write!(f, "prefix has more than {} events", limit)
+            }
+        }
+    }
+}
+
+impl std::error::Error for UnfoldingError {}
+
+/// A token of the unfolding
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Condition {
+    /// Place the token is in
+    pub place: usize,
+    /// Event producing the token, `None` for the tokens of the initial marking
+    pub preset: Option<usize>,
+}
+
+/// A firing of the unfolding
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Event {
+    pub transition: usize,
+    /// Conditions consumed by the event
+    pub preset: Vec<usize>,
+    /// Conditions produced by the event
+    pub postset: Vec<usize>,
+    /// The marking reached by the local configuration was already reached by a smaller one
+    pub cutoff: bool,
+}
+
+/// A possible extension waiting to be added to the prefix
+struct Extension {
+    transition: usize,
+    preset: Vec<usize>,
+    /// Events causing the extension, sorted
+    history: Vec<usize>,
+}
+
+/// Complete finite prefix of the unfolding of a safe net.
+///
+/// Events are numbered in the order they were added, which respects causality: sorting any
+/// configuration by event gives a valid firing sequence.
+#[derive(Clone, Debug)]
+pub struct Unfolding {
+    net: IndexedNet,
+    initial: Marking,
+    conditions: Vec<Condition>,
+    events: Vec<Event>,
+    /// Local configuration of every event, sorted
+    configurations: Vec<Vec<usize>>,
+    /// Conditions that can hold a token together with each condition, except after cut-offs
+    co: Vec<HashSet<usize>>,
+}
+
+impl Unfolding {
+    /// Unfolds the initial marking of `net`, giving up after `limit` events
+    pub fn new(net: &PetriNet, limit: Option<usize>) -> Result<Self, UnfoldingError> {
+        let indexed = IndexedNet::new(net);
+        for transition in 0..indexed.transition_count() {
+            let ordinary = |cons: &[IndexedConnection]| {
+                let mut places = HashSet::new();
+                cons.iter().all(|con| {
+                    con.con_type == ConnectionType::NORMAL
+                        && con.weight == 1
+                        && places.insert(con.place)
+                })
+            };
+            if indexed.pre(transition).is_empty()
+                || !ordinary(indexed.pre(transition))
+                || !ordinary(indexed.post(transition))
+            {
+                return Err(UnfoldingError::Unsupported { transition });
+            }
+        }
+
+        let initial = net.initial_marking();
+        if let Some(place) = (0..initial.len()).find(|place| initial[*place] > 1) {
+            return Err(UnfoldingError::NotSafe {
+                place,
+                firings: vec![],
+            });
+        }
+
+        let mut unfolding = Self {
+            net: indexed,
+            initial: initial.clone(),
+            conditions: vec![],
+            events: vec![],
+            configurations: vec![],
+            co: vec![],
+        };
+        let marked: Vec<usize> = (0..initial.len()).filter(|p| initial[*p] == 1).collect();
+        for place in marked.iter() {
+            unfolding.conditions.push(Condition {
+                place: *place,
+                preset: None,
+            });
+            let others = (0..marked.len())
+                .filter(|c| *c != unfolding.co.len())
+                .collect();
+            unfolding.co.push(others);
+        }
+
+        let mut pending = BinaryHeap::new();
+        let mut extensions: Vec<Option<Extension>> = vec![];
+        let mut generated = HashSet::new();
+        let mut reached = HashMap::from([(initial, (0, vec![0; unfolding.transition_count()]))]);
+
+        let initial_conditions: Vec<usize> = (0..unfolding.conditions.len()).collect();
+        for extension in unfolding.extensions(&initial_conditions, &mut generated) {
+            let key = unfolding.key(&extension);
+            pending.push(Reverse((key, extensions.len())));
+            extensions.push(Some(extension));
+        }
+
+        while let Some(Reverse((key, index))) = pending.pop() {
+            if limit.is_some_and(|limit| unfolding.events.len() >= limit) {
+                return Err(UnfoldingError::EventLimit(unfolding.events.len()));
+            }
+
+            let Some(Extension {
+                transition,
+                preset,
+                history,
+            }) = extensions[index].take()
+            else {
+                continue;
+            };
+
+            let event = unfolding.events.len();
+            let mut configuration = history;
+            configuration.push(event);
+            unfolding.configurations.push(configuration);
+            unfolding.events.push(Event {
+                transition,
+                preset: preset.clone(),
+                postset: vec![],
+                cutoff: false,
+            });
+
+            let marking = unfolding.marking(event);
+            let cutoff = match reached.get(&marking) {
+                Some(smaller) => *smaller < key,
+                None => {
+                    reached.insert(marking, key);
+                    false
+                }
+            };
+            unfolding.events[event].cutoff = cutoff;
+
+            // Conditions that can hold a token together with those of the postset
+            let mut concurrent: Option<HashSet<usize>> = None;
+            if !cutoff {
+                for condition in preset.iter() {
+                    concurrent = Some(match concurrent {
+                        None => unfolding.co[*condition].clone(),
+                        Some(set) => set
+                            .intersection(&unfolding.co[*condition])
+                            .copied()
+                            .collect(),
+                    });
+                }
+            }
+            let concurrent = concurrent.unwrap_or_default();
+
+            let places: Vec<usize> = unfolding
+                .net
+                .post(transition)
+                .iter()
+                .map(|con| con.place)
+                .collect();
+            let first = unfolding.conditions.len();
+            let postset: Vec<usize> = (first..first + places.len()).collect();
+            for place in places.iter() {
+                unfolding.conditions.push(Condition {
+                    place: *place,
+                    preset: Some(event),
+                });
+                unfolding.co.push(HashSet::new());
+            }
+            unfolding.events[event].postset = postset.clone();
+            if cutoff {
+                continue;
+            }
+
+            for condition in postset.iter() {
+                let place = unfolding.conditions[*condition].place;
+                if let Some(other) = concurrent
+                    .iter()
+                    .find(|other| unfolding.conditions[**other].place == place)
+                {
+                    let mut events = unfolding.causes(*condition);
+                    events.extend(unfolding.causes(*other));
+                    events.sort_unstable();
+                    events.dedup();
+                    return Err(UnfoldingError::NotSafe {
+                        place,
+                        firings: events
+                            .iter()
+                            .map(|event| unfolding.events[*event].transition)
+                            .collect(),
+                    });
+                }
+
+                let mut co = concurrent.clone();
+                co.extend(postset.iter().filter(|other| *other != condition));
+                for other in concurrent.iter() {
+                    unfolding.co[*other].insert(*condition);
+                }
+                unfolding.co[*condition] = co;
+            }
+
+            for extension in unfolding.extensions(&postset, &mut generated) {
+                let key = unfolding.key(&extension);
+                pending.push(Reverse((key, extensions.len())));
+                extensions.push(Some(extension));
+            }
+        }
+
+        Ok(unfolding)
+    }
+
+    pub fn conditions(&self) -> &[Condition] {
+        &self.conditions
+    }
+
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// Cut-off events of the prefix
+    pub fn cutoffs(&self) -> Vec<usize> {
+        (0..self.events.len())
+            .filter(|event| self.events[*event].cutoff)
+            .collect()
+    }
+
+    /// `event` and all the events it causally depends on, in an order they can fire in
+    pub fn local_configuration(&self, event: usize) -> &[usize] {
+        &self.configurations[event]
+    }
+
+    /// Marking reached by firing the local configuration of `event`
+    pub fn marking(&self, event: usize) -> Marking {
+        let mut marking = self.initial.clone();
+        for event in self.configurations[event].iter() {
+            self.net.fire(self.events[*event].transition, &mut marking);
+        }
+
+        marking
+    }
+
+    /// A firing sequence leading to a deadlock, if the net has one.
+    ///
+    /// The prefix being complete, a reachable marking is dead exactly when some configuration
+    /// without cut-offs reaches it and enables no event of the prefix. Configurations are
+    /// enumerated by adding events in increasing order, abandoning those where an event that can
+    /// no longer be disabled is enabled.
+    pub fn deadlock(&self) -> Option<Path> {
+        let mut marked = vec![false; self.conditions.len()];
+        for (condition, c) in self.conditions.iter().enumerate() {
+            marked[condition] = c.preset.is_none();
+        }
+        let mut chosen = vec![];
+        if !self.search_deadlock(0, &mut marked, &mut chosen) {
+            return None;
+        }
+
+        let mut marking = self.initial.clone();
+        let mut markings = vec![marking.clone()];
+        let mut firings = vec![];
+        for event in chosen.iter() {
+            let transition = self.events[*event].transition;
+            self.net.fire(transition, &mut marking);
+            markings.push(marking.clone());
+            firings.push(transition);
+        }
+
+        Some(Path {
+            markings,
+            firings,
+            cycle: None,
+        })
+    }
+
+    fn search_deadlock(&self, next: usize, marked: &mut [bool], chosen: &mut Vec<usize>) -> bool {
+        let enabled = |event: &usize| self.events[*event].preset.iter().all(|c| marked[*c]);
+        let enabled_events: Vec<usize> = (0..self.events.len()).filter(enabled).collect();
+        if enabled_events.is_empty() {
+            return true;
+        }
+
+        // An enabled event before `next` can only be disabled by a later one taking its tokens
+        for event in enabled_events.iter().filter(|event| **event < next) {
+            let disabled_later = (next..self.events.len()).any(|later| {
+                !self.events[later].cutoff
+                    && self.events[later]
+                        .preset
+                        .iter()
+                        .any(|c| self.events[*event].preset.contains(c))
+            });
+            if !disabled_later {
+                return false;
+            }
+        }
+
+        for event in enabled_events.into_iter().filter(|event| *event >= next) {
+            if self.events[event].cutoff {
+                continue;
+            }
+
+            let Event {
+                preset, postset, ..
+            } = &self.events[event];
+            for c in preset.iter() {
+                marked[*c] = false;
+            }
+            for c in postset.iter() {
+                marked[*c] = true;
+            }
+            chosen.push(event);
+
+            if self.search_deadlock(event + 1, marked, chosen) {
+                return true;
+            }
+
+            chosen.pop();
+            for c in postset.iter() {
+                marked[*c] = false;
+            }
+            for c in preset.iter() {
+                marked[*c] = true;
+            }
+        }
+
+        false
+    }
+
+    fn transition_count(&self) -> usize {
+        self.net.transition_count()
+    }
+
+    /// Events `condition` causally depends on
+    fn causes(&self, condition: usize) -> Vec<usize> {
+        match self.conditions[condition].preset {
+            Some(event) => self.configurations[event].clone(),
+            None => vec![],
+        }
+    }
+
+    /// Size and Parikh vector of the local configuration of `extension`
+    fn key(&self, extension: &Extension) -> (usize, Vec<usize>) {
+        let mut parikh = vec![0; self.transition_count()];
+        parikh[extension.transition] += 1;
+        for event in extension.history.iter() {
+            parikh[self.events[*event].transition] += 1;
+        }
+
+        (extension.history.len() + 1, parikh)
+    }
+
+    /// Possible extensions consuming at least one of `conditions`, not generated before
+    fn extensions(
+        &self,
+        conditions: &[usize],
+        generated: &mut HashSet<(usize, Vec<usize>)>,
+    ) -> Vec<Extension> {
+        let mut extensions = vec![];
+        for condition in conditions.iter() {
+            let place = self.conditions[*condition].place;
+            for transition in self.net.consumers(place).iter() {
+                // Conditions to pick for the other places the transition consumes from
+                let candidates: Vec<Vec<usize>> = self
+                    .net
+                    .pre(*transition)
+                    .iter()
+                    .filter(|con| con.place != place)
+                    .map(|con| {
+                        self.co[*condition]
+                            .iter()
+                            .copied()
+                            .filter(|other| self.conditions[*other].place == con.place)
+                            .collect()
+                    })
+                    .collect();
+
+                let mut presets = vec![];
+                self.co_sets(&candidates, &mut vec![*condition], &mut presets);
+                for mut preset in presets {
+                    preset.sort_unstable();
+                    if !generated.insert((*transition, preset.clone())) {
+                        continue;
+                    }
+
+                    let mut history: Vec<usize> = preset
+                        .iter()
+                        .flat_map(|condition| self.causes(*condition))
+                        .collect();
+                    history.sort_unstable();
+                    history.dedup();
+                    extensions.push(Extension {
+                        transition: *transition,
+                        preset,
+                        history,
+                    });
+                }
+            }
+        }
+
+        extensions
+    }
+
+    /// Every way of extending `chosen` with one condition of each of `candidates`, all of them
+    /// pairwise concurrent
+    fn co_sets(
+        &self,
+        candidates: &[Vec<usize>],
+        chosen: &mut Vec<usize>,
+        sets: &mut Vec<Vec<usize>>,
+    ) {
+        let Some((first, rest)) = candidates.split_first() else {
+            sets.push(chosen.clone());
+            return;
+        };
+
+        for condition in first.iter() {
+            if chosen
+                .iter()
+                .all(|other| self.co[*other].contains(condition))
+            {
+                chosen.push(*condition);
+                self.co_sets(rest, chosen, sets);
+                chosen.pop();
+            }
+        }
+    }
+}
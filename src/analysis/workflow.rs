@@ -0,0 +1,352 @@
+//! Workflow nets and their classical soundness.
+//!
+//! A workflow net has a single source place `i` without incoming connections, a single sink place
+//! `o` without outgoing connections, and every node lies on a path from `i` to `o`. It is sound
+//! when, starting with one token in `i`:
+//!
+//! - one token in `o` and nothing else can always be reached (option to complete),
+//! - whenever `o` is marked every other place is empty (proper completion),
+//! - every transition can fire at some point (no dead transitions).
+//!
+//! Soundness is decided on the short-circuited net, which adds a transition moving the token from
+//! `o` back to `i`: a workflow net is sound exactly when its short-circuited net is live and
+//! bounded.
+
+use std::collections::VecDeque;
+
+use super::{AnalysisError, StateSpace};
+use crate::net::{
+    Connection, ConnectionType, IndexedNet, InputFrom, Marking, PetriNet, Transition,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum WorkflowError {
+    /// Every place has incoming connections
+    NoSource,
+    /// Every place has outgoing connections
+    NoSink,
+    /// More than one place has no incoming connections
+    SeveralSources(Vec<usize>),
+    /// More than one place has no outgoing connections
+    SeveralSinks(Vec<usize>),
+    /// Nodes that are not on a path from the source to the sink
+    Unconnected {
+        places: Vec<usize>,
+        transitions: Vec<usize>,
+    },
+}
+
+impl std::fmt::Display for WorkflowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WorkflowError::NoSource => write!(f, "net has no source place"),
+            WorkflowError::NoSink => write!(f, "net has no sink place"),
+            WorkflowError::SeveralSources(places) => {
+                write!(f, "net has {} source places", places.len())
+            }
+            WorkflowError::SeveralSinks(places) => {
+                write!(f, "net has {} sink places", places.len())
+            }
+            WorkflowError::Unconnected {
+                places,
+                transitions,
+            } => write!(
+                f,
+                "{} places and {} transitions are not on a path from source to sink",
+                places.len(),
+                transitions.len()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for WorkflowError {}
+
+/// Reason for a workflow net not to be sound
+#[derive(Clone, Debug, PartialEq)]
+pub enum Violation {
+    /// The short-circuited net is unbounded. `firings` may contain the short-circuit transition,
+    /// whose index is the number of transitions of the workflow net.
+    Unbounded {
+        marking: Marking,
+        firings: Vec<usize>,
+    },
+    /// The final marking cannot be reached from `marking`, which `firings` lead to
+    NoOptionToComplete {
+        marking: Marking,
+        firings: Vec<usize>,
+    },
+    /// `marking` marks the sink place along with other places
+    ImproperCompletion {
+        marking: Marking,
+        firings: Vec<usize>,
+    },
+    /// The transition can never fire
+    DeadTransition(usize),
+}
+
+/// Outcome of a soundness check, with one example for each kind of violation found
+#[derive(Clone, Debug, PartialEq)]
+pub struct Soundness {
+    pub violations: Vec<Violation>,
+}
+
+impl Soundness {
+    pub fn is_sound(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// A net recognized as a workflow net
+pub struct WorkflowNet<'a> {
+    net: &'a PetriNet,
+    indexed: IndexedNet,
+    source: usize,
+    sink: usize,
+}
+
+impl<'a> WorkflowNet<'a> {
+    /// Checks that `net` is a workflow net
+    pub fn new(net: &'a PetriNet) -> Result<Self, WorkflowError> {
+        let indexed = IndexedNet::new(net);
+        let places = indexed.place_count();
+
+        let sources: Vec<usize> = (0..places)
+            .filter(|place| indexed.producers(*place).is_empty())
+            .collect();
+        let sinks: Vec<usize> = (0..places)
+            .filter(|place| indexed.consumers(*place).is_empty())
+            .collect();
+
+        let source = match sources.as_slice() {
+            [] => return Err(WorkflowError::NoSource),
+            [source] => *source,
+            _ => return Err(WorkflowError::SeveralSources(sources)),
+        };
+        let sink = match sinks.as_slice() {
+            [] => return Err(WorkflowError::NoSink),
+            [sink] => *sink,
+            _ => return Err(WorkflowError::SeveralSinks(sinks)),
+        };
+
+        // Nodes are numbered places first, then transitions
+        let mut forward = vec![vec![]; places + indexed.transition_count()];
+        let mut backward = vec![vec![]; places + indexed.transition_count()];
+        for transition in 0..indexed.transition_count() {
+            for con in indexed.pre(transition).iter() {
+                forward[con.place].push(places + transition);
+                backward[places + transition].push(con.place);
+            }
+            for con in indexed.post(transition).iter() {
+                forward[places + transition].push(con.place);
+                backward[con.place].push(places + transition);
+            }
+        }
+
+        let from_source = reachable(&forward, source);
+        let to_sink = reachable(&backward, sink);
+        let unconnected: Vec<usize> = (0..forward.len())
+            .filter(|node| !from_source[*node] || !to_sink[*node])
+            .collect();
+
+        if !unconnected.is_empty() {
+            return Err(WorkflowError::Unconnected {
+                places: unconnected
+                    .iter()
+                    .copied()
+                    .filter(|n| *n < places)
+                    .collect(),
+                transitions: unconnected
+                    .iter()
+                    .filter(|n| **n >= places)
+                    .map(|n| n - places)
+                    .collect(),
+            });
+        }
+
+        Ok(Self {
+            net,
+            indexed,
+            source,
+            sink,
+        })
+    }
+
+    pub fn net(&self) -> &PetriNet {
+        self.net
+    }
+
+    /// Index of the source place `i`
+    pub fn source(&self) -> usize {
+        self.source
+    }
+
+    /// Index of the sink place `o`
+    pub fn sink(&self) -> usize {
+        self.sink
+    }
+
+    /// One token in the source place
+    pub fn initial_marking(&self) -> Marking {
+        self.single_token(self.source)
+    }
+
+    /// One token in the sink place
+    pub fn final_marking(&self) -> Marking {
+        self.single_token(self.sink)
+    }
+
+    /// Copy of the net with an extra transition, last of the list, moving a token from the sink
+    /// place back to the source place
+    pub fn short_circuited(&self) -> PetriNet {
+        let mut net = self.net.clone();
+
+        let mut name = "short-circuit".to_string();
+        while net.transition_with_name(&name).is_some() {
+            name.push('\'');
+        }
+        net.add_transition(Transition::new(&name));
+
+        let transition = net.transitions().last().unwrap().clone();
+        let source = net.places()[self.source].clone();
+        let sink = net.places()[self.sink].clone();
+        net.add_connection(Connection::new(
+            sink,
+            transition.clone(),
+            1,
+            InputFrom::PLACE,
+            ConnectionType::NORMAL,
+        ));
+        net.add_connection(Connection::new(
+            source,
+            transition,
+            1,
+            InputFrom::TRANSITION,
+            ConnectionType::NORMAL,
+        ));
+
+        net
+    }
+
+    /// Decides soundness, exploring at most `limit` markings of the short-circuited net
+    pub fn soundness(&self, limit: Option<usize>) -> Result<Soundness, AnalysisError> {
+        let short_circuited = IndexedNet::new(&self.short_circuited());
+        let space = match StateSpace::explore(&short_circuited, self.initial_marking(), limit) {
+            Ok(space) => space,
+            Err(AnalysisError::Unbounded { marking, firings }) => {
+                return Ok(Soundness {
+                    violations: vec![Violation::Unbounded { marking, firings }],
+                })
+            }
+            Err(error) => return Err(error),
+        };
+
+        // The remaining checks are about the workflow net itself: only follow its transitions
+        let short_circuit = self.indexed.transition_count();
+        let edges = |state: usize| {
+            space
+                .successors(state)
+                .iter()
+                .filter(move |(transition, _)| *transition != short_circuit)
+        };
+
+        let mut parents = vec![None; space.len()];
+        let mut visited = vec![false; space.len()];
+        let mut order = vec![];
+        let mut pending = VecDeque::from([0]);
+        visited[0] = true;
+        while let Some(state) = pending.pop_front() {
+            order.push(state);
+            for (transition, target) in edges(state) {
+                if !visited[*target] {
+                    visited[*target] = true;
+                    parents[*target] = Some((state, *transition));
+                    pending.push_back(*target);
+                }
+            }
+        }
+        let firings = |state: usize| {
+            let mut firings = vec![];
+            let mut current = state;
+            while let Some((parent, transition)) = parents[current] {
+                firings.push(transition);
+                current = parent;
+            }
+            firings.reverse();
+            firings
+        };
+
+        let mut completes = vec![false; space.len()];
+        if let Some(end) = space.state(&self.final_marking()) {
+            let predecessors = space.predecessors();
+            let mut pending = vec![end];
+            completes[end] = true;
+            while let Some(state) = pending.pop() {
+                for (source, transition) in predecessors[state].iter() {
+                    if *transition != short_circuit && !completes[*source] {
+                        completes[*source] = true;
+                        pending.push(*source);
+                    }
+                }
+            }
+        }
+
+        let mut violations = vec![];
+        let final_marking = self.final_marking();
+
+        if let Some(state) = order.iter().find(|state| !completes[**state]) {
+            violations.push(Violation::NoOptionToComplete {
+                marking: space.marking(*state).clone(),
+                firings: firings(*state),
+            });
+        }
+
+        if let Some(state) = order.iter().find(|state| {
+            let marking = space.marking(**state);
+            marking.covers(&final_marking) && *marking != final_marking
+        }) {
+            violations.push(Violation::ImproperCompletion {
+                marking: space.marking(*state).clone(),
+                firings: firings(*state),
+            });
+        }
+
+        let mut fired = vec![false; short_circuit];
+        for state in order.iter() {
+            for (transition, _) in edges(*state) {
+                fired[*transition] = true;
+            }
+        }
+        violations.extend(
+            (0..short_circuit)
+                .filter(|transition| !fired[*transition])
+                .map(Violation::DeadTransition),
+        );
+
+        Ok(Soundness { violations })
+    }
+
+    fn single_token(&self, place: usize) -> Marking {
+        let mut marking = Marking::empty(self.indexed.place_count());
+        marking[place] = 1;
+        marking
+    }
+}
+
+/// Nodes reachable from `start` in `graph`, `start` included
+fn reachable(graph: &[Vec<usize>], start: usize) -> Vec<bool> {
+    let mut reached = vec![false; graph.len()];
+    let mut pending = vec![start];
+    reached[start] = true;
+
+    while let Some(node) = pending.pop() {
+        for next in graph[node].iter() {
+            if !reached[*next] {
+                reached[*next] = true;
+                pending.push(*next);
+            }
+        }
+    }
+
+    reached
+}
@@ -4,6 +4,8 @@
 //!
 //! TODO: Documentation
 
+pub mod analysis;
+pub mod mining;
 pub mod net;
 pub mod ui;
 
@@ -27,6 +29,22 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn declare_pn_with_lowercase_names() -> Result<(), String> {
+        // Node names must not clash with the bindings the macro uses internally
+        let pn = petri_net! {
+            places => [place<1>, weight, new_net],
+            transitions => [transition, con_type],
+            connections => [place -> transition, (2) transition -> weight, weight -> con_type, con_type -> new_net]
+        };
+
+        assert_eq!(pn.initial_marking(), vec![1, 0, 0].into());
+        assert_eq!(*pn.connections()[1].weight(), 2);
+        assert_eq!(pn.transitions()[1].name(), "con_type");
+
+        Ok(())
+    }
+
     #[test]
     fn run_simulation() -> Result<(), String> {
         let start = std::time::Instant::now();
@@ -84,4 +102,1571 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn build_net_at_runtime() -> Result<(), String> {
+        let mut builder = net::PetriNetBuilder::new();
+        let p1 = builder.place("P1").tokens(2).id();
+        let p2 = builder.place("P2").id();
+        let t1 = builder.transition("T1").callback(|t, _, _| {
+            println!("{} fired", t.name());
+        });
+        let t1: net::TransitionId = t1.into();
+
+        builder.arc(p1, t1).weight(2);
+        builder.arc("T1", "P2");
+        builder.arc("P2", "T1").inhibitor();
+
+        let pn = builder.build().map_err(|e| e.to_string())?;
+        assert_eq!(pn.connections().len(), 3);
+
+        let simul = net::Simulation::new(pn);
+        simul.run();
+
+        assert_eq!(simul.tokens(p1), Some(0));
+        assert_eq!(simul.tokens(p2), Some(1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn builder_rejects_invalid_arcs() {
+        let mut builder = net::PetriNetBuilder::new();
+        builder.place("P1");
+        builder.place("P2");
+        builder.arc("P1", "P2");
+
+        assert_eq!(
+            builder.build().err(),
+            Some(net::BuildError::SameKindArc("P1".into(), "P2".into()))
+        );
+
+        let mut builder = net::PetriNetBuilder::new();
+        builder.place("P1");
+        builder.transition("T1");
+        builder.arc("T1", "P1").reset();
+
+        assert!(matches!(
+            builder.build(),
+            Err(net::BuildError::OutputArcType(..))
+        ));
+
+        let mut builder = net::PetriNetBuilder::new();
+        builder.place("X");
+        builder.transition("X");
+
+        assert_eq!(
+            builder.build().err(),
+            Some(net::BuildError::DuplicateName("X".into()))
+        );
+    }
+
+    #[test]
+    fn edit_net_in_place() -> Result<(), String> {
+        let mut pn = petri_net! {
+            places => [P1<1>, P2, P3],
+            transitions => [T1, T2],
+            connections => [P1 -> T1, T1 -> P2, P2 -> T2, T2 -> P3]
+        };
+
+        let p2 = net::PlaceId::from(pn.place_with_name("P2").unwrap().as_ref());
+        let t2 = net::TransitionId::from(pn.transition_with_name("T2").unwrap().as_ref());
+
+        pn.rename_transition(t2, "Done")
+            .map_err(|e| e.to_string())?;
+        assert!(pn
+            .connections()
+            .iter()
+            .any(|con| con.transition().name() == "Done"));
+        assert_eq!(
+            pn.rename_place(p2, "P1").err(),
+            Some(net::EditError::DuplicateName("P1".into()))
+        );
+
+        let first = pn.connections()[0].clone();
+        let heavier = pn.set_weight(&first, 2).map_err(|e| e.to_string())?;
+        assert_eq!(*heavier.weight(), 2);
+        assert!(pn.remove_connection(&first).is_err());
+
+        pn.remove_place(p2).map_err(|e| e.to_string())?;
+        assert_eq!(pn.places().len(), 2);
+        assert_eq!(pn.connections().len(), 2);
+
+        pn.remove_transition(t2).map_err(|e| e.to_string())?;
+        assert_eq!(pn.connections().len(), 1);
+
+        let p1 = net::PlaceId::from(pn.place_with_name("P1").unwrap().as_ref());
+        assert_eq!(pn.set_tokens(p1, 2), Ok(1));
+        assert_eq!(pn.initial_marking(), net::Marking::from(vec![2, 0]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn simulate_on_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<net::PetriNet>();
+        assert_send_sync::<net::Simulation>();
+
+        let pn = std::sync::Arc::new(petri_net! {
+            places => [P1<3>, P2],
+            transitions => [T1 -> |t, _, _| {
+                println!("{} fired on {:?}", t.name(), std::thread::current().id());
+            }],
+            connections => [P1 -> T1, T1 -> P2]
+        });
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let pn = pn.clone();
+                std::thread::spawn(move || {
+                    let simul = net::Simulation::new(pn);
+                    simul.run();
+                    simul.marking()
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), net::Marking::from(vec![0, 3]));
+        }
+
+        // The shared net keeps its initial marking
+        assert_eq!(pn.place_with_name("P1").unwrap().tokens(), 3);
+    }
+
+    #[test]
+    fn markings_are_independent_of_the_net() {
+        let pn = petri_net! {
+            places => [P1<2>, P2],
+            transitions => [T1],
+            connections => [P1 -> T1, T1 -> P2]
+        };
+        let initial = pn.initial_marking();
+
+        let simul = net::Simulation::new(pn);
+        simul.run();
+        let last = simul.marking();
+
+        assert_eq!(last, net::Marking::from(vec![0, 2]));
+        assert!(last != initial);
+        assert_eq!(simul.net().initial_marking(), initial);
+
+        // Restart from a saved marking
+        assert!(simul.set_marking(net::Marking::from(vec![1, 1])).is_ok());
+        simul.run();
+        assert_eq!(simul.marking(), net::Marking::from(vec![0, 2]));
+
+        // A marking for another net is rejected
+        assert_eq!(
+            simul.set_marking(net::Marking::from(vec![1, 1, 1])),
+            Err(net::SnapshotError::MarkingLength {
+                expected: 2,
+                found: 3
+            })
+        );
+        assert_eq!(simul.marking(), net::Marking::from(vec![0, 2]));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize_net_and_simulation() -> Result<(), String> {
+        let pn = petri_net! {
+            places => [P1<2>, P2, P3<1>],
+            transitions => [T1, T2],
+            connections => [(2) P1 -> T1, T1 -> P2, P3 @ T2, P2 >> T2]
+        };
+
+        let json = serde_json::to_string(&pn).map_err(|e| e.to_string())?;
+        let restored: net::PetriNet = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+
+        assert_eq!(restored.places(), pn.places());
+        assert_eq!(restored.initial_marking(), pn.initial_marking());
+        assert_eq!(restored.connections(), pn.connections());
+        assert_eq!(
+            restored.connections()[2].connection_type(),
+            net::ConnectionType::INHIBITOR
+        );
+
+        let simul = net::Simulation::new(pn);
+        simul.run();
+
+        let json = serde_json::to_string(&simul.snapshot()).map_err(|e| e.to_string())?;
+        let snapshot: net::SimulationSnapshot =
+            serde_json::from_str(&json).map_err(|e| e.to_string())?;
+
+        let resumed =
+            net::Simulation::restore(restored, snapshot.clone()).map_err(|e| e.to_string())?;
+        assert_eq!(resumed.marking(), simul.marking());
+        assert_eq!(resumed.cycles(), simul.cycles());
+
+        // A snapshot only fits a net with the same places
+        let other = petri_net! {
+            places => [P1<2>, P2],
+            transitions => [T1],
+            connections => [P1 -> T1, T1 -> P2]
+        };
+        assert!(net::Simulation::restore(other, snapshot).is_err());
+
+        // Deserialized nets are checked like built ones
+        let json = serde_json::to_string(resumed.net()).map_err(|e| e.to_string())?;
+        let invalid = [
+            json.replace("\"P3\"", "\"P1\""),
+            json.replace("\"tokens\":2", "\"tokens\":-2"),
+            json.replace("\"weight\":2", "\"weight\":0"),
+        ];
+        for (invalid, error) in invalid.iter().zip([
+            "name `P1` is declared twice",
+            "place `P1` has a negative amount of tokens",
+            "has invalid weight 0",
+        ]) {
+            let message = serde_json::from_str::<net::PetriNet>(invalid)
+                .err()
+                .ok_or("Invalid net was accepted")?
+                .to_string();
+            assert!(message.contains(error), "{}", message);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn export_trace() {
+        let pn = petri_net! {
+            places => [L1<1>, L2],
+            transitions => [T1, T2],
+            connections => [L1 -> T1, L1 -> T2, T1 -> L2, T2 -> L2]
+        };
+
+        let simul = net::Simulation::new(pn);
+        simul.run();
+
+        let trace = simul.trace();
+        assert_eq!(trace.cycles.len(), 2);
+        let conflict = &trace.cycles[0].conflicts[0];
+        assert_eq!(conflict.candidates, vec![0, 1]);
+        assert_eq!(trace.cycles[0].fired, vec![conflict.chosen]);
+
+        let winner = &trace.transitions[conflict.chosen];
+        let json = trace.to_json_lines();
+        let first = json.lines().next().unwrap();
+        assert_eq!(
+            first,
+            format!(
+                "{{\"cycle\":0,\"marking\":{{\"L1\":1,\"L2\":0}},\"fired\":[\"{0}\"],\
+                 \"conflicts\":[{{\"place\":\"L1\",\"candidates\":[\"T1\",\"T2\"],\"chosen\":\"{0}\"}}]}}",
+                winner
+            )
+        );
+
+        let csv = trace.to_csv();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("cycle,L1,L2,T1,T2,conflicts"));
+        assert!(lines
+            .next()
+            .unwrap()
+            .ends_with(&format!("L1:T1|T2->{}", winner)));
+        assert_eq!(lines.next(), Some("1,0,1,0,0,"));
+    }
+
+    #[test]
+    fn export_simulation_as_xes() {
+        let pn = petri_net! {
+            places => [L1<1>, L2, L3],
+            transitions => [Start, Finish],
+            connections => [L1 -> Start, Start -> L2, L2 -> Finish, Finish -> L3]
+        };
+
+        let mut log = mining::EventLog::new();
+        let clock = mining::Clock {
+            start: 1_700_000_000,
+            seconds_per_cycle: 60,
+        };
+
+        let simul = net::Simulation::new(pn);
+        simul.run();
+        log.add_simulation(&simul.trace(), &clock);
+        log.add_simulation(&simul.trace(), &clock);
+
+        assert_eq!(log.traces[0].activities(), vec!["Start", "Finish"]);
+
+        let xes = log.to_xes();
+        assert_eq!(xes.matches("<trace>").count(), 2);
+        assert!(xes.contains(r#"<string key="concept:name" value="run 2"/>"#));
+        assert!(
+            xes.contains(r#"<date key="time:timestamp" value="2023-11-14T22:13:20.000+00:00"/>"#)
+        );
+        assert!(
+            xes.contains(r#"<date key="time:timestamp" value="2023-11-14T22:14:20.000+00:00"/>"#)
+        );
+    }
+
+    #[test]
+    fn replay_event_logs() -> Result<(), String> {
+        let pn = petri_net! {
+            places => [Start<1>, P1, End],
+            transitions => [A, B],
+            connections => [Start -> A, A -> P1, P1 -> B, B -> End]
+        };
+
+        let csv = "case_id,activity,timestamp\n\
+                   1,A,2023-11-14T22:13:20Z\n\
+                   2,B,2023-11-14T22:14:00Z\n\
+                   1,B,2023-11-14T22:15:00+01:00\n\
+                   3,\"C, unknown\",\n";
+        let log = mining::EventLog::from_csv(csv).map_err(|e| e.to_string())?;
+        assert_eq!(log.traces.len(), 3);
+        assert_eq!(log.traces[0].activities(), vec!["A", "B"]);
+        assert_eq!(log.traces[0].events[1].timestamp, Some(1_699_996_500));
+
+        let replay = mining::TokenReplay::new(&pn).replay_log(&log);
+        let fitting = &replay.traces[0];
+        assert!(fitting.fits());
+        assert_eq!(fitting.fitness(), 1.0);
+
+        let skipping = &replay.traces[1];
+        assert_eq!(skipping.fitness(), 0.5);
+        assert_eq!(skipping.missing, vec![0, 1, 0]);
+        assert_eq!(skipping.remaining, vec![1, 0, 0]);
+        assert_eq!(
+            skipping.first_deviation,
+            Some(mining::Deviation {
+                event: 0,
+                activity: "B".into(),
+                kind: mining::DeviationKind::NotEnabled,
+            })
+        );
+
+        let unknown = &replay.traces[2];
+        assert_eq!(
+            unknown.first_deviation.as_ref().map(|d| &d.kind),
+            Some(&mining::DeviationKind::UnknownActivity)
+        );
+        assert_eq!(replay.fitting_traces(), 1.0 / 3.0);
+        assert_eq!(replay.missing(), vec![0, 1, 1]);
+
+        // The same log read back from XES gives the same replay
+        let xes = mining::EventLog::from_xes(&log.to_xes()).map_err(|e| e.to_string())?;
+        assert_eq!(xes, log);
+        assert_eq!(mining::TokenReplay::new(&pn).replay_log(&xes), replay);
+
+        // Markings to replay from or to must fit the net
+        let replay = mining::TokenReplay::new(&pn)
+            .with_initial_marking(vec![1, 0, 0].into())
+            .and_then(|replay| replay.with_final_marking(vec![0, 0, 1].into()))
+            .map_err(|e| e.to_string())?;
+        assert_eq!(replay.replay_log(&xes).fitting_traces(), 1.0 / 3.0);
+        assert_eq!(
+            mining::TokenReplay::new(&pn)
+                .with_initial_marking(vec![1].into())
+                .err(),
+            Some(analysis::AnalysisError::MarkingLength {
+                expected: 3,
+                found: 1
+            })
+        );
+        assert_eq!(
+            mining::TokenReplay::new(&pn)
+                .with_final_marking(vec![0, 0, 0, 1].into())
+                .err(),
+            Some(analysis::AnalysisError::MarkingLength {
+                expected: 3,
+                found: 4
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn ignore_malformed_timestamps() -> Result<(), String> {
+        let timestamps = [
+            "2024-01-01T00:00:00\u{e9}",
+            "2024-01-01T00:00:00.5\u{e9}",
+            "2024-01-01T00:00:00+\u{e9}1:00",
+            "2024-01-01T00:00:00*01:00",
+            "2024-01-01T00:00:00+1:00",
+            "2024/01/01T00:00:00Z",
+            "2024-01-01 00:00:00Z",
+            "2024-01-01T00-00-00Z",
+        ];
+
+        for timestamp in timestamps {
+            let xes = format!(
+                r#"<log><trace><event><string key="concept:name" value="A"/><date key="time:timestamp" value="{}"/></event></trace></log>"#,
+                timestamp
+            );
+            let log = mining::EventLog::from_xes(&xes).map_err(|e| e.to_string())?;
+            assert_eq!(log.traces[0].events[0].timestamp, None, "{}", timestamp);
+        }
+
+        let xes = r#"<log><trace><event><string key="concept:name" value="A"/><date key="time:timestamp" value="2024-01-01T01:00:00.250-01:30"/></event></trace></log>"#;
+        let log = mining::EventLog::from_xes(xes).map_err(|e| e.to_string())?;
+        assert_eq!(log.traces[0].events[0].timestamp, Some(1_704_076_200));
+
+        Ok(())
+    }
+
+    #[test]
+    fn reject_misplaced_xes_elements() {
+        // Traces only belong directly in the log
+        for xes in [
+            r#"<log><trace><event><trace></trace><string key="concept:name" value="A"/></event></trace></log>"#,
+            r#"<log><trace><event><trace/><string key="concept:name" value="A"/></event></trace></log>"#,
+            r#"<trace><event><string key="concept:name" value="A"/></event></trace>"#,
+        ] {
+            assert!(matches!(
+                mining::EventLog::from_xes(xes),
+                Err(mining::LogError::Syntax(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn mine_nets_from_logs() {
+        let mut log = mining::EventLog::new();
+        log.add_trace(mining::LogTrace::from_activities(
+            "1",
+            &["a", "b", "c", "d"],
+        ));
+        log.add_trace(mining::LogTrace::from_activities(
+            "2",
+            &["a", "c", "b", "d"],
+        ));
+        log.add_trace(mining::LogTrace::from_activities(
+            "3",
+            &["a", "b", "c", "e", "e", "d"],
+        ));
+
+        let pn = mining::alpha(&log);
+        assert_eq!(pn.transitions().len(), 5);
+        for name in ["i", "({a},{b})", "({a},{c})", "({b},{d})", "({c},{d})", "o"] {
+            assert!(pn.place_with_name(name).is_some(), "missing place {}", name);
+        }
+        assert_eq!(pn.places().len(), 6);
+        assert_eq!(pn.initial_marking(), vec![1, 0, 0, 0, 0, 0].into());
+
+        // Alpha leaves the self-loop on e disconnected
+        let e = pn.transition_with_name("e").unwrap();
+        assert!(pn
+            .connections()
+            .iter()
+            .all(|c| c.transition().id() != e.id()));
+
+        let pn = mining::alpha_plus(&log);
+        assert!(pn.place_with_name("({c,e},{d,e})").is_some());
+        assert_eq!(pn.places().len(), 6);
+        let replay = mining::TokenReplay::new(&pn).replay_log(&log);
+        assert_eq!(replay.fitness(), 1.0);
+        assert_eq!(replay.fitting_traces(), 1.0);
+
+        // Length-two loops are told apart from parallelism
+        let mut log = mining::EventLog::new();
+        log.add_trace(mining::LogTrace::from_activities(
+            "1",
+            &["a", "b", "c", "b", "c", "b", "d"],
+        ));
+        log.add_trace(mining::LogTrace::from_activities("2", &["a", "b", "d"]));
+        let footprint = mining::Footprint::with_loops(&log, &Default::default());
+        assert!(footprint.loops[1][2]);
+        let pn = mining::alpha_plus(&log);
+        assert!(pn.place_with_name("({a,c},{b})").is_some());
+        assert!(pn.place_with_name("({b},{c,d})").is_some());
+        let replay = mining::TokenReplay::new(&pn).replay_log(&log);
+        assert_eq!(replay.fitness(), 1.0);
+    }
+
+    #[test]
+    fn check_workflow_soundness() -> Result<(), String> {
+        use analysis::{Violation, WorkflowError, WorkflowNet};
+
+        let sound = petri_net! {
+            places => [I<1>, P1, P2, Q1, Q2, O],
+            transitions => [Split, B, C, Join],
+            connections => [
+                I -> Split, Split -> P1, Split -> P2,
+                P1 -> B, B -> Q1, P2 -> C, C -> Q2,
+                Q1 -> Join, Q2 -> Join, Join -> O
+            ]
+        };
+        let workflow = WorkflowNet::new(&sound).map_err(|e| e.to_string())?;
+        assert_eq!((workflow.source(), workflow.sink()), (0, 5));
+        assert_eq!(workflow.short_circuited().transitions().len(), 5);
+        assert!(workflow
+            .soundness(None)
+            .map_err(|e| e.to_string())?
+            .is_sound());
+
+        // An exclusive choice followed by a synchronization gets stuck
+        let unsound = petri_net! {
+            places => [I<1>, P, Q1, Q2, O],
+            transitions => [A, B, C, D, E],
+            connections => [
+                I -> A, A -> P, P -> B, B -> Q1, P -> C, C -> Q2,
+                Q1 -> D, Q2 -> D, D -> O, P -> E, E -> O
+            ]
+        };
+        let soundness = WorkflowNet::new(&unsound)
+            .map_err(|e| e.to_string())?
+            .soundness(Some(100))
+            .map_err(|e| e.to_string())?;
+        assert!(!soundness.is_sound());
+        assert_eq!(
+            soundness.violations,
+            vec![
+                Violation::NoOptionToComplete {
+                    marking: vec![0, 0, 1, 0, 0].into(),
+                    firings: vec![0, 1],
+                },
+                Violation::DeadTransition(3),
+            ]
+        );
+
+        // Producing a token too many breaks proper completion and the short-circuited net
+        // becomes unbounded
+        let improper = petri_net! {
+            places => [I<1>, P, O],
+            transitions => [A, B],
+            connections => [I -> A, A -> P, A -> O, P -> B, B -> O]
+        };
+        let soundness = WorkflowNet::new(&improper)
+            .map_err(|e| e.to_string())?
+            .soundness(None)
+            .map_err(|e| e.to_string())?;
+        assert!(matches!(
+            soundness.violations.as_slice(),
+            [Violation::Unbounded { .. }]
+        ));
+
+        let two_sources = petri_net! {
+            places => [I<1>, J, O],
+            transitions => [A],
+            connections => [I -> A, J -> A, A -> O]
+        };
+        assert_eq!(
+            WorkflowNet::new(&two_sources).err(),
+            Some(WorkflowError::SeveralSources(vec![0, 1]))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn check_ctl_formulas() -> Result<(), String> {
+        use analysis::ctl::{Formula, ModelChecker};
+
+        // A producer filling a buffer of capacity 2 and a consumer that can also stop for good
+        let pn = petri_net! {
+            places => [Free<2>, Buffer, Running<1>, Stopped],
+            transitions => [Produce, Consume, Stop],
+            connections => [
+                Free -> Produce, Produce -> Buffer,
+                Buffer -> Consume, Running -> Consume, Consume -> Running, Consume -> Free,
+                Running -> Stop, Stop -> Stopped
+            ]
+        };
+        let checker = ModelChecker::new(&pn, None).map_err(|e| e.to_string())?;
+        let check = |text: &str| -> Result<_, String> {
+            let formula = Formula::parse(text, &pn).map_err(|e| e.to_string())?;
+            Ok(checker.check(&formula))
+        };
+
+        assert!(check("AG (Buffer <= 2)")?.holds);
+        assert!(check("EF enabled(Consume)")?.holds);
+        assert!(check("E[Stopped = 0 U Buffer = 2]")?.holds);
+
+        // The consumer can stop, after which the buffer fills up and everything stops
+        let verdict = check("AG !deadlock")?;
+        assert!(!verdict.holds);
+        let path = verdict.path.ok_or("no counterexample")?;
+        assert_eq!(path.firings, vec![0, 0, 2]);
+        assert_eq!(path.markings.last(), Some(&vec![0, 2, 0, 1].into()));
+        assert!(!checker.check(&Formula::af(Formula::deadlock())).holds);
+
+        // Producing and consuming forever is a witness of EG
+        let verdict = check("EG Stopped = 0")?;
+        assert!(verdict.holds);
+        let path = verdict.path.ok_or("no witness")?;
+        assert_eq!(path.markings[0], pn.initial_marking());
+        assert!(path.cycle.is_some());
+        assert_eq!(path.firings.len(), path.markings.len());
+
+        let error = Formula::parse("AG Unknown > 1", &pn).unwrap_err();
+        assert_eq!(error.offset, 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn check_ltl_formulas() -> Result<(), String> {
+        use analysis::ltl::{Formula, ModelChecker};
+
+        let pn = petri_net! {
+            places => [Idle<1>, Requested, Granted],
+            transitions => [Request, Grant, Release, Cancel],
+            connections => [
+                Idle -> Request, Request -> Requested, Requested -> Grant, Grant -> Granted,
+                Granted -> Release, Release -> Idle, Requested -> Cancel, Cancel -> Idle
+            ]
+        };
+        let check = |text: &str| -> Result<_, String> {
+            let formula = Formula::parse(text, &pn).map_err(|e| e.to_string())?;
+            ModelChecker::new(&pn)
+                .check(&formula)
+                .map_err(|e| e.to_string())
+        };
+
+        assert!(check("G (Requested + 1 > 0)").is_err());
+        assert!(check("G F (Idle = 1)")?.holds);
+        assert!(check("G (Granted = 1 -> X Idle = 1)")?.holds);
+        assert!(check("Idle = 1 U Requested = 1")?.holds);
+
+        // Cancelling every request forever never grants anything
+        let verdict = check("G (Requested = 1 -> F Granted = 1)")?;
+        assert!(!verdict.holds);
+        let lasso = verdict.counterexample.ok_or("no counterexample")?;
+        assert_eq!(lasso.markings[0], pn.initial_marking());
+        assert!(lasso.firings.iter().all(|t| *t == 0 || *t == 3));
+
+        // The lasso is a run of the net that closes on itself
+        let indexed = net::IndexedNet::new(&pn);
+        let cycle = lasso.cycle.ok_or("no cycle")?;
+        let mut marking = pn.initial_marking();
+        for (step, transition) in lasso.firings.iter().enumerate() {
+            assert_eq!(marking, lasso.markings[step]);
+            marking = indexed
+                .successor(*transition, &marking)
+                .ok_or("not enabled")?;
+            let expected = lasso
+                .markings
+                .get(step + 1)
+                .unwrap_or(&lasso.markings[cycle]);
+            assert_eq!(&marking, expected);
+        }
+
+        // A run ending in a deadlock stays there forever
+        let pn = petri_net! {
+            places => [P<1>, Q],
+            transitions => [T],
+            connections => [P -> T, T -> Q]
+        };
+        let checker = ModelChecker::new(&pn);
+        let eventually_q =
+            Formula::finally(Formula::parse("Q = 1", &pn).map_err(|e| e.to_string())?);
+        assert!(
+            checker
+                .check(&eventually_q)
+                .map_err(|e| e.to_string())?
+                .holds
+        );
+
+        let verdict = checker
+            .check(&Formula::globally(
+                Formula::parse("P = 1", &pn).map_err(|e| e.to_string())?,
+            ))
+            .map_err(|e| e.to_string())?;
+        let path = verdict.counterexample.ok_or("no counterexample")?;
+        assert_eq!(path.firings, vec![0]);
+        assert_eq!(path.markings, vec![vec![1, 0].into(), vec![0, 1].into()]);
+        assert_eq!(path.cycle, None);
+
+        // Unbounded nets are reported instead of explored forever
+        let pn = petri_net! {
+            places => [P<1>, Q],
+            transitions => [T],
+            connections => [P -> T, T -> P, T -> Q]
+        };
+        let never_q = Formula::globally(Formula::parse("Q = 0", &pn).map_err(|e| e.to_string())?);
+        let infinitely_t = Formula::globally(Formula::finally(Formula::enabled(0)));
+        for formula in [never_q, infinitely_t] {
+            match ModelChecker::new(&pn).check(&formula) {
+                Err(analysis::AnalysisError::Unbounded { marking, firings }) => {
+                    assert_eq!(marking, vec![1, 1].into());
+                    assert_eq!(firings, vec![0]);
+                }
+                other => return Err(format!("expected unbounded, got {:?}", other)),
+            }
+        }
+        assert_eq!(
+            ModelChecker::new(&pn)
+                .with_initial_marking(vec![1, 0, 0].into())
+                .check(&Formula::True),
+            Err(analysis::AnalysisError::MarkingLength {
+                expected: 2,
+                found: 3
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn query_reachability() -> Result<(), String> {
+        use analysis::{Reachability, Strategy};
+
+        // Two producers filling a buffer that a consumer empties two tokens at a time
+        let pn = petri_net! {
+            places => [Ready<1>, Buffer, Consumed],
+            transitions => [ProduceOne, ProduceTwo, Consume],
+            connections => [
+                Ready -> ProduceOne, ProduceOne -> Ready, ProduceOne -> Buffer,
+                Ready -> ProduceTwo, ProduceTwo -> Ready, (2) ProduceTwo -> Buffer,
+                (2) Buffer -> Consume, Consume -> Consumed
+            ]
+        };
+
+        let path = pn
+            .can_reach(|m| m[2] == 2)
+            .map_err(|e| e.to_string())?
+            .ok_or("unreachable")?;
+        assert_eq!(path.firings, vec![1, 1, 2, 2]);
+        assert_eq!(path.markings.last(), Some(&vec![1, 0, 2].into()));
+
+        let target: net::Marking = vec![0, 5, 0].into();
+        for strategy in [Strategy::BreadthFirst, Strategy::AStar] {
+            let path = Reachability::new(&pn)
+                .strategy(strategy)
+                .limit(1000)
+                .can_cover(&target)
+                .map_err(|e| e.to_string())?
+                .ok_or("not coverable")?;
+            assert_eq!(path.firings.len(), 3);
+        }
+
+        let path = Reachability::new(&pn)
+            .strategy(Strategy::DepthFirst)
+            .limit(1000)
+            .can_reach(|m| m[1] >= 3)
+            .map_err(|e| e.to_string())?
+            .ok_or("unreachable")?;
+        assert!(path.markings.last().ok_or("empty path")?[1] >= 3);
+
+        // Nothing ever empties Consumed, and the net is unbounded
+        assert_eq!(
+            Reachability::new(&pn).limit(50).can_reach(|m| m[0] == 2),
+            Err(analysis::AnalysisError::StateLimit(50))
+        );
+        // Nothing produces into Start, so covering it twice is ruled out without searching
+        let chain = petri_net! {
+            places => [Start<1>, End],
+            transitions => [T],
+            connections => [Start -> T, T -> End]
+        };
+        assert_eq!(
+            Reachability::new(&chain)
+                .limit(1)
+                .can_cover(&vec![2, 0].into()),
+            Ok(None)
+        );
+
+        // Initial markings must have one entry per place
+        assert_eq!(
+            Reachability::new(&chain)
+                .with_initial_marking(vec![1].into())
+                .can_reach(|m| m[0] == 0),
+            Err(analysis::AnalysisError::MarkingLength {
+                expected: 2,
+                found: 1,
+            })
+        );
+
+        // Targets must have one entry per place, with or without partial-order reduction
+        for target in [vec![2], vec![0, 1, 0]] {
+            for reachability in [
+                Reachability::new(&chain),
+                Reachability::new(&chain).partial_order(&[]),
+            ] {
+                assert_eq!(
+                    reachability.can_cover(&target.clone().into()),
+                    Err(analysis::AnalysisError::MarkingLength {
+                        expected: 2,
+                        found: target.len(),
+                    })
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn reduce_interleavings() -> Result<(), String> {
+        use analysis::{Reachability, StateSpace};
+
+        // Four independent processes, each moving a token from Idle to Busy to Done
+        let mut builder = net::PetriNetBuilder::new();
+        for i in 0..4 {
+            builder.place(&format!("Idle{}", i)).tokens(1);
+            builder.place(&format!("Busy{}", i));
+            builder.place(&format!("Done{}", i));
+            builder.transition(&format!("Start{}", i));
+            builder.transition(&format!("Finish{}", i));
+            builder.arc(format!("Idle{}", i), format!("Start{}", i));
+            builder.arc(format!("Start{}", i), format!("Busy{}", i));
+            builder.arc(format!("Busy{}", i), format!("Finish{}", i));
+            builder.arc(format!("Finish{}", i), format!("Done{}", i));
+        }
+        let pn = builder.build().map_err(|e| e.to_string())?;
+
+        let full = StateSpace::new(&pn).map_err(|e| e.to_string())?;
+        let reduced = StateSpace::reduced(&pn, &[]).map_err(|e| e.to_string())?;
+        assert_eq!(full.len(), 81);
+        assert_eq!(reduced.len(), 9);
+        assert_eq!(full.deadlocks().len(), 1);
+        assert_eq!(
+            reduced.marking(reduced.deadlocks()[0]),
+            full.marking(full.deadlocks()[0])
+        );
+
+        // Initial markings must fit the net, with or without reduction
+        let indexed = net::IndexedNet::new(&pn);
+        let stubborn = analysis::StubbornSets::new(&indexed);
+        for initial in [vec![], vec![1; 13]] {
+            let expected = Some(analysis::AnalysisError::MarkingLength {
+                expected: 12,
+                found: initial.len(),
+            });
+            assert_eq!(
+                StateSpace::explore(&indexed, initial.clone().into(), None).err(),
+                expected
+            );
+            assert_eq!(
+                StateSpace::explore_reduced(&indexed, initial.into(), None, &stubborn).err(),
+                expected
+            );
+        }
+
+        // Both tokens of the first process stay observable
+        let visible = StateSpace::reduced(&pn, &[0, 1]).map_err(|e| e.to_string())?;
+        let mut seen: Vec<(i32, i32)> = visible.markings().iter().map(|m| (m[0], m[1])).collect();
+        seen.sort_unstable();
+        seen.dedup();
+        assert_eq!(seen, vec![(0, 0), (0, 1), (1, 0)]);
+        assert!(visible.len() < full.len());
+
+        // The places of the target are kept visible, the other processes are still reduced
+        let done: net::Marking = vec![0, 0, 1, 0, 0, 1, 0, 0, 1, 0, 0, 1].into();
+        let path = Reachability::new(&pn)
+            .partial_order(&[])
+            .limit(30)
+            .can_cover(&done)
+            .map_err(|e| e.to_string())?
+            .ok_or("not coverable")?;
+        assert_eq!(path.firings.len(), 8);
+        assert_eq!(
+            Reachability::new(&pn).limit(30).can_cover(&done),
+            Err(analysis::AnalysisError::StateLimit(30))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn explore_symbolically() -> Result<(), String> {
+        use analysis::ctl::{Atom, Comparison};
+        use analysis::{StateSpace, SymbolicStateSpace};
+
+        // Philosophers taking their left fork, then their right one
+        let philosophers = |count: usize| {
+            let mut builder = net::PetriNetBuilder::new();
+            for i in 0..count {
+                let right = (i + 1) % count;
+                builder.place(&format!("Fork{}", i)).tokens(1);
+                builder.place(&format!("Thinking{}", i)).tokens(1);
+                builder.place(&format!("HasLeft{}", i));
+                builder.place(&format!("Eating{}", i));
+                builder.transition(&format!("TakeLeft{}", i));
+                builder.transition(&format!("TakeRight{}", i));
+                builder.transition(&format!("Release{}", i));
+                builder.arc(format!("Thinking{}", i), format!("TakeLeft{}", i));
+                builder.arc(format!("Fork{}", i), format!("TakeLeft{}", i));
+                builder.arc(format!("TakeLeft{}", i), format!("HasLeft{}", i));
+                builder.arc(format!("HasLeft{}", i), format!("TakeRight{}", i));
+                builder.arc(format!("Fork{}", right), format!("TakeRight{}", i));
+                builder.arc(format!("TakeRight{}", i), format!("Eating{}", i));
+                builder.arc(format!("Eating{}", i), format!("Release{}", i));
+                builder.arc(format!("Release{}", i), format!("Thinking{}", i));
+                builder.arc(format!("Release{}", i), format!("Fork{}", i));
+                builder.arc(format!("Release{}", i), format!("Fork{}", right));
+            }
+            builder.build().map_err(|e| e.to_string())
+        };
+
+        let small = philosophers(5)?;
+        let explicit = StateSpace::new(&small).map_err(|e| e.to_string())?;
+        let symbolic = SymbolicStateSpace::new(&small, 1).map_err(|e| e.to_string())?;
+        assert_eq!(symbolic.len(), explicit.len() as u128);
+        assert_eq!(symbolic.deadlock_count(), 1);
+        assert!(explicit.markings().iter().all(|m| symbolic.contains(m)));
+
+        let mut large =
+            SymbolicStateSpace::new(&philosophers(30)?, 1).map_err(|e| e.to_string())?;
+        assert!(large.len() > 1_000_000_000);
+        let deadlock = large.deadlock().ok_or("no deadlock")?;
+        assert!((0..30).all(|i| deadlock[4 * i + 2] == 1));
+
+        // Neighbours share a fork, so they never eat together
+        let eating = |i: usize| Atom::Tokens {
+            place: 4 * i + 3,
+            comparison: Comparison::Equal,
+            value: 1,
+        };
+        let reach = |space: &mut SymbolicStateSpace, atoms: &[Atom]| {
+            space.can_reach(atoms).map_err(|e| e.to_string())
+        };
+        assert!(reach(&mut large, &[eating(0), eating(1)])?.is_none());
+        assert!(reach(&mut large, &[eating(0), eating(2)])?.is_some());
+        assert_eq!(
+            large.can_reach(&[eating(30)]),
+            Err(analysis::AnalysisError::UnknownPlace {
+                place: 123,
+                places: 120
+            })
+        );
+        assert_eq!(
+            large.can_reach(&[Atom::Enabled(150)]),
+            Err(analysis::AnalysisError::UnknownTransition {
+                transition: 150,
+                transitions: 90
+            })
+        );
+        assert_eq!(
+            SymbolicStateSpace::explore(&net::IndexedNet::new(&small), vec![1].into(), 1)
+                .map(|space| space.len()),
+            Err(analysis::AnalysisError::MarkingLength {
+                expected: 20,
+                found: 1
+            })
+        );
+
+        let unbounded = petri_net! {
+            places => [Source<1>, Sink],
+            transitions => [Produce],
+            connections => [Source -> Produce, Produce -> Source, Produce -> Sink]
+        };
+        assert_eq!(
+            SymbolicStateSpace::new(&unbounded, 3).map(|space| space.len()),
+            Err(analysis::AnalysisError::BoundExceeded { place: 1, bound: 3 })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn unfold_safe_nets() -> Result<(), String> {
+        use analysis::{StateSpace, Unfolding, UnfoldingError};
+
+        let independent = petri_net! {
+            places => [A<1>, B, C<1>, D],
+            transitions => [AB, BA, CD, DC],
+            connections => [A -> AB, AB -> B, B -> BA, BA -> A, C -> CD, CD -> D, D -> DC, DC -> C]
+        };
+        let prefix = Unfolding::new(&independent, None).map_err(|e| e.to_string())?;
+        assert_eq!(prefix.events().len(), 4);
+        assert_eq!(prefix.cutoffs().len(), 2);
+        assert_eq!(prefix.conditions().len(), 6);
+        assert_eq!(prefix.deadlock(), None);
+
+        // Each worker takes both locks, in opposite orders
+        let locks = petri_net! {
+            places => [Idle1<1>, Idle2<1>, Lock1<1>, Lock2<1>, Holds1, Holds2, Work1, Work2],
+            transitions => [First1, Second1, Done1, First2, Second2, Done2],
+            connections => [
+                Idle1 -> First1, Lock1 -> First1, First1 -> Holds1,
+                Holds1 -> Second1, Lock2 -> Second1, Second1 -> Work1,
+                Work1 -> Done1, Done1 -> Idle1, Done1 -> Lock1, Done1 -> Lock2,
+                Idle2 -> First2, Lock2 -> First2, First2 -> Holds2,
+                Holds2 -> Second2, Lock1 -> Second2, Second2 -> Work2,
+                Work2 -> Done2, Done2 -> Idle2, Done2 -> Lock1, Done2 -> Lock2
+            ]
+        };
+        let prefix = Unfolding::new(&locks, None).map_err(|e| e.to_string())?;
+        let space = StateSpace::new(&locks).map_err(|e| e.to_string())?;
+        for event in 0..prefix.events().len() {
+            assert!(space.state(&prefix.marking(event)).is_some());
+        }
+        let path = prefix.deadlock().ok_or("no deadlock")?;
+        let dead = path.markings.last().ok_or("empty path")?;
+        assert_eq!(dead, &vec![0, 0, 0, 0, 1, 1, 0, 0].into());
+        assert_eq!(
+            space.state(dead).map(|s| space.successors(s).len()),
+            Some(0)
+        );
+
+        let unsafe_net = petri_net! {
+            places => [A<1>, B<1>],
+            transitions => [T],
+            connections => [A -> T, T -> B]
+        };
+        assert_eq!(
+            Unfolding::new(&unsafe_net, None).map(|u| u.events().len()),
+            Err(UnfoldingError::NotSafe {
+                place: 1,
+                firings: vec![0]
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn find_siphons_and_traps() -> Result<(), String> {
+        use analysis::{Liveness, Structure};
+
+        // A free-choice net where choosing Leave empties the loop for good
+        let pn = petri_net! {
+            places => [Ready<1>, Working, Gone],
+            transitions => [Start, Finish, Leave],
+            connections => [
+                Ready -> Start, Start -> Working, Working -> Finish, Finish -> Ready,
+                Ready -> Leave, Leave -> Gone
+            ]
+        };
+        let structure = Structure::new(&pn);
+        assert_eq!(structure.minimal_siphons(), vec![vec![0, 1]]);
+        assert_eq!(structure.minimal_traps(), vec![vec![2]]);
+        assert!(structure.is_trap(&[0, 1, 2]));
+        assert!(structure.maximal_trap(&[0, 1]).is_empty());
+        assert_eq!(
+            structure.liveness(&pn.initial_marking()),
+            Ok(Liveness::NotLive { siphon: vec![0, 1] })
+        );
+        assert_eq!(
+            structure.liveness(&vec![1, 0].into()),
+            Err(analysis::AnalysisError::MarkingLength {
+                expected: 3,
+                found: 2
+            })
+        );
+
+        let cycle = petri_net! {
+            places => [Ready<1>, Working],
+            transitions => [Start, Finish],
+            connections => [Ready -> Start, Start -> Working, Working -> Finish, Finish -> Ready]
+        };
+        let structure = Structure::new(&cycle);
+        assert_eq!(
+            structure.liveness(&cycle.initial_marking()),
+            Ok(Liveness::Live)
+        );
+        assert!(structure
+            .potential_deadlocks(&cycle.initial_marking())
+            .map_err(|e| e.to_string())?
+            .is_empty());
+
+        // Taking two locks in opposite orders is not free-choice, and both locks can be emptied
+        let locks = petri_net! {
+            places => [Idle1<1>, Idle2<1>, Lock1<1>, Lock2<1>, Holds1, Holds2],
+            transitions => [First1, Second1, First2, Second2],
+            connections => [
+                Idle1 -> First1, Lock1 -> First1, First1 -> Holds1,
+                Holds1 -> Second1, Lock2 -> Second1, Second1 -> Idle1, Second1 -> Lock1, Second1 -> Lock2,
+                Idle2 -> First2, Lock2 -> First2, First2 -> Holds2,
+                Holds2 -> Second2, Lock1 -> Second2, Second2 -> Idle2, Second2 -> Lock1, Second2 -> Lock2
+            ]
+        };
+        let structure = Structure::new(&locks);
+        assert_eq!(
+            structure.liveness(&locks.initial_marking()),
+            Ok(Liveness::NotFreeChoice)
+        );
+        let deadlocks = structure
+            .potential_deadlocks(&locks.initial_marking())
+            .map_err(|e| e.to_string())?;
+        assert!(deadlocks.contains(&vec![2, 3]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn classify_nets() {
+        use analysis::{ClassViolation, Classification, NetClass};
+
+        let pn = petri_net! {
+            places => [Ready<1>, Working, Gone],
+            transitions => [Start, Finish, Leave],
+            connections => [
+                Ready -> Start, Start -> Working, Working -> Finish, Finish -> Ready,
+                Ready -> Leave, Leave -> Gone
+            ]
+        };
+        let classification = Classification::new(&pn);
+        assert_eq!(
+            classification.classes(),
+            vec![
+                NetClass::StateMachine,
+                NetClass::FreeChoice,
+                NetClass::ExtendedFreeChoice,
+                NetClass::AsymmetricChoice,
+                NetClass::Ordinary,
+                NetClass::Pure,
+                NetClass::Conservative
+            ]
+        );
+        assert_eq!(
+            classification.violations(NetClass::MarkedGraph),
+            &[
+                ClassViolation::Connections(vec![0, 4]),
+                ClassViolation::Place(2)
+            ]
+        );
+
+        let pn = petri_net! {
+            places => [P<1>, Q<1>, R],
+            transitions => [T, U, V],
+            connections => [P -> T, Q -> T, P -> U, Q -> V, T -> R, (2) U -> R, V -> Q]
+        };
+        let classification = Classification::new(&pn);
+        assert!(classification.classes().is_empty());
+        let connections = |class| {
+            classification
+                .violations(class)
+                .iter()
+                .map(|violation| match violation {
+                    ClassViolation::Connections(connections) => connections.clone(),
+                    _ => vec![],
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            connections(NetClass::FreeChoice),
+            vec![vec![0, 1, 2], vec![0, 1, 3]]
+        );
+        assert_eq!(
+            connections(NetClass::ExtendedFreeChoice),
+            vec![vec![0, 1, 2], vec![0, 1, 3]]
+        );
+        assert_eq!(
+            connections(NetClass::AsymmetricChoice),
+            vec![vec![0, 1, 2, 3]]
+        );
+        assert_eq!(connections(NetClass::Ordinary), vec![vec![5]]);
+        assert_eq!(connections(NetClass::Pure), vec![vec![3, 6]]);
+        assert_eq!(
+            connections(NetClass::Conservative),
+            vec![vec![0, 1, 4], vec![2, 5]]
+        );
+    }
+
+    #[test]
+    fn reduce_nets() -> Result<(), String> {
+        use analysis::{Reduction, Rule, StateSpace};
+
+        let pn = petri_net! {
+            places => [Idle<1>, Busy, Cleanup, Mirror<1>],
+            transitions => [Start, Finish, Tidy, Audit],
+            connections => [
+                Idle -> Start, Mirror -> Start, Start -> Busy,
+                Busy -> Finish, Finish -> Cleanup, Finish -> Mirror,
+                Cleanup -> Tidy, Tidy -> Idle,
+                Busy -> Audit, Audit -> Busy
+            ]
+        };
+        let reduction = Reduction::new(&pn, &[1]).map_err(|e| e.to_string())?;
+        let reduced = reduction.net();
+        assert_eq!(reduced.places().len(), 2);
+        assert_eq!(reduced.transitions().len(), 2);
+        assert_eq!(reduction.removed_places(), &[3]);
+        assert_eq!(reduction.removed_transitions(), &[2, 3]);
+        assert_eq!(
+            reduction.rules(),
+            &[
+                Rule::SeriesPlaces,
+                Rule::ParallelPlaces,
+                Rule::SelfLoopTransition
+            ]
+        );
+
+        // Idle and Cleanup were fused, their tokens add up
+        let fused = reduction.place(0).ok_or("Idle was removed")?;
+        assert_eq!(reduction.place(2), Some(fused));
+        assert_eq!(reduction.place_origins(fused), &[2, 0]);
+        assert_eq!(reduced.places()[fused].tokens(), 1);
+
+        // Busy is kept, with the same reachable token counts
+        let busy = reduction.place(1).ok_or("Busy was removed")?;
+        assert_eq!(reduced.places()[busy].id(), pn.places()[1].id());
+        let tokens = |net: &net::PetriNet, place: usize| -> Result<Vec<i32>, String> {
+            let space = StateSpace::new(net).map_err(|e| e.to_string())?;
+            let mut tokens: Vec<i32> = space.markings().iter().map(|m| m[place]).collect();
+            tokens.sort_unstable();
+            tokens.dedup();
+            Ok(tokens)
+        };
+        assert_eq!(tokens(&pn, 1)?, tokens(reduced, busy)?);
+
+        // Without anything to keep, the cycle fuses into a self-loop, which is removed as well
+        let reduction = Reduction::new(&pn, &[]).map_err(|e| e.to_string())?;
+        assert!(reduction.net().places().is_empty());
+        assert!(reduction.net().transitions().is_empty());
+
+        // A self-loop needing more tokens than its place ever holds is dead, and stays
+        let pn = petri_net! {
+            places => [P<1>],
+            transitions => [Live, Dead],
+            connections => [P -> Live, Live -> P, (2) P -> Dead, (2) Dead -> P]
+        };
+        let reduction = Reduction::new(&pn, &[]).map_err(|e| e.to_string())?;
+        assert_eq!(reduction.rules(), &[Rule::SelfLoopTransition]);
+        assert_eq!(reduction.removed_transitions(), &[0]);
+        assert_eq!(reduction.net().places().len(), 1);
+        assert_eq!(reduction.net().transitions()[0].name(), "Dead");
+        assert_eq!(
+            Reduction::new(&pn, &[1]).err(),
+            Some(analysis::AnalysisError::UnknownPlace {
+                place: 1,
+                places: 1
+            })
+        );
+
+        // A long ring with every step doubled, which rules only reduce one node at a time
+        let length = 1000;
+        let mut builder = net::PetriNetBuilder::new();
+        for step in 0..length {
+            let tokens = if step == 0 { 1 } else { 0 };
+            builder.place(&format!("P{}", step)).tokens(tokens);
+            builder.place(&format!("Q{}", step)).tokens(tokens);
+        }
+        for step in 0..length {
+            for branch in ["A", "B"] {
+                let transition = format!("T{}{}", step, branch);
+                builder.transition(&transition);
+                for place in ["P", "Q"] {
+                    builder.arc(format!("{}{}", place, step), transition.as_str());
+                    builder.arc(
+                        transition.as_str(),
+                        format!("{}{}", place, (step + 1) % length),
+                    );
+                }
+            }
+        }
+        let ring = builder.build().map_err(|e| e.to_string())?;
+
+        // Only the kept place and the transitions around it are left
+        let reduction = Reduction::new(&ring, &[0]).map_err(|e| e.to_string())?;
+        assert_eq!(reduction.net().places().len(), 2);
+        assert_eq!(reduction.net().transitions().len(), 2);
+        assert_eq!(reduction.rules().len(), 3 * length - 2);
+        assert_eq!(reduction.place(0), Some(0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn solve_state_equation() -> Result<(), String> {
+        use analysis::{Reachability, StateEquation, Verdict};
+
+        let pn = petri_net! {
+            places => [Idle1<1>, Critical1, Idle2<1>, Critical2, Lock<1>],
+            transitions => [Enter1, Exit1, Enter2, Exit2],
+            connections => [
+                Idle1 -> Enter1, Lock -> Enter1, Enter1 -> Critical1,
+                Critical1 -> Exit1, Exit1 -> Idle1, Exit1 -> Lock,
+                Idle2 -> Enter2, Lock -> Enter2, Enter2 -> Critical2,
+                Critical2 -> Exit2, Exit2 -> Idle2, Exit2 -> Lock
+            ]
+        };
+        let equation = StateEquation::new(&pn);
+        assert_eq!(equation.incidence()[4], vec![-1, 1, -1, 1]);
+
+        // Mutual exclusion holds without exploring a single marking
+        let both: net::Marking = vec![0, 1, 0, 1, 0].into();
+        assert_eq!(equation.can_reach(&both), Ok(Verdict::Unreachable));
+        assert_eq!(
+            equation.can_cover(&vec![0, 1, 0, 1, 0].into()),
+            Ok(Verdict::Unreachable)
+        );
+        assert!(Reachability::new(&pn)
+            .can_reach(|m| *m == both)
+            .map_err(|e| e.to_string())?
+            .is_none());
+        assert_eq!(
+            equation.can_reach(&vec![0, 1, 1, 0, 0].into()),
+            Ok(Verdict::Solution(vec![1, 0, 0, 0]))
+        );
+        assert_eq!(equation.bound(1), Ok(Some(1)));
+        assert_eq!(equation.bound(4), Ok(Some(1)));
+
+        // Only integer firing counts are solutions
+        let pn = petri_net! {
+            places => [Source<1>, Pairs, Sink],
+            transitions => [Split, Merge],
+            connections => [Source -> Split, (2) Split -> Pairs, (2) Pairs -> Merge, Merge -> Sink]
+        };
+        let equation = StateEquation::new(&pn);
+        assert_eq!(
+            equation.can_reach(&vec![0, 1, 0].into()),
+            Ok(Verdict::Unreachable)
+        );
+        assert_eq!(
+            equation.can_reach(&vec![0, 0, 1].into()),
+            Ok(Verdict::Solution(vec![1, 1]))
+        );
+        assert_eq!(equation.bound(1), Ok(Some(2)));
+
+        // A generator bounds nothing it feeds, while its input place keeps its bound
+        let pn = petri_net! {
+            places => [Ready<1>, Out],
+            transitions => [Produce],
+            connections => [Ready -> Produce, Produce -> Ready, Produce -> Out]
+        };
+        let equation = StateEquation::new(&pn);
+        assert_eq!(equation.bound(0), Ok(Some(1)));
+        assert_eq!(equation.bound(1), Ok(None));
+        assert_eq!(
+            equation.can_cover(&vec![1, 5].into()),
+            Ok(Verdict::Solution(vec![5]))
+        );
+        assert_eq!(
+            equation.bound(2),
+            Err(analysis::AnalysisError::UnknownPlace {
+                place: 2,
+                places: 2
+            })
+        );
+        let shifted = equation.clone().with_initial_marking(vec![1].into());
+        assert_eq!(
+            shifted.can_reach(&vec![1, 0].into()),
+            Err(analysis::AnalysisError::MarkingLength {
+                expected: 2,
+                found: 1
+            })
+        );
+        assert_eq!(
+            shifted.bound(0),
+            Err(analysis::AnalysisError::MarkingLength {
+                expected: 2,
+                found: 1
+            })
+        );
+        for target in [vec![1], vec![1, 5, 0]] {
+            assert_eq!(
+                equation.can_cover(&target.clone().into()),
+                Err(analysis::AnalysisError::MarkingLength {
+                    expected: 2,
+                    found: target.len(),
+                })
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn explore_interactively() -> Result<(), String> {
+        use crossterm::event::KeyCode;
+        use ui::Explorer;
+
+        let pn = petri_net! {
+            places => [Ready<1>, Left, Right],
+            transitions => [GoLeft, GoRight, Back],
+            connections => [Ready -> GoLeft, GoLeft -> Left, Ready -> GoRight, GoRight -> Right, Left -> Back, Back -> Ready]
+        };
+        let simul = net::Simulation::new(pn);
+        let mut explorer = Explorer::new(&simul);
+        assert_eq!(explorer.selected(), Some(0));
+
+        // The selection stays on the enabled transitions
+        explorer.handle_key(KeyCode::Down);
+        explorer.handle_key(KeyCode::Down);
+        assert_eq!(explorer.selected(), Some(1));
+
+        // Firing the selected transition alone, then undoing it
+        explorer.handle_key(KeyCode::Enter);
+        assert_eq!(simul.marking(), vec![0, 0, 1].into());
+        assert_eq!(explorer.selected(), None);
+        assert_eq!(simul.cycles()[0].fired, vec![1]);
+        explorer.handle_key(KeyCode::Char('u'));
+        assert_eq!(simul.marking(), vec![1, 0, 0].into());
+        assert!(simul.cycles().is_empty());
+
+        explorer.handle_key(KeyCode::Enter);
+        explorer.handle_key(KeyCode::Enter);
+        assert_eq!(simul.marking(), vec![1, 0, 0].into());
+        assert_eq!(simul.cycles().len(), 2);
+
+        explorer.handle_key(KeyCode::Char('a'));
+        assert!(explorer.is_auto_running());
+        explorer.handle_key(KeyCode::Char('a'));
+        assert!(!explorer.is_auto_running());
+
+        Ok(())
+    }
+
+    #[test]
+    fn choose_conflict_winners() -> Result<(), String> {
+        use crossterm::event::KeyCode;
+        use ui::Explorer;
+
+        let pn = petri_net! {
+            places => [Shared<1>, Left, Right],
+            transitions => [TakeLeft, TakeRight],
+            connections => [Shared -> TakeLeft, TakeLeft -> Left, Shared -> TakeRight, TakeRight -> Right]
+        };
+        let simul = net::Simulation::new(pn);
+
+        // Declining a conflict leaves the simulation untouched
+        let mut asked = vec![];
+        assert_eq!(
+            simul.step_with(|conflict| {
+                asked.push(conflict.clone());
+                None
+            }),
+            None
+        );
+        assert_eq!(asked.len(), 1);
+        assert_eq!((asked[0].place, asked[0].tokens), (0, 1));
+        assert_eq!(asked[0].candidates, vec![0, 1]);
+        assert!(simul.cycles().is_empty());
+
+        // So does picking a transition that is not a candidate
+        assert_eq!(simul.step_with(|_| Some(5)), None);
+        assert_eq!(simul.marking(), vec![1, 0, 0].into());
+        assert!(simul.cycles().is_empty());
+
+        // The explorer pauses on the conflict until a winner is picked
+        let mut explorer = Explorer::new(&simul);
+        explorer.handle_key(KeyCode::Char(' '));
+        assert_eq!(explorer.conflict(), Some(&asked[0]));
+        explorer.handle_key(KeyCode::Esc);
+        assert!(explorer.conflict().is_none());
+        assert!(simul.cycles().is_empty());
+
+        explorer.handle_key(KeyCode::Char(' '));
+        explorer.handle_key(KeyCode::Down);
+        explorer.handle_key(KeyCode::Enter);
+        assert!(explorer.conflict().is_none());
+        assert_eq!(simul.marking(), vec![0, 0, 1].into());
+        let cycle = simul.cycles().pop().ok_or("No cycle was recorded")?;
+        assert_eq!(cycle.fired, vec![1]);
+        assert_eq!(cycle.conflicts[0].chosen, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn travel_through_time() -> Result<(), String> {
+        use std::sync::Arc;
+
+        let pn = Arc::new(petri_net! {
+            places => [Ready<1>, Left, Right],
+            transitions => [GoLeft, GoRight, BackLeft, BackRight],
+            connections => [
+                Ready -> GoLeft, GoLeft -> Left, Left -> BackLeft, BackLeft -> Ready,
+                Ready -> GoRight, GoRight -> Right, Right -> BackRight, BackRight -> Ready
+            ]
+        });
+        let simul = net::Simulation::new(pn.clone());
+        simul.step_with(|_| Some(0));
+        simul.step();
+        assert_eq!(simul.cycles().len(), 2);
+
+        // Undone cycles can be redone, or jumped over
+        let undone = simul.undo().ok_or("Nothing to undo")?;
+        assert_eq!(undone.fired, vec![2]);
+        assert_eq!(simul.marking(), vec![0, 1, 0].into());
+        simul.undo();
+        assert!(simul.undo().is_none());
+        assert_eq!(simul.marking(), vec![1, 0, 0].into());
+        assert!(simul.cycles().is_empty());
+
+        assert_eq!(simul.redo().map(|cycle| cycle.fired), Some(vec![0]));
+        assert_eq!(simul.marking(), vec![0, 1, 0].into());
+        assert!(simul.jump_to(2));
+        assert!(!simul.jump_to(3));
+        assert_eq!(simul.marking(), vec![1, 0, 0].into());
+        assert!(simul.redo().is_none());
+
+        // Running the same cycle again stays on the branch, another choice starts a new one
+        simul.jump_to(0);
+        simul.step_with(|_| Some(0));
+        assert_eq!(simul.branches().len(), 1);
+        simul.jump_to(0);
+        simul.step_with(|conflict| conflict.candidates.last().copied());
+        assert_eq!(simul.branches().len(), 2);
+        assert_eq!(simul.branch(), 1);
+        assert_eq!(simul.marking(), vec![0, 0, 1].into());
+        assert_eq!(simul.common_cycles(0, 1), Some(0));
+        assert_eq!(simul.common_cycles(0, 2), None);
+
+        simul.switch_branch(0);
+        simul.undo();
+        simul.fire_transition(2);
+        simul.step_with(|_| Some(0));
+        assert_eq!(simul.branches().len(), 2);
+        assert_eq!(simul.cycles().len(), 3);
+        simul.jump_to(2);
+        simul.step_with(|conflict| conflict.candidates.last().copied());
+        assert_eq!(simul.branches().len(), 3);
+        assert_eq!(simul.common_cycles(0, 2), Some(2));
+
+        // Every branch is restored along with the current one
+        let resumed =
+            net::Simulation::restore(pn.clone(), simul.snapshot()).map_err(|e| e.to_string())?;
+        assert_eq!(resumed.branches(), simul.branches());
+        assert_eq!(resumed.branch(), 2);
+        assert_eq!(resumed.cycles(), simul.cycles());
+        assert!(resumed.switch_branch(1));
+        assert_eq!(resumed.marking(), vec![0, 0, 1].into());
+
+        // Snapshots are checked against the net before anything is restored
+        use net::SnapshotError;
+        let snapshot = simul.snapshot();
+        let restore = |change: &dyn Fn(&mut net::SimulationSnapshot)| {
+            let mut snapshot = snapshot.clone();
+            change(&mut snapshot);
+            net::Simulation::restore(pn.clone(), snapshot).err()
+        };
+        assert_eq!(
+            restore(&|s| s.branch = 3),
+            Some(SnapshotError::UnknownBranch {
+                branch: 3,
+                branches: 3
+            })
+        );
+        assert_eq!(
+            restore(&|s| {
+                s.branches.clear();
+                s.branch = 1;
+            }),
+            Some(SnapshotError::UnknownBranch {
+                branch: 1,
+                branches: 1
+            })
+        );
+        assert_eq!(
+            restore(&|s| s.branch = 0),
+            Some(SnapshotError::CyclesNotInBranch { branch: 0 })
+        );
+        assert_eq!(
+            restore(&|s| s.branches[1].marking = vec![0, 1].into()),
+            Some(SnapshotError::MarkingLength {
+                expected: 3,
+                found: 2
+            })
+        );
+        assert_eq!(
+            restore(&|s| s.branches[0].cycles[0].fired.push(4)),
+            Some(SnapshotError::UnknownTransition {
+                transition: 4,
+                transitions: 4
+            })
+        );
+        assert_eq!(
+            restore(&|s| s.branches[0].cycles[0].conflicts[0].chosen = 7),
+            Some(SnapshotError::UnknownTransition {
+                transition: 7,
+                transitions: 4
+            })
+        );
+        assert_eq!(
+            restore(&|s| s.branches[0].cycles[0].conflicts[0].place = 3),
+            Some(SnapshotError::UnknownPlace {
+                place: 3,
+                places: 3
+            })
+        );
+
+        Ok(())
+    }
 }
@@ -0,0 +1,329 @@
+//! Process discovery with the Alpha algorithm and its Alpha+ extension.
+//!
+//! Both build a `PetriNet` with one transition per activity of the log, a source place `i` holding
+//! one token and a sink place `o`. Every other place connects a maximal pair of sets of activities
+//! `(A, B)` where each activity of `A` is causally followed by each activity of `B`, and the
+//! activities inside `A` and inside `B` never follow each other. Places are named `({a,b},{c})`.
+//!
+//! Alpha cannot discover loops of length one or two. Alpha+ first removes the activities that
+//! directly follow themselves, uses `aba` patterns to tell length-two loops from parallelism, and
+//! finally adds the removed activities back with a self-loop on the place between their
+//! predecessors and successors.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use super::EventLog;
+use crate::net::{PetriNet, PetriNetBuilder};
+
+type Set = BTreeSet<usize>;
+
+/// Ordering relations between the activities of a log, indexed like `activities`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Footprint {
+    pub activities: Vec<String>,
+    /// `follows[a][b]` when `b` directly follows `a` in some trace
+    pub follows: Vec<Vec<bool>>,
+    /// `loops[a][b]` when `a` and `b` form a length-two loop (`aba` and `bab` both occur). Always
+    /// false unless built with `Footprint::with_loops`.
+    pub loops: Vec<Vec<bool>>,
+    pub start: Set,
+    pub end: Set,
+}
+
+impl Footprint {
+    /// Footprint used by the Alpha algorithm
+    pub fn new(log: &EventLog) -> Self {
+        Self::build(log, &HashSet::new(), false)
+    }
+
+    /// Footprint used by Alpha+, which ignores the activities in `ignored` and detects
+    /// length-two loops
+    pub fn with_loops(log: &EventLog, ignored: &HashSet<String>) -> Self {
+        Self::build(log, ignored, true)
+    }
+
+    fn build(log: &EventLog, ignored: &HashSet<String>, detect_loops: bool) -> Self {
+        let traces: Vec<Vec<&str>> = log
+            .traces
+            .iter()
+            .map(|trace| {
+                trace
+                    .activities()
+                    .into_iter()
+                    .filter(|a| !ignored.contains(*a))
+                    .collect::<Vec<_>>()
+            })
+            .filter(|trace| !trace.is_empty())
+            .collect();
+
+        let activities: Vec<String> = traces
+            .iter()
+            .flatten()
+            .map(|a| a.to_string())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let index: HashMap<&str, usize> = activities
+            .iter()
+            .enumerate()
+            .map(|(i, a)| (a.as_str(), i))
+            .collect();
+
+        let n = activities.len();
+        let mut follows = vec![vec![false; n]; n];
+        let mut triangles = vec![vec![false; n]; n];
+        let mut start = Set::new();
+        let mut end = Set::new();
+
+        for trace in traces.iter() {
+            let trace: Vec<usize> = trace.iter().map(|a| index[a]).collect();
+            start.insert(trace[0]);
+            end.insert(trace[trace.len() - 1]);
+
+            for pair in trace.windows(2) {
+                follows[pair[0]][pair[1]] = true;
+            }
+            for triple in trace.windows(3) {
+                if triple[0] == triple[2] {
+                    triangles[triple[0]][triple[1]] = true;
+                }
+            }
+        }
+
+        let loops = (0..n)
+            .map(|a| {
+                (0..n)
+                    .map(|b| detect_loops && triangles[a][b] && triangles[b][a])
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            activities,
+            follows,
+            loops,
+            start,
+            end,
+        }
+    }
+
+    /// `a → b`: `b` follows `a` but not the other way around, unless they form a short loop
+    pub fn causal(&self, a: usize, b: usize) -> bool {
+        self.follows[a][b] && (!self.follows[b][a] || self.loops[a][b])
+    }
+
+    /// `a ∥ b`: both follow each other outside of a short loop
+    pub fn parallel(&self, a: usize, b: usize) -> bool {
+        self.follows[a][b] && self.follows[b][a] && !self.loops[a][b]
+    }
+
+    /// `a # b`: neither follows the other
+    pub fn choice(&self, a: usize, b: usize) -> bool {
+        !self.follows[a][b] && !self.follows[b][a]
+    }
+
+    /// Maximal pairs `(A, B)` of the Alpha algorithm, sorted
+    pub fn maximal_pairs(&self) -> Vec<(Set, Set)> {
+        let n = self.activities.len();
+        let mut seen: HashSet<(Set, Set)> = HashSet::new();
+        let mut pending: Vec<(Set, Set)> = vec![];
+
+        for a in 0..n {
+            for b in 0..n {
+                if self.causal(a, b) && self.choice(a, a) && self.choice(b, b) {
+                    let pair = (Set::from([a]), Set::from([b]));
+                    if seen.insert(pair.clone()) {
+                        pending.push(pair);
+                    }
+                }
+            }
+        }
+
+        // Grow every pair one activity at a time, keeping only valid pairs
+        let mut valid = vec![];
+        while let Some((from, to)) = pending.pop() {
+            for x in 0..n {
+                if !self.choice(x, x) {
+                    continue;
+                }
+
+                if !from.contains(&x)
+                    && to.iter().all(|b| self.causal(x, *b))
+                    && from.iter().all(|a| self.choice(x, *a))
+                {
+                    let mut bigger = from.clone();
+                    bigger.insert(x);
+                    let pair = (bigger, to.clone());
+                    if seen.insert(pair.clone()) {
+                        pending.push(pair);
+                    }
+                }
+
+                if !to.contains(&x)
+                    && from.iter().all(|a| self.causal(*a, x))
+                    && to.iter().all(|b| self.choice(x, *b))
+                {
+                    let mut bigger = to.clone();
+                    bigger.insert(x);
+                    let pair = (from.clone(), bigger);
+                    if seen.insert(pair.clone()) {
+                        pending.push(pair);
+                    }
+                }
+            }
+
+            valid.push((from, to));
+        }
+
+        let mut maximal: Vec<(Set, Set)> = valid
+            .iter()
+            .filter(|(from, to)| {
+                !valid.iter().any(|(other_from, other_to)| {
+                    (other_from != from || other_to != to)
+                        && other_from.is_superset(from)
+                        && other_to.is_superset(to)
+                })
+            })
+            .cloned()
+            .collect();
+        maximal.sort();
+
+        maximal
+    }
+}
+
+/// A place of the discovered net, by the activities it connects
+struct MinedPlace {
+    name: String,
+    inputs: BTreeSet<String>,
+    outputs: BTreeSet<String>,
+}
+
+/// Discovers a net from `log` with the Alpha algorithm
+pub fn alpha(log: &EventLog) -> PetriNet {
+    let footprint = Footprint::new(log);
+    let places = mine_places(&footprint);
+
+    build(&footprint.activities, places)
+}
+
+/// Discovers a net from `log` with the Alpha+ algorithm, which also finds short loops
+pub fn alpha_plus(log: &EventLog) -> PetriNet {
+    let all = Footprint::new(log);
+    let self_loops: Vec<usize> = (0..all.activities.len())
+        .filter(|a| all.follows[*a][*a])
+        .collect();
+    let ignored: HashSet<String> = self_loops
+        .iter()
+        .map(|a| all.activities[*a].clone())
+        .collect();
+
+    let footprint = Footprint::with_loops(log, &ignored);
+    let mut places = mine_places(&footprint);
+
+    for activity in self_loops.into_iter() {
+        let name = &all.activities[activity];
+        let neighbours = |forward: bool| -> BTreeSet<String> {
+            (0..all.activities.len())
+                .filter(|other| {
+                    let (a, b) = if forward {
+                        (activity, *other)
+                    } else {
+                        (*other, activity)
+                    };
+                    all.follows[a][b] && !ignored.contains(&all.activities[*other])
+                })
+                .map(|other| all.activities[other].clone())
+                .collect()
+        };
+        let inputs = neighbours(false);
+        let outputs = neighbours(true);
+
+        let position = places
+            .iter()
+            .position(|p| p.inputs == inputs && p.outputs == outputs)
+            .unwrap_or_else(|| {
+                places.push(MinedPlace {
+                    name: place_name(&inputs, &outputs),
+                    inputs,
+                    outputs,
+                });
+                places.len() - 1
+            });
+
+        let place = &mut places[position];
+        place.inputs.insert(name.clone());
+        place.outputs.insert(name.clone());
+        if place.name != "i" && place.name != "o" {
+            place.name = place_name(&place.inputs, &place.outputs);
+        }
+    }
+
+    build(&all.activities, places)
+}
+
+fn place_name(inputs: &BTreeSet<String>, outputs: &BTreeSet<String>) -> String {
+    let join = |set: &BTreeSet<String>| set.iter().cloned().collect::<Vec<_>>().join(",");
+
+    format!("({{{}}},{{{}}})", join(inputs), join(outputs))
+}
+
+fn mine_places(footprint: &Footprint) -> Vec<MinedPlace> {
+    let names = |set: &Set| -> BTreeSet<String> {
+        set.iter()
+            .map(|a| footprint.activities[*a].clone())
+            .collect()
+    };
+
+    let mut places = vec![MinedPlace {
+        name: "i".into(),
+        inputs: BTreeSet::new(),
+        outputs: names(&footprint.start),
+    }];
+
+    for (from, to) in footprint.maximal_pairs().iter() {
+        let (inputs, outputs) = (names(from), names(to));
+        places.push(MinedPlace {
+            name: place_name(&inputs, &outputs),
+            inputs,
+            outputs,
+        });
+    }
+
+    places.push(MinedPlace {
+        name: "o".into(),
+        inputs: names(&footprint.end),
+        outputs: BTreeSet::new(),
+    });
+
+    places
+}
+
+fn build(activities: &[String], places: Vec<MinedPlace>) -> PetriNet {
+    let mut builder = PetriNetBuilder::new();
+    let mut used: HashSet<String> = activities.iter().cloned().collect();
+
+    for activity in activities.iter() {
+        builder.transition(activity);
+    }
+
+    for (index, place) in places.into_iter().enumerate() {
+        // Keep place names apart from activity names
+        let mut name = place.name;
+        while !used.insert(name.clone()) {
+            name.push('\'');
+        }
+
+        let id = builder.place(&name).tokens((index == 0) as i32).id();
+        for input in place.inputs.iter() {
+            builder.arc(input.as_str(), id);
+        }
+        for output in place.outputs.iter() {
+            builder.arc(id, output.as_str());
+        }
+    }
+
+    builder
+        .build()
+        .expect("Mined places only connect activities of the log")
+}
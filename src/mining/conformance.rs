@@ -0,0 +1,279 @@
+//! Token-based replay of event logs on a net.
+//!
+//! Every trace is replayed from the initial marking, firing the transition named after each
+//! activity. When a transition is not enabled it is fired anyway and the tokens it lacked are
+//! counted as missing. At the end the final marking is consumed, and whatever is left is counted
+//! as remaining. Fitness follows the usual definition:
+//!
+//! ```text
+//! fitness = 1/2 (1 - missing / consumed) + 1/2 (1 - remaining / produced)
+//! ```
+
+use std::collections::HashMap;
+
+use super::{EventLog, LogTrace};
+use crate::analysis::AnalysisError;
+use crate::net::{ConnectionType, IndexedNet, Marking, PetriNet};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DeviationKind {
+    /// The transition of the activity was not enabled and had to be forced
+    NotEnabled,
+    /// No transition of the net is named after the activity, the event was skipped
+    UnknownActivity,
+}
+
+/// First event of a trace that does not fit the net
+#[derive(Clone, Debug, PartialEq)]
+pub struct Deviation {
+    /// Position of the event in the trace
+    pub event: usize,
+    pub activity: String,
+    pub kind: DeviationKind,
+}
+
+/// Result of replaying one trace
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceReplay {
+    pub name: String,
+    pub produced: i32,
+    pub consumed: i32,
+    /// Tokens that were missing when forcing transitions or consuming the final marking, by place
+    pub missing: Vec<i32>,
+    /// Tokens left after consuming the final marking, by place
+    pub remaining: Vec<i32>,
+    pub first_deviation: Option<Deviation>,
+}
+
+/// Result of replaying a whole log
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogReplay {
+    pub traces: Vec<TraceReplay>,
+}
+
+/// Replays traces on a net treated as a workflow model
+pub struct TokenReplay<'a> {
+    net: &'a PetriNet,
+    indexed: IndexedNet,
+    initial_marking: Marking,
+    final_marking: Marking,
+    transitions: HashMap<&'a str, usize>,
+}
+
+fn fitness(produced: i32, consumed: i32, missing: i32, remaining: i32) -> f64 {
+    let ratio = |part: i32, total: i32| {
+        if total == 0 {
+            0.0
+        } else {
+            part as f64 / total as f64
+        }
+    };
+
+    0.5 * (1.0 - ratio(missing, consumed)) + 0.5 * (1.0 - ratio(remaining, produced))
+}
+
+impl TraceReplay {
+    pub fn total_missing(&self) -> i32 {
+        self.missing.iter().sum()
+    }
+
+    pub fn total_remaining(&self) -> i32 {
+        self.remaining.iter().sum()
+    }
+
+    pub fn fitness(&self) -> f64 {
+        fitness(
+            self.produced,
+            self.consumed,
+            self.total_missing(),
+            self.total_remaining(),
+        )
+    }
+
+    /// Whether the trace could be replayed without any deviation
+    pub fn fits(&self) -> bool {
+        self.first_deviation.is_none() && self.total_missing() == 0 && self.total_remaining() == 0
+    }
+}
+
+impl LogReplay {
+    /// Fitness of the log, computed from the totals of all traces
+    pub fn fitness(&self) -> f64 {
+        let sum = |f: fn(&TraceReplay) -> i32| self.traces.iter().map(f).sum();
+
+        fitness(
+            sum(|t| t.produced),
+            sum(|t| t.consumed),
+            sum(|t| t.total_missing()),
+            sum(|t| t.total_remaining()),
+        )
+    }
+
+    /// Missing tokens of all traces, by place
+    pub fn missing(&self) -> Vec<i32> {
+        self.per_place(|t| &t.missing)
+    }
+
+    /// Remaining tokens of all traces, by place
+    pub fn remaining(&self) -> Vec<i32> {
+        self.per_place(|t| &t.remaining)
+    }
+
+    /// Share of traces that fit the net
+    pub fn fitting_traces(&self) -> f64 {
+        if self.traces.is_empty() {
+            return 1.0;
+        }
+
+        self.traces.iter().filter(|t| t.fits()).count() as f64 / self.traces.len() as f64
+    }
+
+    fn per_place(&self, f: fn(&TraceReplay) -> &Vec<i32>) -> Vec<i32> {
+        let mut totals = vec![];
+
+        for trace in self.traces.iter() {
+            totals.resize(f(trace).len(), 0);
+            for (total, tokens) in totals.iter_mut().zip(f(trace).iter()) {
+                *total += tokens;
+            }
+        }
+
+        totals
+    }
+}
+
+impl<'a> TokenReplay<'a> {
+    /// Replays from the initial marking of `net`. The final marking has one token in every place
+    /// without outgoing connections, which is the sink place of a workflow net.
+    pub fn new(net: &'a PetriNet) -> Self {
+        let indexed = IndexedNet::new(net);
+        let final_marking = (0..indexed.place_count())
+            .map(|place| indexed.consumers(place).is_empty() as i32)
+            .collect();
+
+        Self {
+            net,
+            initial_marking: net.initial_marking(),
+            final_marking,
+            transitions: net
+                .transitions()
+                .iter()
+                .enumerate()
+                .map(|(index, transition)| (transition.name(), index))
+                .collect(),
+            indexed,
+        }
+    }
+
+    /// Replays from `marking` instead, failing with `AnalysisError::MarkingLength` unless it has
+    /// one entry per place
+    pub fn with_initial_marking(mut self, marking: Marking) -> Result<Self, AnalysisError> {
+        self.check_length(&marking)?;
+        self.initial_marking = marking;
+        Ok(self)
+    }
+
+    /// Consumes `marking` at the end of every trace, failing like `with_initial_marking`
+    pub fn with_final_marking(mut self, marking: Marking) -> Result<Self, AnalysisError> {
+        self.check_length(&marking)?;
+        self.final_marking = marking;
+        Ok(self)
+    }
+
+    fn check_length(&self, marking: &Marking) -> Result<(), AnalysisError> {
+        if marking.len() != self.indexed.place_count() {
+            return Err(AnalysisError::MarkingLength {
+                expected: self.indexed.place_count(),
+                found: marking.len(),
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn net(&self) -> &PetriNet {
+        self.net
+    }
+
+    pub fn replay_log(&self, log: &EventLog) -> LogReplay {
+        LogReplay {
+            traces: log.traces.iter().map(|t| self.replay_trace(t)).collect(),
+        }
+    }
+
+    pub fn replay_trace(&self, trace: &LogTrace) -> TraceReplay {
+        let mut marking = self.initial_marking.clone();
+        let mut missing = vec![0; marking.len()];
+        let mut produced = marking.total();
+        let mut consumed = 0;
+        let mut first_deviation = None;
+
+        for (index, event) in trace.events.iter().enumerate() {
+            let transition = match self.transitions.get(event.activity.as_str()) {
+                Some(transition) => *transition,
+                None => {
+                    first_deviation.get_or_insert(Deviation {
+                        event: index,
+                        activity: event.activity.clone(),
+                        kind: DeviationKind::UnknownActivity,
+                    });
+                    continue;
+                }
+            };
+
+            if !self.indexed.is_enabled(transition, &marking) {
+                first_deviation.get_or_insert(Deviation {
+                    event: index,
+                    activity: event.activity.clone(),
+                    kind: DeviationKind::NotEnabled,
+                });
+
+                // Force the firing by creating the tokens that are lacking
+                for con in self.indexed.pre(transition).iter() {
+                    if con.con_type == ConnectionType::NORMAL && marking[con.place] < con.weight {
+                        missing[con.place] += con.weight - marking[con.place];
+                        marking[con.place] = con.weight;
+                    }
+                }
+            }
+
+            for con in self.indexed.pre(transition).iter() {
+                consumed += match con.con_type {
+                    ConnectionType::NORMAL => con.weight,
+                    ConnectionType::INHIBITOR => 0,
+                    ConnectionType::RESET => marking[con.place],
+                };
+            }
+            produced += self
+                .indexed
+                .post(transition)
+                .iter()
+                .map(|con| con.weight)
+                .sum::<i32>();
+
+            self.indexed.fire(transition, &mut marking);
+        }
+
+        // Consume the final marking
+        for place in 0..marking.len() {
+            let expected = self.final_marking.get(place).unwrap_or(0);
+            consumed += expected;
+
+            if marking[place] < expected {
+                missing[place] += expected - marking[place];
+                marking[place] = 0;
+            } else {
+                marking[place] -= expected;
+            }
+        }
+
+        TraceReplay {
+            name: trace.name.clone(),
+            produced,
+            consumed,
+            missing,
+            remaining: marking.into(),
+            first_deviation,
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! Reading of event logs stored as CSV, one event per row.
+//!
+//! The first row is a header. The case column is the first one named `case:concept:name`,
+//! `case_id`, `case` or `trace`, and the activity column the first one named `concept:name`,
+//! `activity` or `event`. An optional `time:timestamp`, `timestamp` or `time` column holds ISO 8601
+//! dates. Names are matched case-insensitively and other columns are ignored. Events keep the order
+//! of the rows, and traces the order in which their case first appears.
+
+use std::collections::HashMap;
+use std::io::Read;
+
+use super::{xes, Event, EventLog, LogError, LogTrace};
+
+const CASE_COLUMNS: [&str; 4] = ["case:concept:name", "case_id", "case", "trace"];
+const ACTIVITY_COLUMNS: [&str; 3] = ["concept:name", "activity", "event"];
+const TIMESTAMP_COLUMNS: [&str; 3] = ["time:timestamp", "timestamp", "time"];
+
+impl EventLog {
+    pub fn from_csv(input: &str) -> Result<Self, LogError> {
+        let mut rows = parse(input)?.into_iter();
+        let header = rows
+            .next()
+            .ok_or_else(|| LogError::Syntax("empty CSV".into()))?;
+
+        let find = |names: &[&str]| {
+            names.iter().find_map(|name| {
+                header
+                    .iter()
+                    .position(|column| column.trim().eq_ignore_ascii_case(name))
+            })
+        };
+        let case = find(&CASE_COLUMNS).ok_or(LogError::MissingColumn("case"))?;
+        let activity = find(&ACTIVITY_COLUMNS).ok_or(LogError::MissingColumn("activity"))?;
+        let timestamp = find(&TIMESTAMP_COLUMNS);
+
+        let mut log = EventLog::new();
+        let mut traces: HashMap<String, usize> = HashMap::new();
+
+        for (line, row) in rows.enumerate() {
+            if row.iter().all(|field| field.is_empty()) {
+                continue;
+            }
+
+            let field = |index: usize| {
+                row.get(index).ok_or_else(|| {
+                    LogError::Syntax(format!("row {} has only {} fields", line + 2, row.len()))
+                })
+            };
+
+            let case_name = field(case)?;
+            let event = Event {
+                activity: field(activity)?.clone(),
+                timestamp: match timestamp {
+                    Some(index) => xes::parse_timestamp(field(index)?),
+                    None => None,
+                },
+            };
+
+            let index = *traces.entry(case_name.clone()).or_insert_with(|| {
+                log.traces.push(LogTrace {
+                    name: case_name.clone(),
+                    events: vec![],
+                });
+                log.traces.len() - 1
+            });
+            log.traces[index].events.push(event);
+        }
+
+        Ok(log)
+    }
+
+    pub fn read_csv(mut reader: impl Read) -> Result<Self, LogError> {
+        let mut input = String::new();
+        reader.read_to_string(&mut input)?;
+
+        Self::from_csv(&input)
+    }
+}
+
+/// Splits `input` into rows of fields, following RFC 4180 quoting
+fn parse(input: &str) -> Result<Vec<Vec<String>>, LogError> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => quoted = false,
+                c => field.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            '"' => quoted = true,
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+
+    if quoted {
+        return Err(LogError::Syntax("unterminated quoted field".into()));
+    }
+
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
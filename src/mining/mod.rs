@@ -0,0 +1,137 @@
+//! Process mining on top of the net engine: event logs, their CSV and XES representations, the
+//! conversion of simulation runs into logs, discovery of nets from logs, and conformance checking
+//! of logs against nets.
+
+pub mod alpha;
+pub mod conformance;
+pub mod csv;
+pub mod xes;
+
+pub use alpha::{alpha, alpha_plus, Footprint};
+pub use conformance::{Deviation, DeviationKind, LogReplay, TokenReplay, TraceReplay};
+
+use crate::net::Trace;
+
+/// Errors raised while reading an event log
+#[derive(Debug)]
+pub enum LogError {
+    Io(std::io::Error),
+    /// The input is not well-formed, with a description of the problem
+    Syntax(String),
+    /// A CSV log lacks a required column
+    MissingColumn(&'static str),
+}
+
+impl std::fmt::Display for LogError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogError::Io(error) => write!(f, "could not read log: {}", error),
+            LogError::Syntax(message) => write!(f, "malformed log: {}", message),
+            LogError::MissingColumn(column) => write!(f, "log has no {} column", column),
+        }
+    }
+}
+
+impl std::error::Error for LogError {}
+
+impl From<std::io::Error> for LogError {
+    fn from(error: std::io::Error) -> Self {
+        LogError::Io(error)
+    }
+}
+
+/// A single activity execution
+#[derive(Clone, Debug, PartialEq)]
+pub struct Event {
+    pub activity: String,
+    /// Seconds since the Unix epoch, if known
+    pub timestamp: Option<i64>,
+}
+
+/// Sequence of events of one process execution (a case)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LogTrace {
+    pub name: String,
+    pub events: Vec<Event>,
+}
+
+/// A collection of traces, e.g. several simulation runs or the executions of a real process
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EventLog {
+    pub traces: Vec<LogTrace>,
+}
+
+/// Maps simulation cycles to timestamps: cycle `n` happens at `start + n * seconds_per_cycle`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Clock {
+    /// Seconds since the Unix epoch
+    pub start: i64,
+    pub seconds_per_cycle: i64,
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self {
+            start: 0,
+            seconds_per_cycle: 1,
+        }
+    }
+}
+
+impl LogTrace {
+    /// Creates a trace from a list of activities without timestamps
+    pub fn from_activities<S: AsRef<str>>(name: &str, activities: &[S]) -> Self {
+        Self {
+            name: name.into(),
+            events: activities
+                .iter()
+                .map(|activity| Event {
+                    activity: activity.as_ref().into(),
+                    timestamp: None,
+                })
+                .collect(),
+        }
+    }
+
+    /// Creates a trace with one event per transition fired during a simulation. Transitions fired
+    /// in the same cycle share a timestamp and appear in the order of the net's transitions.
+    pub fn from_simulation(name: &str, trace: &Trace, clock: &Clock) -> Self {
+        let mut events = vec![];
+
+        for (index, cycle) in trace.cycles.iter().enumerate() {
+            for transition in cycle.fired.iter() {
+                events.push(Event {
+                    activity: trace.transitions[*transition].clone(),
+                    timestamp: Some(clock.start + index as i64 * clock.seconds_per_cycle),
+                });
+            }
+        }
+
+        Self {
+            name: name.into(),
+            events,
+        }
+    }
+
+    /// Activities of the trace, in order
+    pub fn activities(&self) -> Vec<&str> {
+        self.events.iter().map(|e| e.activity.as_str()).collect()
+    }
+}
+
+impl EventLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_trace(&mut self, trace: LogTrace) {
+        self.traces.push(trace);
+    }
+
+    /// Adds a simulation run as a new trace named after its position in the log
+    pub fn add_simulation(&mut self, trace: &Trace, clock: &Clock) {
+        let name = format!("run {}", self.traces.len() + 1);
+        self.traces
+            .push(LogTrace::from_simulation(&name, trace, clock));
+    }
+}
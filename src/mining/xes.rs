@@ -0,0 +1,450 @@
+//! Reading and writing of event logs in the XES format (IEEE 1849).
+//!
+//! Only the attributes used by the crate are read: `concept:name` of traces and events, and
+//! `time:timestamp` of events. Events whose `lifecycle:transition` is set to anything other than
+//! `complete` are skipped, so that each activity execution is counted once.
+
+use std::io::{self, Read, Write};
+
+use super::{Event, EventLog, LogError, LogTrace};
+
+impl EventLog {
+    /// Writes the log as XES. Every event gets the `concept:name`, `time:timestamp` (when known)
+    /// and `lifecycle:transition` attributes.
+    pub fn write_xes(&self, mut writer: impl Write) -> io::Result<()> {
+        writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8" ?>"#)?;
+        writeln!(
+            writer,
+            r#"<log xes.version="1849-2016" xes.features="nested-attributes" xmlns="http://www.xes-standard.org/">"#
+        )?;
+        writeln!(
+            writer,
+            r#"  <extension name="Concept" prefix="concept" uri="http://www.xes-standard.org/concept.xesext"/>"#
+        )?;
+        writeln!(
+            writer,
+            r#"  <extension name="Time" prefix="time" uri="http://www.xes-standard.org/time.xesext"/>"#
+        )?;
+        writeln!(
+            writer,
+            r#"  <extension name="Lifecycle" prefix="lifecycle" uri="http://www.xes-standard.org/lifecycle.xesext"/>"#
+        )?;
+        writeln!(writer, r#"  <global scope="trace">"#)?;
+        writeln!(
+            writer,
+            r#"    <string key="concept:name" value="__INVALID__"/>"#
+        )?;
+        writeln!(writer, r#"  </global>"#)?;
+        writeln!(writer, r#"  <global scope="event">"#)?;
+        writeln!(
+            writer,
+            r#"    <string key="concept:name" value="__INVALID__"/>"#
+        )?;
+        writeln!(
+            writer,
+            r#"    <string key="lifecycle:transition" value="complete"/>"#
+        )?;
+        writeln!(writer, r#"  </global>"#)?;
+        writeln!(
+            writer,
+            r#"  <classifier name="Activity" keys="concept:name"/>"#
+        )?;
+
+        for trace in self.traces.iter() {
+            writeln!(writer, "  <trace>")?;
+            writeln!(
+                writer,
+                r#"    <string key="concept:name" value="{}"/>"#,
+                escape(&trace.name)
+            )?;
+
+            for event in trace.events.iter() {
+                writeln!(writer, "    <event>")?;
+                writeln!(
+                    writer,
+                    r#"      <string key="concept:name" value="{}"/>"#,
+                    escape(&event.activity)
+                )?;
+                if let Some(timestamp) = event.timestamp {
+                    writeln!(
+                        writer,
+                        r#"      <date key="time:timestamp" value="{}"/>"#,
+                        format_timestamp(timestamp)
+                    )?;
+                }
+                writeln!(
+                    writer,
+                    r#"      <string key="lifecycle:transition" value="complete"/>"#
+                )?;
+                writeln!(writer, "    </event>")?;
+            }
+
+            writeln!(writer, "  </trace>")?;
+        }
+
+        writeln!(writer, "</log>")
+    }
+
+    pub fn to_xes(&self) -> String {
+        let mut buffer = vec![];
+        self.write_xes(&mut buffer)
+            .expect("Writing to a Vec cannot fail");
+
+        String::from_utf8(buffer).expect("Log is valid UTF-8")
+    }
+}
+
+impl EventLog {
+    pub fn from_xes(input: &str) -> Result<Self, LogError> {
+        let mut log = EventLog::new();
+        let mut stack: Vec<String> = vec![];
+        let mut lifecycle_complete = true;
+
+        for tag in tags(input)? {
+            if tag.closing {
+                match stack.pop() {
+                    Some(name) if name == tag.name => {}
+                    _ => return Err(LogError::Syntax(format!("unexpected </{}>", tag.name))),
+                }
+
+                if tag.name == "event" && !lifecycle_complete {
+                    current_trace(&mut log)?.events.pop();
+                }
+                continue;
+            }
+
+            let parent = stack.last().map(String::as_str);
+            match (parent, tag.name.as_str()) {
+                (Some("log"), "trace") => log.traces.push(LogTrace::default()),
+                (_, "trace") => return Err(LogError::Syntax("<trace> outside of <log>".into())),
+                (Some("trace"), "event") => {
+                    lifecycle_complete = true;
+                    current_trace(&mut log)?.events.push(Event {
+                        activity: String::new(),
+                        timestamp: None,
+                    });
+                }
+                (Some("trace"), _) if tag.attribute("key") == Some("concept:name") => {
+                    current_trace(&mut log)?.name = tag.attribute("value").unwrap_or("").into();
+                }
+                (Some("event"), _) => {
+                    let event = current_trace(&mut log)?
+                        .events
+                        .last_mut()
+                        .ok_or_else(|| LogError::Syntax("attribute outside of <event>".into()))?;
+                    let value = tag.attribute("value").unwrap_or("");
+
+                    match tag.attribute("key") {
+                        Some("concept:name") => event.activity = value.into(),
+                        Some("time:timestamp") => event.timestamp = parse_timestamp(value),
+                        Some("lifecycle:transition") => {
+                            lifecycle_complete = value.eq_ignore_ascii_case("complete")
+                        }
+                        _ => {}
+                    }
+                }
+                (_, "event") => return Err(LogError::Syntax("<event> outside of <trace>".into())),
+                _ => {}
+            }
+
+            if !tag.self_closing {
+                stack.push(tag.name);
+            }
+        }
+
+        if let Some(name) = stack.pop() {
+            return Err(LogError::Syntax(format!("<{}> is never closed", name)));
+        }
+
+        Ok(log)
+    }
+
+    pub fn read_xes(mut reader: impl Read) -> Result<Self, LogError> {
+        let mut input = String::new();
+        reader.read_to_string(&mut input)?;
+
+        Self::from_xes(&input)
+    }
+}
+
+/// Trace the elements being read belong to
+fn current_trace(log: &mut EventLog) -> Result<&mut LogTrace, LogError> {
+    log.traces
+        .last_mut()
+        .ok_or_else(|| LogError::Syntax("element outside of <trace>".into()))
+}
+
+struct Tag {
+    name: String,
+    attributes: Vec<(String, String)>,
+    closing: bool,
+    self_closing: bool,
+}
+
+impl Tag {
+    fn attribute(&self, key: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Splits an XML document into its tags, skipping text, comments and declarations
+fn tags(input: &str) -> Result<Vec<Tag>, LogError> {
+    let mut tags = vec![];
+    let mut rest = input;
+
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start..];
+
+        let skip_until = if rest.starts_with("<?") {
+            Some("?>")
+        } else if rest.starts_with("<!--") {
+            Some("-->")
+        } else if rest.starts_with("<!") {
+            Some(">")
+        } else {
+            None
+        };
+        if let Some(end) = skip_until {
+            let position = rest
+                .find(end)
+                .ok_or_else(|| LogError::Syntax("unterminated declaration".into()))?;
+            rest = &rest[position + end.len()..];
+            continue;
+        }
+
+        // Find the end of the tag, ignoring '>' inside quoted attribute values
+        let mut quote = None;
+        let end = rest
+            .char_indices()
+            .find(|(_, c)| match quote {
+                Some(q) if *c == q => {
+                    quote = None;
+                    false
+                }
+                Some(_) => false,
+                None if *c == '"' || *c == '\'' => {
+                    quote = Some(*c);
+                    false
+                }
+                None => *c == '>',
+            })
+            .map(|(i, _)| i)
+            .ok_or_else(|| LogError::Syntax("unterminated tag".into()))?;
+
+        tags.push(parse_tag(&rest[1..end])?);
+        rest = &rest[end + 1..];
+    }
+
+    Ok(tags)
+}
+
+fn parse_tag(content: &str) -> Result<Tag, LogError> {
+    let closing = content.starts_with('/');
+    let self_closing = content.ends_with('/');
+    let content = content.trim_start_matches('/').trim_end_matches('/');
+
+    let name_end = content.find(char::is_whitespace).unwrap_or(content.len());
+    let name = content[..name_end].to_string();
+    let mut rest = content[name_end..].trim_start();
+    let mut attributes = vec![];
+
+    while !rest.is_empty() {
+        let equals = rest
+            .find('=')
+            .ok_or_else(|| LogError::Syntax(format!("attribute without value in <{}>", name)))?;
+        let key = rest[..equals].trim().to_string();
+        rest = rest[equals + 1..].trim_start();
+
+        let quote = rest
+            .chars()
+            .next()
+            .filter(|c| *c == '"' || *c == '\'')
+            .ok_or_else(|| LogError::Syntax(format!("unquoted attribute in <{}>", name)))?;
+        let value_end = rest[1..]
+            .find(quote)
+            .ok_or_else(|| LogError::Syntax(format!("unterminated attribute in <{}>", name)))?;
+
+        attributes.push((key, unescape(&rest[1..value_end + 1])));
+        rest = rest[value_end + 2..].trim_start();
+    }
+
+    Ok(Tag {
+        name,
+        attributes,
+        closing,
+        self_closing,
+    })
+}
+
+/// Replaces the predefined and numeric XML entities of `value`
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let entity = rest.find(';').map(|end| (&rest[1..end], end));
+        let replacement = entity.and_then(|(name, _)| match name {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ if name.starts_with("#x") => u32::from_str_radix(&name[2..], 16)
+                .ok()
+                .and_then(char::from_u32),
+            _ if name.starts_with('#') => name[1..].parse().ok().and_then(char::from_u32),
+            _ => None,
+        });
+
+        match (replacement, entity) {
+            (Some(c), Some((_, end))) => {
+                unescaped.push(c);
+                rest = &rest[end + 1..];
+            }
+            _ => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    unescaped.push_str(rest);
+    unescaped
+}
+
+/// Escapes `value` for use inside an XML attribute
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Formats seconds since the Unix epoch as an `xs:dateTime` in UTC
+fn format_timestamp(timestamp: i64) -> String {
+    let days = timestamp.div_euclid(86_400);
+    let seconds = timestamp.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.000+00:00",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
+
+/// Parses an ISO 8601 date such as `2023-11-14T22:13:20.000+01:00` into seconds since the Unix
+/// epoch. Fractions of seconds are dropped and a missing offset is read as UTC.
+pub(crate) fn parse_timestamp(value: &str) -> Option<i64> {
+    let value = value.trim();
+    let number = |range: std::ops::Range<usize>| -> Option<i64> {
+        let digits = value.get(range)?;
+        if digits.chars().all(|c| c.is_ascii_digit()) {
+            digits.parse().ok()
+        } else {
+            None
+        }
+    };
+    let separator = |index: usize, allowed: &[u8]| -> Option<()> {
+        allowed.contains(value.as_bytes().get(index)?).then_some(())
+    };
+
+    let year = number(0..4)?;
+    separator(4, b"-")?;
+    let month = number(5..7)?;
+    separator(7, b"-")?;
+    let day = number(8..10)?;
+    let (hour, minute, second) = if value.len() > 10 {
+        separator(10, b"Tt")?;
+        separator(13, b":")?;
+        separator(16, b":")?;
+        (number(11..13)?, number(14..16)?, number(17..19)?)
+    } else {
+        (0, 0, 0)
+    };
+
+    let mut rest = if value.len() > 19 {
+        value.get(19..)?
+    } else {
+        ""
+    };
+    if let Some(fraction) = rest.strip_prefix('.') {
+        rest = fraction.trim_start_matches(|c: char| c.is_ascii_digit());
+    }
+
+    let offset = match rest {
+        "" | "Z" | "z" => 0,
+        _ => {
+            let (sign, offset) = if let Some(offset) = rest.strip_prefix('+') {
+                (1, offset)
+            } else if let Some(offset) = rest.strip_prefix('-') {
+                (-1, offset)
+            } else {
+                return None;
+            };
+            let two_digits = |text: &str| -> Option<i64> {
+                if text.len() == 2 && text.bytes().all(|b| b.is_ascii_digit()) {
+                    text.parse().ok()
+                } else {
+                    None
+                }
+            };
+            let (hours, minutes) = match offset.split_once(':') {
+                Some((hours, minutes)) => (two_digits(hours)?, two_digits(minutes)?),
+                None => (two_digits(offset)?, 0),
+            };
+            sign * (hours * 3600 + minutes * 60)
+        }
+    };
+
+    Some(days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second - offset)
+}
+
+/// Converts a date of the proleptic Gregorian calendar into days since 1970-01-01, following
+/// Howard Hinnant's `days_from_civil`
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+    era * 146_097 + doe - 719_468
+}
+
+/// Converts days since 1970-01-01 into a (year, month, day) date of the proleptic Gregorian
+/// calendar, following Howard Hinnant's `civil_from_days`
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
@@ -0,0 +1,391 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::net::*;
+
+/// Builds a `PetriNet` at runtime, e.g. from a configuration read from a database.
+///
+/// ```
+/// use petri_engine::net::PetriNetBuilder;
+///
+/// let mut builder = PetriNetBuilder::new();
+/// let p1 = builder.place("P1").tokens(2).id();
+/// let t1 = builder.transition("T1").id();
+/// builder.place("P2");
+///
+/// builder.arc(p1, t1).weight(2);
+/// builder.arc("T1", "P2");
+///
+/// let pn = builder.build().unwrap();
+/// assert_eq!(pn.place(p1).unwrap().tokens(), 2);
+/// ```
+#[derive(Default)]
+pub struct PetriNetBuilder {
+    places: Vec<PlaceSpec>,
+    transitions: Vec<TransitionSpec>,
+    arcs: Vec<ArcSpec>,
+}
+
+struct PlaceSpec {
+    id: PlaceId,
+    name: String,
+    tokens: i32,
+}
+
+struct TransitionSpec {
+    id: TransitionId,
+    name: String,
+    callback: Option<TransitionCallback>,
+}
+
+struct ArcSpec {
+    from: NodeRef,
+    to: NodeRef,
+    weight: i32,
+    con_type: ConnectionType,
+}
+
+/// Either end of an arc, referenced by name or by handle
+#[derive(Clone, Debug, PartialEq)]
+pub enum NodeRef {
+    Name(String),
+    Place(PlaceId),
+    Transition(TransitionId),
+}
+
+impl From<&str> for NodeRef {
+    fn from(name: &str) -> Self {
+        NodeRef::Name(name.into())
+    }
+}
+
+impl From<String> for NodeRef {
+    fn from(name: String) -> Self {
+        NodeRef::Name(name)
+    }
+}
+
+impl From<PlaceId> for NodeRef {
+    fn from(id: PlaceId) -> Self {
+        NodeRef::Place(id)
+    }
+}
+
+impl From<TransitionId> for NodeRef {
+    fn from(id: TransitionId) -> Self {
+        NodeRef::Transition(id)
+    }
+}
+
+impl std::fmt::Display for NodeRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NodeRef::Name(name) => write!(f, "{}", name),
+            NodeRef::Place(id) => write!(f, "place {}", id.uuid()),
+            NodeRef::Transition(id) => write!(f, "transition {}", id.uuid()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum BuildError {
+    /// Two nodes were declared with the same name
+    DuplicateName(String),
+    /// A place was given a negative amount of tokens
+    NegativeTokens(String),
+    /// An arc references a node that was not declared
+    UnknownNode(NodeRef),
+    /// An arc connects two places or two transitions
+    SameKindArc(NodeRef, NodeRef),
+    /// An inhibitor or reset arc goes from a transition to a place
+    OutputArcType(NodeRef, NodeRef, ConnectionType),
+    /// An arc has a weight lower than one
+    InvalidWeight(NodeRef, NodeRef, i32),
+}
+
+impl std::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildError::DuplicateName(name) => write!(f, "name `{}` is declared twice", name),
+            BuildError::NegativeTokens(name) => {
+                write!(f, "place `{}` has a negative amount of tokens", name)
+            }
+            BuildError::UnknownNode(node) => write!(f, "`{}` was not declared", node),
+            BuildError::SameKindArc(from, to) => write!(
+                f,
+                "arc `{}` -> `{}` must connect a place and a transition",
+                from, to
+            ),
+            BuildError::OutputArcType(from, to, con_type) => write!(
+                f,
+                "arc `{}` -> `{}` goes into a place and cannot be of type {}",
+                from, to, con_type
+            ),
+            BuildError::InvalidWeight(from, to, weight) => write!(
+                f,
+                "arc `{}` -> `{}` has invalid weight {}",
+                from, to, weight
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// Returned by [`PetriNetBuilder::place`]
+pub struct PlaceBuilder<'a> {
+    spec: &'a mut PlaceSpec,
+}
+
+impl PlaceBuilder<'_> {
+    /// Sets the initial amount of tokens
+    pub fn tokens(self, amount: i32) -> Self {
+        self.spec.tokens = amount;
+        self
+    }
+
+    pub fn id(&self) -> PlaceId {
+        self.spec.id
+    }
+}
+
+impl From<PlaceBuilder<'_>> for PlaceId {
+    fn from(builder: PlaceBuilder<'_>) -> Self {
+        builder.id()
+    }
+}
+
+/// Returned by [`PetriNetBuilder::transition`]
+pub struct TransitionBuilder<'a> {
+    spec: &'a mut TransitionSpec,
+}
+
+impl TransitionBuilder<'_> {
+    /// Sets the callback run every time the transition fires
+    pub fn callback<F>(self, callback: F) -> Self
+    where
+        F: Fn(&Transition, &ConnectionMap, &ConnectionMap) + Send + Sync + 'static,
+    {
+        self.spec.callback = Some(Arc::new(callback));
+        self
+    }
+
+    pub fn id(&self) -> TransitionId {
+        self.spec.id
+    }
+}
+
+impl From<TransitionBuilder<'_>> for TransitionId {
+    fn from(builder: TransitionBuilder<'_>) -> Self {
+        builder.id()
+    }
+}
+
+/// Returned by [`PetriNetBuilder::arc`]
+pub struct ArcBuilder<'a> {
+    spec: &'a mut ArcSpec,
+}
+
+impl ArcBuilder<'_> {
+    pub fn weight(self, weight: i32) -> Self {
+        self.spec.weight = weight;
+        self
+    }
+
+    /// Turns the arc into an inhibitor arc
+    pub fn inhibitor(self) -> Self {
+        self.spec.con_type = ConnectionType::INHIBITOR;
+        self
+    }
+
+    /// Turns the arc into a reset arc
+    pub fn reset(self) -> Self {
+        self.spec.con_type = ConnectionType::RESET;
+        self
+    }
+
+    #[cfg(feature = "serde")]
+    pub(crate) fn connection_type(self, con_type: ConnectionType) -> Self {
+        self.spec.con_type = con_type;
+        self
+    }
+}
+
+#[derive(Clone)]
+enum Resolved {
+    Place(Arc<Place>),
+    Transition(Arc<Transition>),
+}
+
+// Lookup tables used to resolve the ends of arcs
+#[derive(Default)]
+struct Nodes {
+    by_name: HashMap<String, Resolved>,
+    by_id: HashMap<Uuid, Resolved>,
+}
+
+impl Nodes {
+    fn insert(&mut self, name: &str, id: Uuid, node: Resolved) {
+        self.by_name.insert(name.into(), node.clone());
+        self.by_id.insert(id, node);
+    }
+}
+
+impl PetriNetBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declares a place with no tokens
+    pub fn place(&mut self, name: &str) -> PlaceBuilder<'_> {
+        self.place_with_id(PlaceId(Uuid::new_v4()), name)
+    }
+
+    /// Declares a place keeping a known ID, e.g. one read back from a file
+    pub(crate) fn place_with_id(&mut self, id: PlaceId, name: &str) -> PlaceBuilder<'_> {
+        self.places.push(PlaceSpec {
+            id,
+            name: name.into(),
+            tokens: 0,
+        });
+
+        PlaceBuilder {
+            spec: self.places.last_mut().unwrap(),
+        }
+    }
+
+    /// Declares a transition without callback
+    pub fn transition(&mut self, name: &str) -> TransitionBuilder<'_> {
+        self.transition_with_id(TransitionId(Uuid::new_v4()), name)
+    }
+
+    /// Declares a transition keeping a known ID, e.g. one read back from a file
+    pub(crate) fn transition_with_id(
+        &mut self,
+        id: TransitionId,
+        name: &str,
+    ) -> TransitionBuilder<'_> {
+        self.transitions.push(TransitionSpec {
+            id,
+            name: name.into(),
+            callback: None,
+        });
+
+        TransitionBuilder {
+            spec: self.transitions.last_mut().unwrap(),
+        }
+    }
+
+    /// Declares a normal arc of weight 1. Its direction is given by the kind of `from` and `to`,
+    /// which are only resolved by [`build`](Self::build).
+    pub fn arc(&mut self, from: impl Into<NodeRef>, to: impl Into<NodeRef>) -> ArcBuilder<'_> {
+        self.arcs.push(ArcSpec {
+            from: from.into(),
+            to: to.into(),
+            weight: 1,
+            con_type: ConnectionType::NORMAL,
+        });
+
+        ArcBuilder {
+            spec: self.arcs.last_mut().unwrap(),
+        }
+    }
+
+    /// Validates the declarations and creates the net
+    pub fn build(self) -> Result<PetriNet, BuildError> {
+        let mut names = HashSet::new();
+        for name in self
+            .places
+            .iter()
+            .map(|p| &p.name)
+            .chain(self.transitions.iter().map(|t| &t.name))
+        {
+            if !names.insert(name) {
+                return Err(BuildError::DuplicateName(name.clone()));
+            }
+        }
+
+        let mut net = PetriNet::new();
+        let mut nodes = Nodes::default();
+
+        for spec in self.places.iter() {
+            if spec.tokens < 0 {
+                return Err(BuildError::NegativeTokens(spec.name.clone()));
+            }
+
+            net.add_place(Place::with_id(spec.id, &spec.name).with_tokens(spec.tokens));
+
+            let place = net.places().last().unwrap().clone();
+            nodes.insert(&spec.name, *spec.id.uuid(), Resolved::Place(place));
+        }
+
+        for spec in self.transitions.into_iter() {
+            let mut transition = Transition::with_id(spec.id, &spec.name);
+            if let Some(callback) = spec.callback {
+                transition.add_callback(callback);
+            }
+            net.add_transition(transition);
+
+            let transition = net.transitions().last().unwrap().clone();
+            nodes.insert(
+                &spec.name,
+                *spec.id.uuid(),
+                Resolved::Transition(transition),
+            );
+        }
+
+        for arc in self.arcs.into_iter() {
+            if arc.weight < 1 {
+                return Err(BuildError::InvalidWeight(arc.from, arc.to, arc.weight));
+            }
+
+            let from = Self::resolve(&nodes, &arc.from)?;
+            let to = Self::resolve(&nodes, &arc.to)?;
+
+            let connection = match (from, to) {
+                (Resolved::Place(place), Resolved::Transition(transition)) => Connection::new(
+                    place,
+                    transition,
+                    arc.weight,
+                    InputFrom::PLACE,
+                    arc.con_type,
+                ),
+                (Resolved::Transition(transition), Resolved::Place(place)) => {
+                    if arc.con_type != ConnectionType::NORMAL {
+                        return Err(BuildError::OutputArcType(arc.from, arc.to, arc.con_type));
+                    }
+
+                    Connection::new(
+                        place,
+                        transition,
+                        arc.weight,
+                        InputFrom::TRANSITION,
+                        arc.con_type,
+                    )
+                }
+                _ => return Err(BuildError::SameKindArc(arc.from, arc.to)),
+            };
+
+            net.add_connection(connection);
+        }
+
+        Ok(net)
+    }
+
+    fn resolve(nodes: &Nodes, node: &NodeRef) -> Result<Resolved, BuildError> {
+        let resolved = match node {
+            NodeRef::Name(name) => nodes.by_name.get(name.as_str()).cloned(),
+            NodeRef::Place(id) => nodes.by_id.get(id.uuid()).cloned(),
+            NodeRef::Transition(id) => nodes.by_id.get(id.uuid()).cloned(),
+        };
+
+        match (node, resolved) {
+            (NodeRef::Place(_), Some(Resolved::Transition(_)))
+            | (NodeRef::Transition(_), Some(Resolved::Place(_)))
+            | (_, None) => Err(BuildError::UnknownNode(node.clone())),
+            (_, Some(resolved)) => Ok(resolved),
+        }
+    }
+}
@@ -1,10 +1,11 @@
-use std::rc::Rc;
+use std::sync::Arc;
 
 use super::Connectable;
 use crate::net::*;
 use crate::ui::UITable;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ConnectionType {
     NORMAL,
     INHIBITOR,
@@ -26,6 +27,7 @@ impl std::fmt::Display for ConnectionType {
 }
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum InputFrom {
     PLACE,
     TRANSITION,
@@ -33,8 +35,8 @@ pub enum InputFrom {
 
 #[derive(Debug)]
 pub struct Connection {
-    place: Rc<Place>,
-    transition: Rc<Transition>,
+    place: Arc<Place>,
+    transition: Arc<Transition>,
     weight: i32,
     input_from: InputFrom,
     con_type: ConnectionType,
@@ -44,11 +46,11 @@ impl PartialEq for Connection {
     fn eq(&self, other: &Self) -> bool {
         self.place == other.place() &&
         self.transition() == other.transition() &&
-        self.weight == other.weight().clone()
+        self.weight == *other.weight()
     }
 }
 
-impl UITable for Vec<Rc<Connection>> {
+impl UITable for Vec<Arc<Connection>> {
     fn header(&self) -> Vec<&str> {
         vec!["Input", "Output", "Weight", "Type"]
     }
@@ -72,8 +74,8 @@ impl UITable for Vec<Rc<Connection>> {
 impl Connection {
     /// Creates a new connection
     pub fn new(
-        place: Rc<Place>,
-        transition: Rc<Transition>,
+        place: Arc<Place>,
+        transition: Arc<Transition>,
         weight: i32,
         input_from: InputFrom,
         con_type: ConnectionType,
@@ -92,14 +94,14 @@ impl Connection {
     }
 
     pub fn connection_type(&self) -> ConnectionType {
-        self.con_type.clone()
+        self.con_type
     }
 
-    pub fn place(&self) -> Rc<Place> {
+    pub fn place(&self) -> Arc<Place> {
         self.place.clone()
     }
 
-    pub fn transition(&self) -> Rc<Transition> {
+    pub fn transition(&self) -> Arc<Transition> {
         self.transition.clone()
     }
 
@@ -108,7 +110,7 @@ impl Connection {
     }
 
     /// Returns the connection's input. Can be either a Place or a Transition
-    pub fn input(&self) -> Rc<dyn Connectable> {
+    pub fn input(&self) -> Arc<dyn Connectable> {
         match self.input_from {
             InputFrom::PLACE => self.place.clone(),
             InputFrom::TRANSITION => self.transition.clone(),
@@ -116,7 +118,7 @@ impl Connection {
     }
 
     /// Returns the connection's output. Can be either a Place or a Transition
-    pub fn output(&self) -> Rc<dyn Connectable> {
+    pub fn output(&self) -> Arc<dyn Connectable> {
         match self.input_from {
             InputFrom::PLACE => self.transition.clone(),
             InputFrom::TRANSITION => self.place.clone(),
@@ -0,0 +1,114 @@
+//! Typed handles for the nodes of a net.
+//!
+//! [`PlaceId`] and [`TransitionId`] identify nodes at runtime and are handed out by the
+//! [`PetriNetBuilder`](super::PetriNetBuilder).
+//!
+//! The `petri_net!` macro declares a zero-sized type for every place and transition it is given.
+//! Those types implement [`Node`] and [`ValidArc`], which lets the compiler check that each arc
+//! goes from a place to a transition or the other way around. Unknown names fail with "cannot find
+//! value", a name declared twice fails with "defined multiple times", and a place→place or
+//! transition→transition arc fails with "arcs must connect a place and a transition", pointing at
+//! the offending node.
+
+use uuid::Uuid;
+
+use super::{Connection, ConnectionType, InputFrom, PetriNet, Place, Transition};
+
+/// Runtime handle for a place
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct PlaceId(pub(crate) Uuid);
+
+/// Runtime handle for a transition
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct TransitionId(pub(crate) Uuid);
+
+impl PlaceId {
+    pub fn uuid(&self) -> &Uuid {
+        &self.0
+    }
+}
+
+impl TransitionId {
+    pub fn uuid(&self) -> &Uuid {
+        &self.0
+    }
+}
+
+impl From<&Place> for PlaceId {
+    fn from(place: &Place) -> Self {
+        Self(*place.id())
+    }
+}
+
+impl From<&Transition> for TransitionId {
+    fn from(transition: &Transition) -> Self {
+        Self(*transition.id())
+    }
+}
+
+/// Kind marker for places
+pub struct PlaceKind;
+
+/// Kind marker for transitions
+pub struct TransitionKind;
+
+/// A node declared at compile time, usually generated by `petri_net!`
+pub trait Node {
+    /// Either `PlaceKind` or `TransitionKind`
+    type Kind;
+
+    /// Name of the node inside the net
+    const NAME: &'static str;
+}
+
+/// A node of `petri_net!` that is a place
+#[diagnostic::on_unimplemented(
+    message = "arcs must connect a place and a transition",
+    label = "`{Self}` is a transition, not a place"
+)]
+pub trait PlaceNode: Node {}
+
+/// A node of `petri_net!` that is a transition
+#[diagnostic::on_unimplemented(
+    message = "arcs must connect a place and a transition",
+    label = "`{Self}` is a place, not a transition"
+)]
+pub trait TransitionNode: Node {}
+
+/// Implemented by every node for the nodes it is allowed to have an arc to: places for every
+/// `TransitionNode`, transitions for every `PlaceNode`
+#[diagnostic::on_unimplemented(message = "arcs must connect a place and a transition")]
+pub trait ValidArc<To> {
+    const INPUT_FROM: InputFrom;
+}
+
+/// Adds an arc between two compile-time nodes. Both nodes must already be in `net`.
+///
+/// Only meant to be called by `petri_net!`, which adds every node before connecting them.
+#[doc(hidden)]
+pub fn connect<F, T>(net: &mut PetriNet, _from: F, _to: T, weight: i32, con_type: ConnectionType)
+where
+    F: Node + ValidArc<T>,
+    T: Node,
+{
+    let input_from = <F as ValidArc<T>>::INPUT_FROM;
+    let (place_name, transition_name) = match input_from {
+        InputFrom::PLACE => (F::NAME, T::NAME),
+        InputFrom::TRANSITION => (T::NAME, F::NAME),
+    };
+
+    let place = net
+        .place_with_name(place_name)
+        .expect("Place was declared but not added to the net");
+    let transition = net
+        .transition_with_name(transition_name)
+        .expect("Transition was declared but not added to the net");
+
+    net.add_connection(Connection::new(
+        place, transition, weight, input_from, con_type,
+    ));
+}
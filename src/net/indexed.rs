@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+use super::{ConnectionType, InputFrom, Marking, PetriNet};
+
+/// A connection of an `IndexedNet`, pointing at a place by its index in `PetriNet::places`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IndexedConnection {
+    pub place: usize,
+    pub weight: i32,
+    pub con_type: ConnectionType,
+}
+
+/// Dense, index-based view of the structure of a `PetriNet`.
+///
+/// Places and transitions are referred to by their position in `PetriNet::places` and
+/// `PetriNet::transitions`. The incoming (pre) and outgoing (post) connections of every
+/// transition are precomputed, as well as which transitions may change their enabledness when
+/// another one fires, so that simulations and analyses never have to scan the whole net.
+#[derive(Clone, Debug)]
+pub struct IndexedNet {
+    place_count: usize,
+    pre: Vec<Vec<IndexedConnection>>,
+    post: Vec<Vec<IndexedConnection>>,
+    consumers: Vec<Vec<usize>>,
+    producers: Vec<Vec<usize>>,
+    neighbours: Vec<Vec<usize>>,
+}
+
+impl IndexedNet {
+    pub fn new(net: &PetriNet) -> Self {
+        let place_count = net.places().len();
+        let transition_count = net.transitions().len();
+
+        let mut pre = vec![vec![]; transition_count];
+        let mut post = vec![vec![]; transition_count];
+        let mut consumers = vec![vec![]; place_count];
+        let mut producers = vec![vec![]; place_count];
+
+        let place_index: HashMap<&Uuid, usize> = net
+            .places()
+            .iter()
+            .enumerate()
+            .map(|(index, place)| (place.id(), index))
+            .collect();
+        let transition_index: HashMap<&Uuid, usize> = net
+            .transitions()
+            .iter()
+            .enumerate()
+            .map(|(index, transition)| (transition.id(), index))
+            .collect();
+
+        for connection in net.connections().iter() {
+            let place = *place_index
+                .get(connection.place().id())
+                .expect("Connection references a place outside of the net");
+            let transition = *transition_index
+                .get(connection.transition().id())
+                .expect("Connection references a transition outside of the net");
+
+            let indexed = IndexedConnection {
+                place,
+                weight: *connection.weight(),
+                con_type: connection.connection_type(),
+            };
+
+            match connection.input_from() {
+                InputFrom::PLACE => {
+                    pre[transition].push(indexed);
+                    consumers[place].push(transition);
+                }
+                InputFrom::TRANSITION => {
+                    post[transition].push(indexed);
+                    producers[place].push(transition);
+                }
+            }
+        }
+
+        for list in consumers.iter_mut().chain(producers.iter_mut()) {
+            list.sort_unstable();
+            list.dedup();
+        }
+
+        // Firing a transition only changes the places it is connected to, so only the
+        // consumers of those places have to be checked again afterwards
+        let neighbours = (0..transition_count)
+            .map(|transition| {
+                let mut list: Vec<usize> = pre[transition]
+                    .iter()
+                    .chain(post[transition].iter())
+                    .flat_map(|con: &IndexedConnection| consumers[con.place].iter().copied())
+                    .collect();
+                list.sort_unstable();
+                list.dedup();
+                list
+            })
+            .collect();
+
+        Self {
+            place_count,
+            pre,
+            post,
+            consumers,
+            producers,
+            neighbours,
+        }
+    }
+
+    pub fn place_count(&self) -> usize {
+        self.place_count
+    }
+
+    pub fn transition_count(&self) -> usize {
+        self.pre.len()
+    }
+
+    /// Connections going from places into `transition`
+    pub fn pre(&self, transition: usize) -> &[IndexedConnection] {
+        &self.pre[transition]
+    }
+
+    /// Connections going from `transition` into places
+    pub fn post(&self, transition: usize) -> &[IndexedConnection] {
+        &self.post[transition]
+    }
+
+    /// Transitions with a connection coming from `place`, of any type
+    pub fn consumers(&self, place: usize) -> &[usize] {
+        &self.consumers[place]
+    }
+
+    /// Transitions with a connection going into `place`
+    pub fn producers(&self, place: usize) -> &[usize] {
+        &self.producers[place]
+    }
+
+    /// Transitions whose enabledness may change after `transition` fires
+    pub fn neighbours(&self, transition: usize) -> &[usize] {
+        &self.neighbours[transition]
+    }
+
+    /// Whether `transition` is enabled in `marking`
+    pub fn is_enabled(&self, transition: usize, marking: &Marking) -> bool {
+        self.pre[transition].iter().all(|con| match con.con_type {
+            ConnectionType::NORMAL => marking[con.place] >= con.weight,
+            ConnectionType::INHIBITOR => marking[con.place] < con.weight,
+            ConnectionType::RESET => true,
+        })
+    }
+
+    /// All transitions enabled in `marking`
+    pub fn enabled(&self, marking: &Marking) -> Vec<usize> {
+        (0..self.transition_count())
+            .filter(|transition| self.is_enabled(*transition, marking))
+            .collect()
+    }
+
+    /// Removes the tokens taken by `transition` from `marking`
+    pub fn consume(&self, transition: usize, marking: &mut Marking) {
+        for con in self.pre[transition].iter() {
+            match con.con_type {
+                ConnectionType::NORMAL => marking[con.place] -= con.weight,
+                ConnectionType::INHIBITOR => {}
+                ConnectionType::RESET => marking[con.place] = 0,
+            }
+        }
+    }
+
+    /// Adds the tokens produced by `transition` to `marking`
+    pub fn produce(&self, transition: usize, marking: &mut Marking) {
+        for con in self.post[transition].iter() {
+            marking[con.place] += con.weight;
+        }
+    }
+
+    /// Fires `transition` in `marking` without checking whether it is enabled
+    pub fn fire(&self, transition: usize, marking: &mut Marking) {
+        self.consume(transition, marking);
+        self.produce(transition, marking);
+    }
+
+    /// Returns the marking reached by firing `transition`, or `None` if it is not enabled
+    pub fn successor(&self, transition: usize, marking: &Marking) -> Option<Marking> {
+        if !self.is_enabled(transition, marking) {
+            return None;
+        }
+
+        let mut next = marking.clone();
+        self.fire(transition, &mut next);
+
+        Some(next)
+    }
+}
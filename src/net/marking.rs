@@ -0,0 +1,93 @@
+use std::ops::{Index, IndexMut};
+
+/// Tokens of every place of a net, indexed like `PetriNet::places`.
+///
+/// Markings are plain values: they can be cloned, compared and hashed independently of the net
+/// they belong to, which is what simulations and analyses operate on.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Marking(Vec<i32>);
+
+impl Marking {
+    /// Creates a marking where every one of the `places` places is empty
+    pub fn empty(places: usize) -> Self {
+        Self(vec![0; places])
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Tokens of the place at `index`, if it exists
+    pub fn get(&self, index: usize) -> Option<i32> {
+        self.0.get(index).copied()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, i32> {
+        self.0.iter()
+    }
+
+    pub fn as_slice(&self) -> &[i32] {
+        &self.0
+    }
+
+    /// Total amount of tokens in the marking
+    pub fn total(&self) -> i32 {
+        self.0.iter().sum()
+    }
+
+    /// Whether every place holds at least as many tokens as in `other`
+    pub fn covers(&self, other: &Marking) -> bool {
+        self.0.len() == other.0.len() && self.0.iter().zip(other.0.iter()).all(|(a, b)| a >= b)
+    }
+}
+
+impl From<Vec<i32>> for Marking {
+    fn from(tokens: Vec<i32>) -> Self {
+        Self(tokens)
+    }
+}
+
+impl From<Marking> for Vec<i32> {
+    fn from(marking: Marking) -> Self {
+        marking.0
+    }
+}
+
+impl FromIterator<i32> for Marking {
+    fn from_iter<I: IntoIterator<Item = i32>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl Index<usize> for Marking {
+    type Output = i32;
+
+    fn index(&self, index: usize) -> &i32 {
+        &self.0[index]
+    }
+}
+
+impl IndexMut<usize> for Marking {
+    fn index_mut(&mut self, index: usize) -> &mut i32 {
+        &mut self.0[index]
+    }
+}
+
+impl std::fmt::Display for Marking {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(")?;
+        for (i, tokens) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", tokens)?;
+        }
+        write!(f, ")")
+    }
+}
@@ -1,14 +1,29 @@
+pub mod builder;
 pub mod connection;
+pub mod handle;
+pub mod indexed;
+pub mod marking;
 pub mod net;
+#[cfg(feature = "serde")]
+mod persist;
 pub mod place;
 pub mod simulation;
+pub mod trace;
 pub mod transition;
 
+pub use builder::{BuildError, PetriNetBuilder};
 pub use connection::{Connection, ConnectionType, InputFrom};
-pub use net::PetriNet;
+pub use handle::{PlaceId, TransitionId};
+pub use indexed::{IndexedConnection, IndexedNet};
+pub use marking::Marking;
+pub use net::{EditError, PetriNet};
 pub use place::Place;
-pub use simulation::{ConnectionMap, Simulation};
-pub use transition::Transition;
+pub use simulation::{
+    Branch, Conflict, ConflictChoice, ConnectionMap, Cycle, Simulation, SimulationSnapshot,
+    SnapshotError,
+};
+pub use trace::Trace;
+pub use transition::{Transition, TransitionCallback};
 
 pub trait Connectable {
     fn connection_title(&self) -> &str;
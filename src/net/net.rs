@@ -1,12 +1,42 @@
-use std::rc::Rc;
+use std::sync::Arc;
 
 use crate::net::*;
 
-#[derive(Default)]
+/// Errors returned by the methods that edit a `PetriNet` in place
+#[derive(Clone, Debug, PartialEq)]
+pub enum EditError {
+    UnknownPlace(PlaceId),
+    UnknownTransition(TransitionId),
+    /// The connection is not part of the net, or was replaced by a previous edit
+    UnknownConnection,
+    /// Another node already uses the name
+    DuplicateName(String),
+    InvalidWeight(i32),
+    NegativeTokens(i32),
+}
+
+impl std::fmt::Display for EditError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EditError::UnknownPlace(id) => write!(f, "place {} is not in the net", id.uuid()),
+            EditError::UnknownTransition(id) => {
+                write!(f, "transition {} is not in the net", id.uuid())
+            }
+            EditError::UnknownConnection => write!(f, "connection is not in the net"),
+            EditError::DuplicateName(name) => write!(f, "name `{}` is already in use", name),
+            EditError::InvalidWeight(weight) => write!(f, "invalid weight {}", weight),
+            EditError::NegativeTokens(tokens) => write!(f, "invalid amount of tokens {}", tokens),
+        }
+    }
+}
+
+impl std::error::Error for EditError {}
+
+#[derive(Clone, Default)]
 pub struct PetriNet {
-    places: Vec<Rc<Place>>,
-    transitions: Vec<Rc<Transition>>,
-    connections: Vec<Rc<Connection>>,
+    places: Vec<Arc<Place>>,
+    transitions: Vec<Arc<Transition>>,
+    connections: Vec<Arc<Connection>>,
 }
 
 impl PetriNet {
@@ -21,36 +51,222 @@ impl PetriNet {
 
     /// Adds `place` to the net
     pub fn add_place(&mut self, place: Place) {
-        self.places.push(Rc::new(place));
+        self.places.push(Arc::new(place));
     }
 
     /// Adds `transition` to the net
     pub fn add_transition(&mut self, transition: Transition) {
-        self.transitions.push(Rc::new(transition));
+        self.transitions.push(Arc::new(transition));
     }
 
     /// Adds `connection` to the net
     pub fn add_connection(&mut self, connection: Connection) {
-        self.connections.push(Rc::new(connection));
+        self.connections.push(Arc::new(connection));
+    }
+
+    /// Removes the place identified by `id` along with every connection attached to it
+    pub fn remove_place(&mut self, id: PlaceId) -> Result<Arc<Place>, EditError> {
+        let index = self.place_index(id).ok_or(EditError::UnknownPlace(id))?;
+
+        self.connections
+            .retain(|connection| connection.place().id() != id.uuid());
+
+        Ok(self.places.remove(index))
+    }
+
+    /// Removes the transition identified by `id` along with every connection attached to it
+    pub fn remove_transition(&mut self, id: TransitionId) -> Result<Arc<Transition>, EditError> {
+        let index = self
+            .transitions
+            .iter()
+            .position(|transition| transition.id() == id.uuid())
+            .ok_or(EditError::UnknownTransition(id))?;
+
+        self.connections
+            .retain(|connection| connection.transition().id() != id.uuid());
+
+        Ok(self.transitions.remove(index))
+    }
+
+    /// Removes `connection`, which must be one of the values returned by `connections`
+    pub fn remove_connection(
+        &mut self,
+        connection: &Arc<Connection>,
+    ) -> Result<Arc<Connection>, EditError> {
+        let index = self.connection_index(connection)?;
+
+        Ok(self.connections.remove(index))
+    }
+
+    /// Changes the weight of `connection`. Connections are immutable, so the old value is
+    /// replaced and the new one is returned.
+    pub fn set_weight(
+        &mut self,
+        connection: &Arc<Connection>,
+        weight: i32,
+    ) -> Result<Arc<Connection>, EditError> {
+        if weight < 1 {
+            return Err(EditError::InvalidWeight(weight));
+        }
+
+        let index = self.connection_index(connection)?;
+        let new_connection = Arc::new(Connection::new(
+            connection.place(),
+            connection.transition(),
+            weight,
+            connection.input_from(),
+            connection.connection_type(),
+        ));
+        self.connections[index] = new_connection.clone();
+
+        Ok(new_connection)
+    }
+
+    /// Sets the tokens of the place identified by `id` in the initial marking, returning the
+    /// previous amount. The place and its connections are replaced by copies.
+    pub fn set_tokens(&mut self, id: PlaceId, tokens: i32) -> Result<i32, EditError> {
+        if tokens < 0 {
+            return Err(EditError::NegativeTokens(tokens));
+        }
+
+        let index = self.place_index(id).ok_or(EditError::UnknownPlace(id))?;
+        let previous = self.places[index].tokens();
+        let place = self.places[index].renamed(self.places[index].name());
+        self.replace_place(index, place.with_tokens(tokens));
+
+        Ok(previous)
+    }
+
+    /// Renames the place identified by `id`. The place and its connections are replaced by
+    /// copies, so previously obtained `Arc`s keep the old name.
+    pub fn rename_place(&mut self, id: PlaceId, name: &str) -> Result<Arc<Place>, EditError> {
+        self.check_name_available(name)?;
+
+        let index = self.place_index(id).ok_or(EditError::UnknownPlace(id))?;
+        let place = self.places[index].renamed(name);
+
+        Ok(self.replace_place(index, place))
+    }
+
+    /// Renames the transition identified by `id`. The transition and its connections are
+    /// replaced by copies, so previously obtained `Arc`s keep the old name.
+    pub fn rename_transition(
+        &mut self,
+        id: TransitionId,
+        name: &str,
+    ) -> Result<Arc<Transition>, EditError> {
+        self.check_name_available(name)?;
+
+        let index = self
+            .transitions
+            .iter()
+            .position(|transition| transition.id() == id.uuid())
+            .ok_or(EditError::UnknownTransition(id))?;
+
+        let transition = Arc::new(self.transitions[index].renamed(name));
+        self.transitions[index] = transition.clone();
+
+        for connection in self.connections.iter_mut() {
+            if connection.transition().id() == id.uuid() {
+                *connection = Arc::new(Connection::new(
+                    connection.place(),
+                    transition.clone(),
+                    *connection.weight(),
+                    connection.input_from(),
+                    connection.connection_type(),
+                ));
+            }
+        }
+
+        Ok(transition)
+    }
+
+    /// Replaces the place at `index` and the connections attached to it
+    fn replace_place(&mut self, index: usize, place: Place) -> Arc<Place> {
+        let place = Arc::new(place);
+        let old = std::mem::replace(&mut self.places[index], place.clone());
+
+        for connection in self.connections.iter_mut() {
+            if connection.place().id() == old.id() {
+                *connection = Arc::new(Connection::new(
+                    place.clone(),
+                    connection.transition(),
+                    *connection.weight(),
+                    connection.input_from(),
+                    connection.connection_type(),
+                ));
+            }
+        }
+
+        place
+    }
+
+    fn connection_index(&self, connection: &Arc<Connection>) -> Result<usize, EditError> {
+        self.connections
+            .iter()
+            .position(|con| Arc::ptr_eq(con, connection))
+            .ok_or(EditError::UnknownConnection)
+    }
+
+    fn check_name_available(&self, name: &str) -> Result<(), EditError> {
+        if self.place_with_name(name).is_some() || self.transition_with_name(name).is_some() {
+            return Err(EditError::DuplicateName(name.into()));
+        }
+
+        Ok(())
     }
 
     /// Return a reference to `places`
-    pub fn places(&self) -> &Vec<Rc<Place>> {
+    pub fn places(&self) -> &Vec<Arc<Place>> {
         &self.places
     }
 
     /// Return a reference to `transitions`
-    pub fn transitions(&self) -> &Vec<Rc<Transition>> {
+    pub fn transitions(&self) -> &Vec<Arc<Transition>> {
         &self.transitions
     }
 
     /// Return a reference to `connections`
-    pub fn connections(&self) -> &Vec<Rc<Connection>> {
+    pub fn connections(&self) -> &Vec<Arc<Connection>> {
         &self.connections
     }
 
+    /// Return the place identified by `id`, if it doesn't exist, return None
+    pub fn place(&self, id: PlaceId) -> Option<Arc<Place>> {
+        self.places
+            .iter()
+            .find(|place| place.id() == id.uuid())
+            .cloned()
+    }
+
+    /// Return the transition identified by `id`, if it doesn't exist, return None
+    pub fn transition(&self, id: TransitionId) -> Option<Arc<Transition>> {
+        self.transitions
+            .iter()
+            .find(|transition| transition.id() == id.uuid())
+            .cloned()
+    }
+
+    /// Return the position of the place identified by `id` in `places`, which is also its index
+    /// in a `Marking`
+    pub fn place_index(&self, id: PlaceId) -> Option<usize> {
+        self.places.iter().position(|place| place.id() == id.uuid())
+    }
+
+    /// Return the position of the transition identified by `id` in `transitions`
+    pub fn transition_index(&self, id: TransitionId) -> Option<usize> {
+        self.transitions
+            .iter()
+            .position(|transition| transition.id() == id.uuid())
+    }
+
+    /// Return the marking given by the tokens of each place
+    pub fn initial_marking(&self) -> Marking {
+        self.places.iter().map(|place| place.tokens()).collect()
+    }
+
     /// Return place named `name`, if it doesn't exist, return None
-    pub fn place_with_name(&self, name: &str) -> Option<Rc<Place>> {
+    pub fn place_with_name(&self, name: &str) -> Option<Arc<Place>> {
         for place in self.places.iter() {
             if place.name() == name {
                 return Some(place.clone());
@@ -61,7 +277,7 @@ impl PetriNet {
     }
 
     /// Return transition named `name`, if it doesn't exist, return None
-    pub fn transition_with_name(&self, name: &str) -> Option<Rc<Transition>> {
+    pub fn transition_with_name(&self, name: &str) -> Option<Arc<Transition>> {
         for transition in self.transitions.iter() {
             if transition.name() == name {
                 return Some(transition.clone());
@@ -74,69 +290,145 @@ impl PetriNet {
 
 #[macro_export]
 macro_rules! connection_type {
-    (->) => { crate::net::ConnectionType::NORMAL };
-    (@) => { crate::net::ConnectionType::INHIBITOR };
-    (>>) => { crate::net::ConnectionType::RESET };
+    (->) => { $crate::net::ConnectionType::NORMAL };
+    (@) => { $crate::net::ConnectionType::INHIBITOR };
+    (>>) => { $crate::net::ConnectionType::RESET };
 }
 
+/// Declares a `PetriNet`.
+///
+/// Every place and transition becomes a typed handle, so arcs are checked at compile time:
+///
+/// ```
+/// use petri_engine::petri_net;
+///
+/// let pn = petri_net! {
+///     places => [P1<1>, P2],
+///     transitions => [T1],
+///     connections => [P1 -> T1, (2) T1 -> P2]
+/// };
+///
+/// assert_eq!(pn.connections().len(), 2);
+/// ```
+///
+/// Sections may come in any order, connections are only resolved once every node is declared:
+///
+/// ```
+/// use petri_engine::petri_net;
+///
+/// let pn = petri_net! {
+///     connections => [P1 -> T1],
+///     places => [P1<1>],
+///     transitions => [T1]
+/// };
+///
+/// assert_eq!(pn.connections().len(), 1);
+/// ```
+///
+/// Arcs between two places are rejected:
+///
+/// ```compile_fail
+/// use petri_engine::petri_net;
+///
+/// let pn = petri_net! {
+///     places => [P1, P2],
+///     transitions => [T1],
+///     connections => [P1 -> P2]
+/// };
+/// ```
+///
+/// So are names that were never declared:
+///
+/// ```compile_fail
+/// use petri_engine::petri_net;
+///
+/// let pn = petri_net! {
+///     places => [P1],
+///     transitions => [T1],
+///     connections => [P1 -> T2]
+/// };
+/// ```
+///
+/// And a place and a transition sharing a name:
+///
+/// ```compile_fail
+/// use petri_engine::petri_net;
+///
+/// let pn = petri_net! {
+///     places => [X],
+///     transitions => [X],
+///     connections => [X -> X]
+/// };
+/// ```
 #[macro_export]
 macro_rules! petri_net {
+    (@handle $name:ident $kind:ident $node:ident $target:ident $input_from:ident) => {
+        #[allow(non_camel_case_types, dead_code)]
+        struct $name;
+
+        impl $crate::net::handle::Node for $name {
+            type Kind = $crate::net::handle::$kind;
+            const NAME: &'static str = stringify!($name);
+        }
+
+        impl $crate::net::handle::$node for $name {}
+
+        impl<To: $crate::net::handle::$target> $crate::net::handle::ValidArc<To> for $name {
+            const INPUT_FROM: $crate::net::InputFrom = $crate::net::InputFrom::$input_from;
+        }
+    };
+
     (places $var:ident [ $($name:ident $(< $tokens:literal >)?),+ ]) => {
         $(
-            let place = crate::net::Place::new(stringify!($name));
-            $( place.add_tokens($tokens); )?
-            $var.add_place(place);
+            $crate::petri_net!(@handle $name PlaceKind PlaceNode TransitionNode PLACE);
+
+            let __pn_place = $crate::net::Place::new(stringify!($name));
+            $( let __pn_place = __pn_place.with_tokens($tokens); )?
+            $var.add_place(__pn_place);
         )+
     };
 
     (transitions $var:ident [ $($name:ident $(-> |$tr:pat_param, $inc:pat_param, $out:pat_param| $callback:block)?),+ ]) => {
         $(
+            $crate::petri_net!(@handle $name TransitionKind TransitionNode PlaceNode TRANSITION);
+
             #[allow(unused_mut)]
-            let mut transition = crate::net::Transition::new(stringify!($name));
+            let mut __pn_transition = $crate::net::Transition::new(stringify!($name));
 
-            $( transition.add_callback(Box::new(|$tr, $inc, $out| $callback)); )?
+            $( __pn_transition.add_callback(std::sync::Arc::new(|$tr, $inc, $out| $callback)); )?
 
-            $var.add_transition(transition);
+            $var.add_transition(__pn_transition);
         )+
     };
 
     (connections $var:ident [ $( $(( $weight:literal ))? $from:ident $ctype:tt $to:ident ),+ ]) => {
         $(
-            let con_type = $crate::connection_type!($ctype);
+            let __pn_con_type = $crate::connection_type!($ctype);
 
             #[allow(unused_variables)]
-            let weight: i32 = 1;
-            $( let weight = $weight; )?
-
-            if let Some(place) = $var.place_with_name(stringify!($from)) {
-                let input_from = crate::net::InputFrom::PLACE;
-                let transition = $var.transition_with_name(stringify!($to))
-                    .expect("Transition does not exist");
-
-                $var.add_connection(
-                    crate::net::Connection::new(place, transition, weight, input_from, con_type)
-                );
-            } else {
-                let input_from = crate::net::InputFrom::TRANSITION;
-                let transition = $var.transition_with_name(stringify!($from))
-                    .expect("Transition does not exist");
-                let place = $var.place_with_name(stringify!($to))
-                    .expect("Place does not exist");
-
-                $var.add_connection(
-                    crate::net::Connection::new(place, transition, weight, input_from, con_type)
-                );
-            }
+            let __pn_weight: i32 = 1;
+            $( let __pn_weight = $weight; )?
+
+            $crate::net::handle::connect(&mut $var, $from, $to, __pn_weight, __pn_con_type);
         )+
     };
 
+    (@declare connections $var:ident $vals:tt) => {};
+    (@declare $decl:ident $var:ident $vals:tt) => { $crate::petri_net!($decl $var $vals); };
+
+    (@connect connections $var:ident $vals:tt) => { $crate::petri_net!(connections $var $vals); };
+    (@connect $decl:ident $var:ident $vals:tt) => {};
+
     ($( $decl:tt => $vals:tt ),+) => {
         {
-            let mut new_net = crate::net::PetriNet::new();
+            let mut __pn_net = $crate::net::PetriNet::new();
 
-            $( petri_net!($decl new_net $vals); )+
+            // Every node is added before the first connection looks it up, whatever the order the
+            // sections are written in
+            $( $crate::petri_net!(@declare $decl __pn_net $vals); )+
+            $( $crate::petri_net!(@connect $decl __pn_net $vals); )+
 
-            new_net
+            __pn_net
         }
     };
 }
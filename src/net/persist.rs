@@ -0,0 +1,97 @@
+//! Serde support, enabled by the `serde` feature.
+//!
+//! Connections are written with the IDs of their place and transition instead of copies of the
+//! nodes, so they can only be read back as part of a `PetriNet`, which links them to its own nodes.
+//! Transition callbacks are never serialized.
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::net::builder::NodeRef;
+use crate::net::*;
+
+#[derive(Serialize, Deserialize)]
+struct ConnectionRepr {
+    place: PlaceId,
+    transition: TransitionId,
+    weight: i32,
+    input_from: InputFrom,
+    con_type: ConnectionType,
+}
+
+impl From<&Connection> for ConnectionRepr {
+    fn from(connection: &Connection) -> Self {
+        Self {
+            place: connection.place().as_ref().into(),
+            transition: connection.transition().as_ref().into(),
+            weight: *connection.weight(),
+            input_from: connection.input_from(),
+            con_type: connection.connection_type(),
+        }
+    }
+}
+
+impl Serialize for Connection {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ConnectionRepr::from(self).serialize(serializer)
+    }
+}
+
+#[derive(Serialize)]
+struct PetriNetRef<'a> {
+    places: Vec<&'a Place>,
+    transitions: Vec<&'a Transition>,
+    connections: Vec<ConnectionRepr>,
+}
+
+#[derive(Deserialize)]
+struct PetriNetRepr {
+    places: Vec<Place>,
+    transitions: Vec<Transition>,
+    connections: Vec<ConnectionRepr>,
+}
+
+impl Serialize for PetriNet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        PetriNetRef {
+            places: self.places().iter().map(|place| place.as_ref()).collect(),
+            transitions: self.transitions().iter().map(|tr| tr.as_ref()).collect(),
+            connections: self
+                .connections()
+                .iter()
+                .map(|con| con.as_ref().into())
+                .collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PetriNet {
+    /// Checks the net like `PetriNetBuilder::build`, keeping the IDs of its nodes
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = PetriNetRepr::deserialize(deserializer)?;
+        let mut builder = PetriNetBuilder::new();
+
+        for place in repr.places.iter() {
+            builder
+                .place_with_id(place.into(), place.name())
+                .tokens(place.tokens());
+        }
+        for transition in repr.transitions.iter() {
+            builder.transition_with_id(transition.into(), transition.name());
+        }
+
+        for con in repr.connections.into_iter() {
+            let (from, to) = match con.input_from {
+                InputFrom::PLACE => (NodeRef::from(con.place), NodeRef::from(con.transition)),
+                InputFrom::TRANSITION => (NodeRef::from(con.transition), NodeRef::from(con.place)),
+            };
+            builder
+                .arc(from, to)
+                .weight(con.weight)
+                .connection_type(con.con_type);
+        }
+
+        builder.build().map_err(D::Error::custom)
+    }
+}
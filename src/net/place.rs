@@ -1,23 +1,21 @@
 use derivative::Derivative;
 use uuid::Uuid;
 
-use std::cell::RefCell;
-use std::rc::Rc;
+use std::sync::Arc;
 
-use super::Connectable;
+use super::{Connectable, PlaceId};
 use crate::ui::UITable;
 
-#[derive(Clone, Debug)]
-pub struct TokensInner(i32);
-
 #[derive(Derivative)]
 #[derivative(Debug, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Place {
     id: Uuid,
     name: String,
 
+    // Tokens in the initial marking. The marking during a simulation is kept in a `Marking`.
     #[derivative(Hash = "ignore")]
-    tokens: RefCell<TokensInner>,
+    tokens: i32,
 }
 
 impl PartialEq for Place {
@@ -26,7 +24,7 @@ impl PartialEq for Place {
     }
 }
 
-impl UITable for Vec<Rc<Place>> {
+impl UITable for Vec<Arc<Place>> {
     fn header(&self) -> Vec<&str> {
         vec!["Name", "Tokens"]
     }
@@ -37,7 +35,7 @@ impl UITable for Vec<Rc<Place>> {
         for elem in self {
             rows.push(vec![
                 elem.name().to_string(),
-                elem.tokens.borrow().0.clone().to_string(),
+                elem.tokens().to_string(),
             ])
         }
 
@@ -54,33 +52,43 @@ impl Connectable for Place {
 impl Place {
     /// Creates a new place with given name
     pub fn new(name: &str) -> Self {
+        Self::with_id(PlaceId(Uuid::new_v4()), name)
+    }
+
+    /// Creates a new place with a known handle
+    pub(crate) fn with_id(id: PlaceId, name: &str) -> Self {
         Self {
-            id: Uuid::new_v4(),
+            id: id.0,
             name: name.into(),
-            tokens: RefCell::new(TokensInner(0)),
+            tokens: 0,
         }
     }
 
-    pub fn name(&self) -> &str {
-        &self.name
+    /// Returns a copy of the place with the same ID and tokens but a different name
+    pub(crate) fn renamed(&self, name: &str) -> Self {
+        Self {
+            id: self.id,
+            name: name.into(),
+            tokens: self.tokens,
+        }
     }
 
-    pub fn tokens(&self) -> i32 {
-        self.tokens.borrow().0.clone()
+    pub fn name(&self) -> &str {
+        &self.name
     }
 
-    /// Adds `amount` tokens to place
-    pub fn add_tokens(&self, amount: i32) {
-        self.tokens.borrow_mut().0 += amount;
+    pub fn id(&self) -> &Uuid {
+        &self.id
     }
 
-    /// Removes `amount` tokens from place
-    pub fn remove_tokens(&self, amount: i32) {
-        self.tokens.borrow_mut().0 -= amount;
+    /// Tokens of the place in the initial marking
+    pub fn tokens(&self) -> i32 {
+        self.tokens
     }
 
-    /// Removes all tokens from place, usually called by a Reset connection
-    pub fn clear_tokens(&self) {
-        self.tokens.borrow_mut().0 = 0;
+    /// Sets the tokens of the place in the initial marking
+    pub fn with_tokens(mut self, amount: i32) -> Self {
+        self.tokens = amount;
+        self
     }
 }
@@ -1,34 +1,189 @@
-use std::cell::RefCell;
 use std::collections::HashMap;
-use std::io::stdout;
-use std::rc::Rc;
-
-use crossterm::{
-    event::{read, Event, KeyCode},
-    execute,
-    style::{Color, Print, ResetColor, SetForegroundColor},
-    terminal::{disable_raw_mode, enable_raw_mode},
-};
+use std::sync::{Arc, Mutex};
+
 use rand::prelude::*;
 
-use super::{Connection, PetriNet, Place, Transition};
-use crate::ui::UITable;
+use uuid::Uuid;
 
-// A `Transition` (key) connects to a `Place` via a `Connection` (value tuple)
-pub type ConnectionMap = HashMap<Rc<Transition>, Vec<(Rc<Place>, Rc<Connection>)>>;
+use super::{
+    Connection, IndexedNet, InputFrom, Marking, PetriNet, Place, PlaceId, Trace, Transition,
+};
+use crate::ui::{Explorer, UITable};
 
-// A `Plaace` (key) can have at least two concurrent `Connection`s (value)
-pub type ConcurrencyMap = HashMap<Rc<Place>, Vec<Rc<Connection>>>;
+// A `Transition` (key) connects to a `Place` via a `Connection` (value tuple)
+pub type ConnectionMap = HashMap<Arc<Transition>, Vec<(Arc<Place>, Arc<Connection>)>>;
 
 pub struct Simulation {
-    net: PetriNet,
-    incoming_connections: ConnectionMap,
+    net: Arc<PetriNet>,
+    indexed: IndexedNet,
+    incoming_connections: ConnectionMap, // Handed to transition callbacks
     outgoing_connections: ConnectionMap,
-    concurrent_connections: ConcurrencyMap,
-    ui_rows: RefCell<Vec<Vec<String>>>, // Store a log of each step for printing
-    interactive: bool,                  // Whether user confirmation is required to advance
+    place_index: HashMap<Uuid, usize>, // Position of each place in `net.places()`
+    state: Mutex<State>,               // Always locked before `history`
+    history: Mutex<History>,           // Every cycle run so far, including undone ones
+    interactive: bool,                 // Whether `run` lets the user drive the simulation
+}
+
+struct State {
+    marking: Marking,   // Current state, the net itself is never modified
+    enabled: Vec<bool>, // Whether each transition is enabled in `marking`
+}
+
+/// Every branch explored by a simulation, and where in them the current marking is
+struct History {
+    branches: Vec<Branch>,
+    current: usize,  // Branch being explored
+    position: usize, // Cycles of the current branch leading to the current marking
+}
+
+impl History {
+    fn new(marking: Marking) -> Self {
+        Self {
+            branches: vec![Branch {
+                cycles: vec![],
+                marking,
+            }],
+            current: 0,
+            position: 0,
+        }
+    }
+
+    /// Marking reached after `position` cycles of `branch`
+    fn marking(&self, branch: usize, position: usize) -> Marking {
+        let branch = &self.branches[branch];
+        match branch.cycles.get(position) {
+            Some(cycle) => cycle.marking.clone(),
+            None => branch.marking.clone(),
+        }
+    }
+
+    /// Appends a cycle at the current position, in a new branch if the current one goes on
+    /// differently
+    fn record(&mut self, cycle: Cycle, marking: Marking) {
+        let branch = &mut self.branches[self.current];
+        if branch.cycles.get(self.position) == Some(&cycle) {
+            // Same cycle as the undone one, which leads to the same marking
+        } else if self.position < branch.cycles.len() {
+            let mut cycles = branch.cycles[..self.position].to_vec();
+            cycles.push(cycle);
+            self.branches.push(Branch { cycles, marking });
+            self.current = self.branches.len() - 1;
+        } else {
+            branch.cycles.push(cycle);
+            branch.marking = marking;
+        }
+        self.position += 1;
+    }
 }
 
+/// A sequence of cycles explored by a `Simulation`. Running a cycle after going back in time
+/// starts a new branch, sharing the cycles before it with the branch it left.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Branch {
+    pub cycles: Vec<Cycle>,
+    /// Marking after the last cycle
+    pub marking: Marking,
+}
+
+/// One cycle of a `Simulation`
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cycle {
+    /// Marking at the start of the cycle
+    pub marking: Marking,
+    /// Indices of the transitions fired during the cycle
+    pub fired: Vec<usize>,
+    /// Conflicts that were resolved to choose `fired`
+    pub conflicts: Vec<ConflictChoice>,
+}
+
+/// Enabled transitions competing for the tokens of a place, and the one that was allowed to fire
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConflictChoice {
+    pub place: usize,
+    pub candidates: Vec<usize>,
+    pub chosen: usize,
+}
+
+/// Enabled transitions competing for the tokens of a place, only one of which can fire
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Conflict {
+    pub place: usize,
+    /// Tokens on the place when the cycle starts
+    pub tokens: i32,
+    pub candidates: Vec<usize>,
+}
+
+/// Saved state of a `Simulation`. The net is not part of the snapshot, since transition callbacks
+/// cannot be saved, and has to be given again to `Simulation::restore`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SimulationSnapshot {
+    pub marking: Marking,
+    /// Cycles leading to `marking`
+    pub cycles: Vec<Cycle>,
+    pub interactive: bool,
+    /// Every branch explored, undone cycles included. When empty, `cycles` is the only one.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub branches: Vec<Branch>,
+    /// Branch `cycles` belong to
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub branch: usize,
+}
+
+/// Raised when a marking or a snapshot does not fit the net of a simulation
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// A marking has `found` entries for a net with `expected` places
+    MarkingLength { expected: usize, found: usize },
+    /// The current branch is not one of the `branches` of the snapshot
+    UnknownBranch { branch: usize, branches: usize },
+    /// The cycles leading to the marking are not the start of the current branch
+    CyclesNotInBranch { branch: usize },
+    /// A cycle refers to a place the net does not have
+    UnknownPlace { place: usize, places: usize },
+    /// A cycle refers to a transition the net does not have
+    UnknownTransition {
+        transition: usize,
+        transitions: usize,
+    },
+}
+
+impl std::fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::MarkingLength { expected, found } => write!(
+                f,
+                "marking has {} entries but the net has {} places",
+                found, expected
+            ),
+            SnapshotError::UnknownBranch { branch, branches } => write!(
+                f,
+                "branch {} does not exist, the snapshot has {} branches",
+                branch, branches
+            ),
+            SnapshotError::CyclesNotInBranch { branch } => {
+                write!(f, "cycles do not follow branch {}", branch)
+            }
+            SnapshotError::UnknownPlace { place, places } => {
+                write!(f, "place {} does not exist, the net has {}", place, places)
+            }
+            SnapshotError::UnknownTransition {
+                transition,
+                transitions,
+            } => write!(
+                f,
+                "transition {} does not exist, the net has {}",
+                transition, transitions
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
 impl UITable for Simulation {
     fn header(&self) -> Vec<&str> {
         let mut cols = vec!["Cycle"];
@@ -45,50 +200,238 @@ impl UITable for Simulation {
     }
 
     fn rows(&self) -> Vec<Vec<String>> {
-        self.ui_rows.borrow().clone()
+        let transitions = self.indexed.transition_count();
+
+        self.cycles()
+            .iter()
+            .enumerate()
+            .map(|(index, cycle)| {
+                let mut row = vec![index.to_string()];
+                row.extend(cycle.marking.iter().map(|tokens| tokens.to_string()));
+
+                let mut fired = vec![" ".to_string(); transitions];
+                for transition in cycle.fired.iter() {
+                    fired[*transition] = "X".to_string();
+                }
+                row.append(&mut fired);
+
+                row
+            })
+            .collect()
     }
 }
 
 impl Simulation {
-    /// Create a new `Simulation` with the provided `net`.
-    pub fn new(net: PetriNet) -> Self {
+    /// Create a new `Simulation` with the provided `net`. The net is never modified by the
+    /// simulation, so an `Arc<PetriNet>` can be shared by simulations running on other threads.
+    pub fn new(net: impl Into<Arc<PetriNet>>) -> Self {
+        Self::with_interactive(net.into(), false)
+    }
+
+    /// Create a new interactive `Simulation` with the provided `net`. Running an interactive
+    /// simulation opens a full-screen `Explorer` where the user fires transitions.
+    pub fn new_interactive(net: impl Into<Arc<PetriNet>>) -> Self {
+        Self::with_interactive(net.into(), true)
+    }
+
+    fn with_interactive(net: Arc<PetriNet>, interactive: bool) -> Self {
+        let indexed = IndexedNet::new(&net);
+        let place_index = net
+            .places()
+            .iter()
+            .enumerate()
+            .map(|(index, place)| (*place.id(), index))
+            .collect();
+        let marking = net.initial_marking();
+        let enabled = Self::enabled_flags(&indexed, &marking);
+        let history = History::new(marking.clone());
+
         let mut simul = Self {
             net,
+            indexed,
             incoming_connections: HashMap::new(),
             outgoing_connections: HashMap::new(),
-            concurrent_connections: HashMap::new(),
-            ui_rows: RefCell::new(vec![]),
-            interactive: false,
+            place_index,
+            state: Mutex::new(State { marking, enabled }),
+            history: Mutex::new(history),
+            interactive,
         };
 
         simul.scan_connections();
-        simul.check_concurrency();
 
         simul
     }
 
-    /// Create a new interactive `Simulation` with the provided `net`. An interactive simulation
-    /// requires the user to press *ENTER* to advance cycles.
-    pub fn new_interactive(net: PetriNet) -> Self {
-        let mut simul = Self {
-            net,
-            incoming_connections: HashMap::new(),
-            outgoing_connections: HashMap::new(),
-            concurrent_connections: HashMap::new(),
-            ui_rows: RefCell::new(vec![]),
-            interactive: true,
+    /// Saves the current state of the simulation
+    pub fn snapshot(&self) -> SimulationSnapshot {
+        // Locked in the same order as `step`, so that no cycle is recorded in between
+        let state = self.state.lock().unwrap();
+        let history = self.history.lock().unwrap();
+
+        SimulationSnapshot {
+            marking: state.marking.clone(),
+            cycles: history.branches[history.current].cycles[..history.position].to_vec(),
+            interactive: self.interactive,
+            branches: history.branches.clone(),
+            branch: history.current,
+        }
+    }
+
+    /// Resumes a simulation of `net` from a snapshot taken with `snapshot`, failing if the
+    /// snapshot does not fit the net
+    pub fn restore(
+        net: impl Into<Arc<PetriNet>>,
+        snapshot: SimulationSnapshot,
+    ) -> Result<Self, SnapshotError> {
+        let simul = Self::with_interactive(net.into(), snapshot.interactive);
+        simul.set_marking(snapshot.marking.clone())?;
+
+        let position = snapshot.cycles.len();
+        let branches = if snapshot.branches.is_empty() {
+            vec![Branch {
+                cycles: snapshot.cycles.clone(),
+                marking: snapshot.marking.clone(),
+            }]
+        } else {
+            snapshot.branches
+        };
+        let Some(current) = branches.get(snapshot.branch) else {
+            return Err(SnapshotError::UnknownBranch {
+                branch: snapshot.branch,
+                branches: branches.len(),
+            });
         };
+        if !current.cycles.starts_with(&snapshot.cycles) {
+            return Err(SnapshotError::CyclesNotInBranch {
+                branch: snapshot.branch,
+            });
+        }
+        for branch in branches.iter() {
+            simul.check_marking(&branch.marking)?;
+            for cycle in branch.cycles.iter() {
+                simul.check_cycle(cycle)?;
+            }
+        }
 
-        simul.scan_connections();
-        simul.check_concurrency();
+        *simul.history.lock().unwrap() = History {
+            branches,
+            current: snapshot.branch,
+            position,
+        };
 
-        simul
+        Ok(simul)
+    }
+
+    fn check_marking(&self, marking: &Marking) -> Result<(), SnapshotError> {
+        if marking.len() != self.net.places().len() {
+            return Err(SnapshotError::MarkingLength {
+                expected: self.net.places().len(),
+                found: marking.len(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Whether every index in `cycle` belongs to the net
+    fn check_cycle(&self, cycle: &Cycle) -> Result<(), SnapshotError> {
+        self.check_marking(&cycle.marking)?;
+
+        let (places, transitions) = (self.net.places().len(), self.net.transitions().len());
+        let mut used = cycle.fired.iter().chain(
+            cycle
+                .conflicts
+                .iter()
+                .flat_map(|c| c.candidates.iter().chain([&c.chosen])),
+        );
+        if let Some(transition) = used.find(|t| **t >= transitions) {
+            return Err(SnapshotError::UnknownTransition {
+                transition: *transition,
+                transitions,
+            });
+        }
+        if let Some(conflict) = cycle.conflicts.iter().find(|c| c.place >= places) {
+            return Err(SnapshotError::UnknownPlace {
+                place: conflict.place,
+                places,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Cycles leading to the current marking
+    pub fn cycles(&self) -> Vec<Cycle> {
+        let history = self.history.lock().unwrap();
+
+        history.branches[history.current].cycles[..history.position].to_vec()
+    }
+
+    /// Every branch explored so far, including the cycles undone in each
+    pub fn branches(&self) -> Vec<Branch> {
+        self.history.lock().unwrap().branches.clone()
+    }
+
+    /// Index of the branch being explored in `branches`
+    pub fn branch(&self) -> usize {
+        self.history.lock().unwrap().current
+    }
+
+    /// Number of cycles branches `a` and `b` share before they diverge, or `None` if either of
+    /// them does not exist
+    pub fn common_cycles(&self, a: usize, b: usize) -> Option<usize> {
+        let history = self.history.lock().unwrap();
+        let (a, b) = (
+            &history.branches.get(a)?.cycles,
+            &history.branches.get(b)?.cycles,
+        );
+
+        Some(a.iter().zip(b.iter()).take_while(|(x, y)| x == y).count())
+    }
+
+    /// Cycles executed so far, along with the names needed to export them
+    pub fn trace(&self) -> Trace {
+        Trace::new(&self.net, self.cycles())
     }
 
     pub fn net(&self) -> &PetriNet {
         &self.net
     }
 
+    /// Index-based view of the net used to run the simulation
+    pub fn indexed(&self) -> &IndexedNet {
+        &self.indexed
+    }
+
+    /// Current marking of the net
+    pub fn marking(&self) -> Marking {
+        self.state.lock().unwrap().marking.clone()
+    }
+
+    /// Replaces the current marking, e.g. to resume from a previously saved state. The marking
+    /// must have one entry per place of the net.
+    pub fn set_marking(&self, marking: Marking) -> Result<(), SnapshotError> {
+        self.check_marking(&marking)?;
+
+        let enabled = Self::enabled_flags(&self.indexed, &marking);
+        *self.state.lock().unwrap() = State { marking, enabled };
+
+        Ok(())
+    }
+
+    /// Current tokens of the place identified by `id`
+    pub fn tokens(&self, id: PlaceId) -> Option<i32> {
+        let index = self.place_index.get(id.uuid())?;
+
+        Some(self.state.lock().unwrap().marking[*index])
+    }
+
+    fn enabled_flags(indexed: &IndexedNet, marking: &Marking) -> Vec<bool> {
+        (0..indexed.transition_count())
+            .map(|transition| indexed.is_enabled(transition, marking))
+            .collect()
+    }
+
     /// Maps incoming and outpoing places for all transitions
     fn scan_connections(&mut self) {
         // Initialize keys
@@ -99,224 +442,246 @@ impl Simulation {
 
         for connection in self.net.connections().iter() {
             match connection.input_from() {
-                super::InputFrom::PLACE => self
+                InputFrom::PLACE => self
                     .incoming_connections
                     .get_mut(&connection.transition())
                     .expect("Incoming connections map was not properly initialized")
-                    .push((connection.place().clone(), connection.clone())),
-                super::InputFrom::TRANSITION => self
+                    .push((connection.place(), connection.clone())),
+                InputFrom::TRANSITION => self
                     .outgoing_connections
                     .get_mut(&connection.transition())
                     .expect("Outgoing connections map was not properly initialized")
-                    .push((connection.place().clone(), connection.clone())),
+                    .push((connection.place(), connection.clone())),
             }
         }
     }
 
-    fn check_concurrency(&mut self) {
-        // Initialize keys
-        for place in self.net.places().iter() {
-            self.concurrent_connections.insert(place.clone(), vec![]);
+    /// Chooses which of the enabled transitions fire in this cycle. Transitions sharing an input
+    /// place are in conflict, and only the one picked by `choose` can fire. Returns `None` as soon
+    /// as `choose` does, or picks a transition that is not one of the candidates.
+    fn resolve_conflicts(
+        &self,
+        marking: &Marking,
+        enabled: &[usize],
+        choose: &mut impl FnMut(&Conflict) -> Option<usize>,
+    ) -> Option<(Vec<usize>, Vec<ConflictChoice>)> {
+        let mut firing = vec![false; self.indexed.transition_count()];
+        for transition in enabled.iter() {
+            firing[*transition] = true;
         }
 
-        // For each connection, if input is from place, add to corresponding vector
-        for connection in self.net.connections().iter() {
-            if connection.input_from() == super::InputFrom::PLACE {
-                self.concurrent_connections
-                    .get_mut(&connection.place())
-                    .expect("Concurrent transactions map was not properly initialized")
-                    .push(connection.clone());
-            }
-        }
+        let mut contested: Vec<usize> = enabled
+            .iter()
+            .flat_map(|transition| self.indexed.pre(*transition).iter())
+            .map(|con| con.place)
+            .filter(|place| self.indexed.consumers(*place).len() > 1)
+            .collect();
+        contested.sort_unstable();
+        contested.dedup();
+
+        let mut conflicts = vec![];
+
+        for place in contested.into_iter() {
+            let candidates: Vec<usize> = self
+                .indexed
+                .consumers(place)
+                .iter()
+                .copied()
+                .filter(|transition| firing[*transition])
+                .collect();
+
+            if candidates.len() > 1 {
+                let conflict = Conflict {
+                    place,
+                    tokens: marking[place],
+                    candidates,
+                };
+                let chosen = choose(&conflict).filter(|t| conflict.candidates.contains(t))?;
+                let candidates = conflict.candidates;
+                for transition in candidates.iter() {
+                    firing[*transition] = *transition == chosen;
+                }
 
-        // Iterate through all map keys. If vector contains only one entry,
-        // there is no concurrency and key is dropped
-        let mut drop_keys = vec![];
-        for (key, value) in self.concurrent_connections.iter() {
-            if value.len() <= 1 {
-                drop_keys.push(key.clone());
+                conflicts.push(ConflictChoice {
+                    place,
+                    candidates,
+                    chosen,
+                });
             }
         }
 
-        drop_keys.into_iter().for_each(|key| {
-            self.concurrent_connections.remove(&key);
-        });
+        let fired = enabled
+            .iter()
+            .copied()
+            .filter(|transition| firing[*transition])
+            .collect();
+
+        Some((fired, conflicts))
     }
 
-    // Check whether the transition is enabled
-    fn transition_enabled(&self, transition: &Transition) -> bool {
-        if let Some(vals) = self.incoming_connections.get(transition) {
-            for (place, connection) in vals.iter() {
-                let tokens = place.tokens();
+    /// Fires `transitions` and updates the enabledness of their neighbours only
+    fn fire(&self, state: &mut State, transitions: &[usize]) {
+        for transition in transitions.iter() {
+            let tr = &self.net.transitions()[*transition];
+            if let Some(callback) = tr.callback() {
+                callback(tr, &self.incoming_connections, &self.outgoing_connections);
+            }
+
+            self.indexed.fire(*transition, &mut state.marking);
+        }
 
-                let enabled = match connection.connection_type() {
-                    super::ConnectionType::NORMAL => connection.weight() <= &tokens,
-                    super::ConnectionType::INHIBITOR => connection.weight() > &tokens,
-                    super::ConnectionType::RESET => true,
-                };
+        let mut affected: Vec<usize> = transitions
+            .iter()
+            .flat_map(|transition| self.indexed.neighbours(*transition).iter().copied())
+            .collect();
+        affected.sort_unstable();
+        affected.dedup();
 
-                if !enabled {
-                    return false;
-                }
-            }
+        for transition in affected.into_iter() {
+            state.enabled[transition] = self.indexed.is_enabled(transition, &state.marking);
         }
+    }
 
-        true
+    /// Transitions enabled in the current marking
+    pub fn enabled(&self) -> Vec<usize> {
+        let state = self.state.lock().unwrap();
+
+        (0..state.enabled.len())
+            .filter(|transition| state.enabled[*transition])
+            .collect()
     }
 
-    /// Consume tokens from incoming places
-    fn consume_tokens(&self, transition: &Transition, incoming_connections: &ConnectionMap) {
-        if let Some(vals) = incoming_connections.get(transition) {
-            for (place, connection) in vals.iter() {
-                match connection.connection_type() {
-                    super::ConnectionType::NORMAL => {
-                        place.remove_tokens(connection.weight().clone())
-                    }
-                    super::ConnectionType::INHIBITOR => {}
-                    super::ConnectionType::RESET => place.clear_tokens(),
-                }
-            }
-        }
+    /// Runs one cycle, firing every enabled transition that wins its conflicts, and returns
+    /// whether any transition fired. The cycle is recorded even when none did.
+    pub fn step(&self) -> bool {
+        let mut rng = rand::thread_rng();
+
+        self.step_with(|conflict| conflict.candidates.choose(&mut rng).copied())
+            .unwrap_or(false)
     }
 
-    /// Propagate tokens to outgoing places
-    fn propagate_tokens(&self, transition: &Transition) {
-        if let Some(vals) = self.outgoing_connections.get(transition) {
-            for (place, connection) in vals.iter() {
-                place.add_tokens(connection.weight().clone());
-            }
-        }
+    /// Runs one cycle like `step`, letting `choose` pick which candidate wins every conflict.
+    /// Gives up on the cycle, without firing or recording anything, as soon as `choose` returns
+    /// `None` or a transition that is not one of the candidates.
+    pub fn step_with(&self, mut choose: impl FnMut(&Conflict) -> Option<usize>) -> Option<bool> {
+        let mut state = self.state.lock().unwrap();
+
+        let enabled: Vec<usize> = (0..state.enabled.len())
+            .filter(|transition| state.enabled[*transition])
+            .collect();
+
+        let (fired, conflicts) = self.resolve_conflicts(&state.marking, &enabled, &mut choose)?;
+        let marking = state.marking.clone();
+
+        self.fire(&mut state, &fired);
+
+        let some_transition_fired = !fired.is_empty();
+        self.history.lock().unwrap().record(
+            Cycle {
+                marking,
+                fired,
+                conflicts,
+            },
+            state.marking.clone(),
+        );
+
+        Some(some_transition_fired)
     }
 
-    /// Prints interactive prompt and returns true when the user wants to cancel the execution,
-    /// otherwise returns false.
-    fn interactive_print(&self) -> bool {
-        let should_cancel;
-
-        self.print_table();
-
-        execute!(
-            stdout(),
-            Print("Press "),
-            SetForegroundColor(Color::Magenta),
-            Print("ENTER"),
-            ResetColor,
-            Print(" to execute the next step, or "),
-            SetForegroundColor(Color::Red),
-            Print("c"),
-            ResetColor,
-            Print(" to cancel the simulation.\n"),
-        )
-        .expect("Failed to setup interactive session");
-
-        enable_raw_mode().expect("Failed to setup interactive session");
-
-        loop {
-            match read().expect("Failed to setup interactive session") {
-                Event::Key(event) => match event.code {
-                    KeyCode::Enter => {
-                        should_cancel = false;
-                        break;
-                    }
-                    KeyCode::Char('c') => {
-                        should_cancel = true;
-                        break;
-                    }
-                    _ => {}
-                },
-                _ => {}
-            }
+    /// Runs a cycle firing only `transition`, if it is enabled, and returns whether it fired
+    pub fn fire_transition(&self, transition: usize) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if !state.enabled.get(transition).copied().unwrap_or(false) {
+            return false;
         }
 
-        disable_raw_mode().expect("Failed to setup interactive session");
+        let marking = state.marking.clone();
+        self.fire(&mut state, &[transition]);
+
+        self.history.lock().unwrap().record(
+            Cycle {
+                marking,
+                fired: vec![transition],
+                conflicts: vec![],
+            },
+            state.marking.clone(),
+        );
 
-        should_cancel
+        true
     }
 
-    /// Execute the simulation
-    pub fn run(&self) {
-        let mut some_transition_enabled = true;
-        let mut cycle_count = -1;
+    /// Goes back to the marking the last cycle started from, and returns that cycle. It stays in
+    /// the branch, to be redone. Side effects of transition callbacks are not undone.
+    pub fn undo(&self) -> Option<Cycle> {
+        let mut undone = None;
+        self.travel(|history| {
+            let position = history.position.checked_sub(1)?;
+            undone = Some(history.branches[history.current].cycles[position].clone());
+            Some((history.current, position))
+        });
 
-        while some_transition_enabled {
-            cycle_count += 1;
-            some_transition_enabled = false;
+        undone
+    }
 
-            // Add current places' marks to table
-            let mut places_marks = vec![];
-            for places in self.net.places() {
-                places_marks.push(places.tokens().to_string());
-            }
+    /// Restores the marking after the next undone cycle of the branch, and returns that cycle.
+    /// Transition callbacks are not called again.
+    pub fn redo(&self) -> Option<Cycle> {
+        let mut redone = None;
+        self.travel(|history| {
+            let cycle = history.branches[history.current]
+                .cycles
+                .get(history.position)?;
+            redone = Some(cycle.clone());
+            Some((history.current, history.position + 1))
+        });
 
-            // Scan transitions and check if enabled
-            let mut enabled_transitions = vec![];
-            for transition in self.net.transitions() {
-                if self.transition_enabled(transition.as_ref()) {
-                    enabled_transitions.push(transition.clone());
-                }
-            }
+        redone
+    }
 
-            // Choose path for concurrent connections
-            let mut rng = rand::thread_rng();
-            let mut unconcurrent_incoming_connections = self.incoming_connections.clone();
-            for (_, connections) in self.concurrent_connections.iter() {
-                if enabled_transitions.len() > 1 {
-                    let disable_cons = connections
-                        .into_iter()
-                        .filter(|con| enabled_transitions.contains(&con.transition()))
-                        .choose_multiple(&mut rng, connections.len() - 1);
-
-                    for connection in disable_cons.iter() {
-                        unconcurrent_incoming_connections
-                            .get_mut(&connection.transition())
-                            .expect("Incoming connections map was not properly initialized")
-                            .retain(|(_, map_connection)| {
-                                map_connection.as_ref() != connection.as_ref()
-                            });
-
-                        enabled_transitions
-                            .retain(|tr| tr.id() != connection.transition().as_ref().id());
-                    }
-                }
-            }
+    /// Goes back or forward to the marking `cycle` starts from in the current branch, or to the
+    /// end of the branch when `cycle` is its length. Returns whether the branch has that cycle.
+    pub fn jump_to(&self, cycle: usize) -> bool {
+        self.travel(|history| {
+            (cycle <= history.branches[history.current].cycles.len())
+                .then_some((history.current, cycle))
+        })
+    }
 
-            // Run cycle
-            for transition in self.net.transitions().iter() {
-                if enabled_transitions.contains(transition) {
-                    some_transition_enabled = true;
-                    if let Some(callback) = transition.callback() {
-                        callback(
-                            &transition,
-                            &unconcurrent_incoming_connections,
-                            &self.outgoing_connections,
-                        );
-                    }
-
-                    self.consume_tokens(transition.as_ref(), &unconcurrent_incoming_connections);
-                    self.propagate_tokens(transition.as_ref());
-                }
-            }
+    /// Continues from the end of another branch. Returns whether `branch` exists.
+    pub fn switch_branch(&self, branch: usize) -> bool {
+        self.travel(|history| {
+            let length = history.branches.get(branch)?.cycles.len();
+            Some((branch, length))
+        })
+    }
 
-            // Insert cycle status into table
-            let mut table_row = vec![cycle_count.to_string()];
-            table_row.append(&mut places_marks);
-            table_row.append(&mut Vec::from_iter(self.net.transitions().into_iter().map(
-                |tr| {
-                    if enabled_transitions.contains(tr) {
-                        "X".to_string()
-                    } else {
-                        " ".to_string()
-                    }
-                },
-            )));
-            self.ui_rows.borrow_mut().push(table_row.clone());
-
-            // Interactive session
-            if self.interactive {
-                if self.interactive_print() {
-                    return;
-                }
-            }
+    /// Moves to the branch and position chosen by `target`, if any, restoring the marking there
+    fn travel(&self, target: impl FnOnce(&History) -> Option<(usize, usize)>) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let mut history = self.history.lock().unwrap();
+        let Some((branch, position)) = target(&history) else {
+            return false;
+        };
+
+        history.current = branch;
+        history.position = position;
+        let marking = history.marking(branch, position);
+        let enabled = Self::enabled_flags(&self.indexed, &marking);
+        *state = State { marking, enabled };
+
+        true
+    }
+
+    /// Execute the simulation. An interactive simulation opens an `Explorer` instead, where the
+    /// user decides what fires.
+    pub fn run(&self) {
+        if self.interactive {
+            Explorer::new(self)
+                .run()
+                .expect("Failed to run interactive session");
+            return;
         }
+
+        while self.step() {}
     }
 }
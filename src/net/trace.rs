@@ -0,0 +1,170 @@
+use std::io::{self, Write};
+
+use super::{Cycle, PetriNet};
+
+/// Structured record of the cycles of a `Simulation`, exportable as JSON Lines or CSV.
+///
+/// Cycles refer to places and transitions by index; the trace keeps their names so that exports
+/// can be read without the net.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Trace {
+    pub places: Vec<String>,
+    pub transitions: Vec<String>,
+    pub cycles: Vec<Cycle>,
+}
+
+impl Trace {
+    pub fn new(net: &PetriNet, cycles: Vec<Cycle>) -> Self {
+        Self {
+            places: net.places().iter().map(|p| p.name().to_string()).collect(),
+            transitions: net
+                .transitions()
+                .iter()
+                .map(|t| t.name().to_string())
+                .collect(),
+            cycles,
+        }
+    }
+
+    /// Writes one JSON object per cycle, e.g.
+    ///
+    /// ```text
+    /// {"cycle":0,"marking":{"P1":1,"P2":0},"fired":["T1"],"conflicts":[{"place":"P1","candidates":["T1","T2"],"chosen":"T1"}]}
+    /// ```
+    pub fn write_json_lines(&self, mut writer: impl Write) -> io::Result<()> {
+        for (index, cycle) in self.cycles.iter().enumerate() {
+            let marking: Vec<String> = self
+                .places
+                .iter()
+                .zip(cycle.marking.iter())
+                .map(|(place, tokens)| format!("{}:{}", json_string(place), tokens))
+                .collect();
+
+            let conflicts: Vec<String> = cycle
+                .conflicts
+                .iter()
+                .map(|conflict| {
+                    format!(
+                        "{{\"place\":{},\"candidates\":{},\"chosen\":{}}}",
+                        json_string(&self.places[conflict.place]),
+                        self.json_transitions(&conflict.candidates),
+                        json_string(&self.transitions[conflict.chosen])
+                    )
+                })
+                .collect();
+
+            writeln!(
+                writer,
+                "{{\"cycle\":{},\"marking\":{{{}}},\"fired\":{},\"conflicts\":[{}]}}",
+                index,
+                marking.join(","),
+                self.json_transitions(&cycle.fired),
+                conflicts.join(",")
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes a header and one row per cycle. There is a column with the tokens of each place,
+    /// a column per transition holding 1 if it fired, and a `conflicts` column listing the
+    /// resolved conflicts as `place:candidate|candidate->chosen`, separated by `;`.
+    pub fn write_csv(&self, mut writer: impl Write) -> io::Result<()> {
+        let mut header = vec!["cycle".to_string()];
+        header.extend(self.places.iter().map(|name| csv_field(name)));
+        header.extend(self.transitions.iter().map(|name| csv_field(name)));
+        header.push("conflicts".to_string());
+        writeln!(writer, "{}", header.join(","))?;
+
+        for (index, cycle) in self.cycles.iter().enumerate() {
+            let mut row = vec![index.to_string()];
+            row.extend(cycle.marking.iter().map(|tokens| tokens.to_string()));
+
+            let mut fired = vec!["0".to_string(); self.transitions.len()];
+            for transition in cycle.fired.iter() {
+                fired[*transition] = "1".to_string();
+            }
+            row.append(&mut fired);
+
+            let conflicts: Vec<String> = cycle
+                .conflicts
+                .iter()
+                .map(|conflict| {
+                    let candidates: Vec<&str> = conflict
+                        .candidates
+                        .iter()
+                        .map(|t| self.transitions[*t].as_str())
+                        .collect();
+
+                    format!(
+                        "{}:{}->{}",
+                        self.places[conflict.place],
+                        candidates.join("|"),
+                        self.transitions[conflict.chosen]
+                    )
+                })
+                .collect();
+            row.push(csv_field(&conflicts.join(";")));
+
+            writeln!(writer, "{}", row.join(","))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn to_json_lines(&self) -> String {
+        let mut buffer = vec![];
+        self.write_json_lines(&mut buffer)
+            .expect("Writing to a Vec cannot fail");
+
+        String::from_utf8(buffer).expect("Trace is valid UTF-8")
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut buffer = vec![];
+        self.write_csv(&mut buffer)
+            .expect("Writing to a Vec cannot fail");
+
+        String::from_utf8(buffer).expect("Trace is valid UTF-8")
+    }
+
+    fn json_transitions(&self, transitions: &[usize]) -> String {
+        let names: Vec<String> = transitions
+            .iter()
+            .map(|t| json_string(&self.transitions[*t]))
+            .collect();
+
+        format!("[{}]", names.join(","))
+    }
+}
+
+/// Quotes and escapes `value` as a JSON string
+fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped.push('"');
+    escaped
+}
+
+/// Quotes `value` if it contains characters with a meaning in CSV
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
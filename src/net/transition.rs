@@ -1,23 +1,27 @@
 use derivative::Derivative;
 use uuid::Uuid;
 
-use std::rc::Rc;
+use std::sync::Arc;
 
-use super::{Connectable, ConnectionMap};
+use super::{Connectable, ConnectionMap, TransitionId};
 use crate::ui::UITable;
 
 // Callbacks for Transition receive a reference for the `Transition`, as well as references to the
-// `HashMap` expressing the net graph.
-type TransitionCallback = Box<dyn Fn(&Transition, &ConnectionMap, &ConnectionMap)>;
+// `HashMap` expressing the net graph. They are shared so that an edited copy of a transition keeps
+// its callback, and must be `Send + Sync` so that nets can be used from several threads.
+pub type TransitionCallback =
+    Arc<dyn Fn(&Transition, &ConnectionMap, &ConnectionMap) + Send + Sync>;
 
 #[derive(Derivative)]
 #[derivative(Debug, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Transition {
     id: Uuid,
     name: String,
 
     #[derivative(Hash = "ignore")]
     #[derivative(Debug = "ignore")]
+    #[cfg_attr(feature = "serde", serde(skip))]
     callback: Option<TransitionCallback>,
 }
 
@@ -33,7 +37,7 @@ impl PartialEq for Transition {
     }
 }
 
-impl UITable for Vec<Rc<Transition>> {
+impl UITable for Vec<Arc<Transition>> {
     fn header(&self) -> Vec<&str> {
         vec!["Name"]
     }
@@ -52,8 +56,13 @@ impl UITable for Vec<Rc<Transition>> {
 impl Transition {
     /// Creates a new Transition with given name
     pub fn new(name: &str) -> Self {
+        Self::with_id(TransitionId(Uuid::new_v4()), name)
+    }
+
+    /// Creates a new Transition with a known handle
+    pub(crate) fn with_id(id: TransitionId, name: &str) -> Self {
         Self {
-            id: Uuid::new_v4(),
+            id: id.0,
             name: name.into(),
             callback: None,
         }
@@ -71,6 +80,15 @@ impl Transition {
         self.callback.as_ref()
     }
 
+    /// Returns a copy of the transition with the same ID and callback but a different name
+    pub(crate) fn renamed(&self, name: &str) -> Self {
+        Self {
+            id: self.id,
+            name: name.into(),
+            callback: self.callback.clone(),
+        }
+    }
+
     pub fn add_callback(&mut self, callback: TransitionCallback) -> Option<TransitionCallback> {
         self.callback.replace(callback)
     }
@@ -0,0 +1,475 @@
+use std::io::{stdout, Write};
+use std::time::Duration;
+
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{poll, read, Event, KeyCode, KeyEventKind},
+    execute, queue,
+    style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor},
+    terminal::{
+        disable_raw_mode, enable_raw_mode, size, Clear, ClearType, EnterAlternateScreen,
+        LeaveAlternateScreen,
+    },
+};
+
+use rand::prelude::*;
+
+use crate::net::{Conflict, Simulation};
+
+/// Delay between two cycles while auto-running
+const TICK: Duration = Duration::from_millis(250);
+
+const HELP: &str = "↑/↓ select  ENTER fire  SPACE step  u/r undo/redo  HOME/END jump  b branch  \
+                    a auto-run  PgUp/PgDn history  q quit";
+
+const CONFLICT_HELP: &str = "↑/↓ select  ENTER let it win  r random  ESC cancel the cycle  q quit";
+
+/// Raw mode and the alternate screen, left when dropped
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn new() -> std::io::Result<Self> {
+        enable_raw_mode()?;
+        // From here on, dropping the guard disables raw mode even if the screen cannot be set up
+        let guard = TerminalGuard;
+        execute!(stdout(), EnterAlternateScreen, Hide)?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        // Nothing can be reported from here, and every step must be tried whatever fails
+        let _ = execute!(stdout(), Show, LeaveAlternateScreen);
+        let _ = disable_raw_mode();
+    }
+}
+
+/// Full-screen terminal explorer driving a `Simulation`.
+///
+/// The screen shows the current marking, the enabled transitions, one of which is selected, and
+/// the history of cycles. The user fires the selected transition alone with *ENTER*, runs a
+/// whole cycle with *SPACE*, undoes the last cycle with *u* and lets cycles run on their own with
+/// *a*, until the net deadlocks or *a* is pressed again.
+///
+/// Undone cycles stay in the history, greyed out, to be redone with *r*. *HOME* and *END* jump to
+/// the start and the end of the branch, and *b* switches to the next branch: running a cycle
+/// after going back starts a new one.
+///
+/// A cycle run with *SPACE* pauses on every conflict, showing the tokens on the contested place,
+/// until the user picks the transition that wins it. Auto-run lets chance decide.
+pub struct Explorer<'a> {
+    simulation: &'a Simulation,
+    selected: usize, // Position in the list of enabled transitions
+    scroll: usize,   // Most recent cycles hidden below the history panel
+    auto_run: bool,
+    quit: bool,
+    pending: Option<PendingConflict>,
+}
+
+/// A conflict of the cycle being run, waiting for the user
+struct PendingConflict {
+    conflict: Conflict,
+    /// Winners of the conflicts resolved before it in the same cycle
+    decided: Vec<usize>,
+    selected: usize, // Position in the candidates
+}
+
+impl<'a> Explorer<'a> {
+    pub fn new(simulation: &'a Simulation) -> Self {
+        Self {
+            simulation,
+            selected: 0,
+            scroll: 0,
+            auto_run: false,
+            quit: false,
+            pending: None,
+        }
+    }
+
+    /// Takes over the terminal until the user quits. The terminal is given back as it was on
+    /// every way out, errors and panics included.
+    pub fn run(&mut self) -> std::io::Result<()> {
+        let _terminal = TerminalGuard::new()?;
+        self.event_loop(&mut stdout())
+    }
+
+    /// Applies a key press, as `run` does for every key the user presses
+    pub fn handle_key(&mut self, code: KeyCode) {
+        if self.pending.is_some() {
+            self.handle_conflict_key(code);
+            return;
+        }
+
+        let enabled = self.simulation.enabled();
+
+        match code {
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.selected += 1,
+            KeyCode::Enter => {
+                if let Some(transition) = enabled.get(self.selected) {
+                    self.simulation.fire_transition(*transition);
+                    self.scroll = 0;
+                }
+            }
+            KeyCode::Char(' ') if !enabled.is_empty() => self.step(vec![]),
+            KeyCode::Char('u') | KeyCode::Backspace => {
+                self.simulation.undo();
+                self.auto_run = false;
+            }
+            KeyCode::Char('r') => {
+                self.simulation.redo();
+            }
+            KeyCode::Home => {
+                self.simulation.jump_to(0);
+                self.auto_run = false;
+            }
+            KeyCode::End => {
+                let branch = &self.simulation.branches()[self.simulation.branch()];
+                self.simulation.jump_to(branch.cycles.len());
+            }
+            KeyCode::Char('b') => {
+                let next = (self.simulation.branch() + 1) % self.simulation.branches().len();
+                self.simulation.switch_branch(next);
+                self.auto_run = false;
+                self.scroll = 0;
+            }
+            KeyCode::Char('a') => self.auto_run = !self.auto_run,
+            KeyCode::PageUp => self.scroll += self.page(),
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_sub(self.page()),
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            _ => {}
+        }
+
+        self.clamp();
+    }
+
+    /// The selected transition, if any is enabled
+    pub fn selected(&self) -> Option<usize> {
+        self.simulation.enabled().get(self.selected).copied()
+    }
+
+    pub fn is_auto_running(&self) -> bool {
+        self.auto_run
+    }
+
+    /// The conflict the user has to resolve before the cycle goes on, if any
+    pub fn conflict(&self) -> Option<&Conflict> {
+        self.pending.as_ref().map(|pending| &pending.conflict)
+    }
+
+    fn handle_conflict_key(&mut self, code: KeyCode) {
+        let Some(pending) = self.pending.as_mut() else {
+            return;
+        };
+        let candidates = pending.conflict.candidates.len();
+        // There is nothing to select or pick in a conflict without candidates
+        if candidates == 0 {
+            self.pending = None;
+            return;
+        }
+
+        match code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                pending.selected = pending.selected.saturating_sub(1)
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                pending.selected = (pending.selected + 1).min(candidates - 1)
+            }
+            KeyCode::Enter | KeyCode::Char('r') => {
+                let Some(mut pending) = self.pending.take() else {
+                    return;
+                };
+                let winner = if code == KeyCode::Enter {
+                    pending.conflict.candidates.get(pending.selected)
+                } else {
+                    pending.conflict.candidates.choose(&mut thread_rng())
+                };
+                let Some(winner) = winner.copied() else {
+                    return;
+                };
+                pending.decided.push(winner);
+                self.step(pending.decided);
+            }
+            KeyCode::Esc => self.pending = None,
+            KeyCode::Char('q') => self.quit = true,
+            _ => {}
+        }
+
+        self.clamp();
+    }
+
+    /// Runs a cycle with the winners of its first conflicts already `decided`, stopping at the
+    /// next conflict to ask the user
+    fn step(&mut self, decided: Vec<usize>) {
+        let mut winners = decided.iter();
+        let mut undecided = None;
+        let outcome = self.simulation.step_with(|conflict| match winners.next() {
+            Some(winner) => Some(*winner),
+            None => {
+                undecided = Some(conflict.clone());
+                None
+            }
+        });
+
+        match (outcome, undecided) {
+            (Some(_), _) => self.scroll = 0,
+            (None, Some(conflict)) => {
+                self.pending = Some(PendingConflict {
+                    conflict,
+                    decided,
+                    selected: 0,
+                })
+            }
+            (None, None) => {}
+        }
+    }
+
+    fn event_loop(&mut self, out: &mut impl Write) -> std::io::Result<()> {
+        while !self.quit {
+            self.draw(out)?;
+
+            if self.auto_run && self.pending.is_none() && !poll(TICK)? {
+                self.tick();
+                continue;
+            }
+
+            if let Event::Key(event) = read()? {
+                if event.kind != KeyEventKind::Release {
+                    self.handle_key(event.code);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Runs one cycle while auto-running, stopping on a deadlock
+    fn tick(&mut self) {
+        if self.simulation.enabled().is_empty() {
+            self.auto_run = false;
+        } else {
+            self.simulation.step();
+            self.scroll = 0;
+        }
+        self.clamp();
+    }
+
+    /// Keeps the selection and the history scroll within bounds
+    fn clamp(&mut self) {
+        let enabled = self.simulation.enabled().len();
+        self.selected = self.selected.min(enabled.saturating_sub(1));
+
+        let cycles = self.simulation.branches()[self.simulation.branch()]
+            .cycles
+            .len();
+        self.scroll = self.scroll.min(cycles.saturating_sub(1));
+    }
+
+    /// Rows of the panels
+    fn page(&self) -> usize {
+        let (_, height) = size().unwrap_or((80, 24));
+        (height as usize).saturating_sub(4).max(1)
+    }
+
+    fn draw(&self, out: &mut impl Write) -> std::io::Result<()> {
+        let (width, height) = size()?;
+        let (width, height) = (width as usize, height as usize);
+        let net = self.simulation.net();
+        let marking = self.simulation.marking();
+        let enabled = self.simulation.enabled();
+        let branches = self.simulation.branches();
+        let branch = self.simulation.branch();
+        let position = self.simulation.cycles().len();
+        let cycles = &branches[branch].cycles;
+
+        queue!(out, Clear(ClearType::All), MoveTo(0, 0))?;
+
+        let status = if self.pending.is_some() {
+            "conflict"
+        } else if self.auto_run {
+            "auto-running"
+        } else if enabled.is_empty() {
+            "deadlock"
+        } else {
+            "paused"
+        };
+        queue!(
+            out,
+            SetAttribute(Attribute::Bold),
+            Print(fit(
+                &format!(
+                    "Cycle {}/{} of branch {}/{} - {}",
+                    position,
+                    cycles.len(),
+                    branch + 1,
+                    branches.len(),
+                    status
+                ),
+                width
+            )),
+            SetAttribute(Attribute::Reset),
+        )?;
+
+        // Marking, enabled transitions and history side by side
+        let columns = [0, width / 4, width / 2];
+        let widths = [width / 4, width / 4, width - width / 2];
+        let rows = height.saturating_sub(4);
+        let titles = match self.pending {
+            Some(_) => ["Marking", "Conflict", "History"],
+            None => ["Marking", "Enabled", "History"],
+        };
+        for (column, title) in titles.iter().enumerate() {
+            queue!(
+                out,
+                MoveTo(columns[column] as u16, 1),
+                SetForegroundColor(if column == 1 && self.pending.is_some() {
+                    Color::Red
+                } else {
+                    Color::Magenta
+                }),
+                Print(fit(title, widths[column])),
+                ResetColor,
+            )?;
+        }
+
+        for (row, (place, tokens)) in net.places().iter().zip(marking.iter()).enumerate() {
+            if row >= rows {
+                break;
+            }
+            let name_width = widths[0].saturating_sub(7);
+            queue!(
+                out,
+                MoveTo(0, 2 + row as u16),
+                Print(fit(
+                    &format!("{} {:>5}", fit(place.name(), name_width), tokens),
+                    widths[0]
+                )),
+            )?;
+        }
+
+        // The contested place and the candidates with what they take from it, or the enabled
+        // transitions
+        let (list, selected, first_row) = match &self.pending {
+            Some(pending) => {
+                let place = pending.conflict.place;
+                queue!(
+                    out,
+                    MoveTo(columns[1] as u16, 2),
+                    Print(fit(
+                        &format!(
+                            "{} holds {}",
+                            net.places()[place].name(),
+                            pending.conflict.tokens
+                        ),
+                        widths[1].saturating_sub(1)
+                    )),
+                )?;
+                let list: Vec<String> = pending
+                    .conflict
+                    .candidates
+                    .iter()
+                    .map(|transition| {
+                        let weight = self
+                            .simulation
+                            .indexed()
+                            .pre(*transition)
+                            .iter()
+                            .find(|con| con.place == place)
+                            .map_or(0, |con| con.weight);
+                        format!("{} takes {}", net.transitions()[*transition].name(), weight)
+                    })
+                    .collect();
+                (list, pending.selected, 3)
+            }
+            None => (
+                enabled
+                    .iter()
+                    .map(|transition| net.transitions()[*transition].name().to_string())
+                    .collect(),
+                self.selected,
+                2,
+            ),
+        };
+        let rows_left = height.saturating_sub(2 + first_row);
+        let first = (selected + 1).saturating_sub(rows_left);
+        for (row, name) in list.iter().enumerate().skip(first).take(rows_left) {
+            queue!(
+                out,
+                MoveTo(columns[1] as u16, (first_row + row - first) as u16)
+            )?;
+            if row == selected {
+                queue!(
+                    out,
+                    SetAttribute(Attribute::Reverse),
+                    Print(fit(&format!("> {name}"), widths[1].saturating_sub(1))),
+                    SetAttribute(Attribute::Reset),
+                )?;
+            } else {
+                queue!(
+                    out,
+                    Print(fit(&format!("  {name}"), widths[1].saturating_sub(1)))
+                )?;
+            }
+        }
+
+        let end = cycles.len().saturating_sub(self.scroll);
+        let start = end.saturating_sub(rows);
+        for (row, cycle) in cycles[start..end].iter().enumerate() {
+            let fired: Vec<&str> = cycle
+                .fired
+                .iter()
+                .map(|transition| net.transitions()[*transition].name())
+                .collect();
+            let mut line = format!(
+                "{:>4}  {}",
+                start + row,
+                if fired.is_empty() {
+                    "-".to_string()
+                } else {
+                    fired.join(", ")
+                }
+            );
+            for conflict in cycle.conflicts.iter() {
+                line.push_str(&format!(
+                    " ({} won {})",
+                    net.transitions()[conflict.chosen].name(),
+                    net.places()[conflict.place].name()
+                ));
+            }
+            queue!(
+                out,
+                MoveTo(columns[2] as u16, 2 + row as u16),
+                SetForegroundColor(if start + row < position {
+                    Color::Reset
+                } else {
+                    Color::DarkGrey
+                }),
+                Print(fit(&line, widths[2])),
+                ResetColor,
+            )?;
+        }
+
+        queue!(
+            out,
+            MoveTo(0, height.saturating_sub(1) as u16),
+            SetForegroundColor(Color::DarkGrey),
+            Print(fit(
+                if self.pending.is_some() {
+                    CONFLICT_HELP
+                } else {
+                    HELP
+                },
+                width
+            )),
+            ResetColor,
+        )?;
+
+        out.flush()
+    }
+}
+
+/// `text` cut or padded to exactly `width` characters
+fn fit(text: &str, width: usize) -> String {
+    format!("{:<width$}", text.chars().take(width).collect::<String>())
+}
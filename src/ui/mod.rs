@@ -1,6 +1,10 @@
 use comfy_table::presets::UTF8_FULL;
 use comfy_table::*;
 
+pub mod explorer;
+
+pub use explorer::Explorer;
+
 pub trait UITable {
     /// Table header
     fn header(&self) -> Vec<&str>;